@@ -0,0 +1,26 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::Any;
+
+/// Type map holding the values attached to a generated wrapper with the
+/// `attachments` option, at most one per type.
+#[derive(Default)]
+pub struct Attachments(Vec<Box<dyn Any + Send + Sync>>);
+
+impl Attachments {
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Stores `value`, replacing an earlier value of the same type.
+    pub fn insert<A: Any + Send + Sync>(&mut self, value: A) {
+        match self.0.iter_mut().find(|slot| slot.is::<A>()) {
+            Some(slot) => *slot = Box::new(value),
+            None => self.0.push(Box::new(value)),
+        }
+    }
+
+    pub fn get<A: Any + Send + Sync>(&self) -> Option<&A> {
+        self.0.iter().find_map(|slot| slot.downcast_ref::<A>())
+    }
+}
@@ -0,0 +1,1169 @@
+//! Context message buffers used by the code generated with `#[resext]`.
+
+use core::fmt::{self, Display, Formatter, Write};
+
+#[cfg(feature = "diagnostics")]
+use crate::ErrorStats;
+use crate::formatter::{ContextFrames, Contexts, FrameEnds};
+
+/// Storage for the context messages of a generated wrapper.
+///
+/// Generated code calls into this trait instead of expanding buffer handling
+/// per enum. `#[resext]` uses a fixed-size inline buffer by default (or one
+/// that spills to the heap with `alloc = true`), the `storage` attribute
+/// option plugs in any other implementation, e.g. `String`.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext(storage = String)]
+/// enum Error {
+///     Io(std::io::Error),
+/// }
+///
+/// let err = std::fs::read("missing.txt")
+///     .context("Failed to read file")
+///     .unwrap_err();
+///
+/// assert!(err.to_string().starts_with("Failed to read file\n"));
+/// ```
+pub trait ContextBuf: Write + Default {
+    /// Bytes of context the buffer holds before it starts truncating.
+    ///
+    /// Checked against `static_context!()` messages at compile time. Buffers
+    /// that grow as needed keep the default of `usize::MAX`.
+    const LIMIT: usize = usize::MAX;
+
+    /// Returns the written context messages.
+    fn as_str(&self) -> &str;
+
+    /// Returns the bytes of context written, also when they fail UTF-8
+    /// validation and [`Self::as_str()`] shows a placeholder instead.
+    fn len(&self) -> usize {
+        self.as_str().len()
+    }
+
+    /// Moves everything written after `start` to the front of the buffer,
+    /// recording it as the first context message.
+    ///
+    /// `start` is a byte offset as returned by [`Self::len()`]. Offsets inside
+    /// a character are rounded up to the next char boundary.
+    fn rotate_tail(&mut self, start: usize);
+
+    /// Records the end of the context message written last.
+    ///
+    /// `contexts()` yields the messages between the recorded ends. Buffers
+    /// that don't record them keep the default, their messages are split at
+    /// the delimiter instead.
+    fn end_frame(&mut self) {}
+
+    /// Returns the ends recorded with [`Self::end_frame()`] and
+    /// [`Self::rotate_tail()`], in display order.
+    fn frame_ends(&self) -> FrameEnds<'_> {
+        FrameEnds::Unknown
+    }
+
+    /// Returns `true` if no context was written.
+    fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+
+    /// Returns `true` if context was cut off because the buffer ran out of space.
+    fn is_truncated(&self) -> bool {
+        false
+    }
+
+    /// Returns the bytes of context written, counting the bytes cut off by
+    /// truncation in debug builds.
+    fn required_len(&self) -> usize {
+        self.len()
+    }
+
+    /// Returns the counters collected for this buffer.
+    #[cfg(feature = "diagnostics")]
+    fn stats(&self) -> ErrorStats {
+        ErrorStats::default()
+    }
+
+    /// Counts a context message written without [`Self::push_context()`].
+    #[cfg(feature = "diagnostics")]
+    fn count_context(&mut self) {}
+
+    /// Appends a context message, or writes it to the front of the buffer
+    /// when `headline_last` is set.
+    ///
+    /// The first message is written as is, without delimiter and affixes.
+    fn push_context(
+        &mut self,
+        msg: &str,
+        delimiter: &str,
+        msg_prefix: &str,
+        msg_suffix: &str,
+        headline_last: bool,
+    ) {
+        #[cfg(feature = "diagnostics")]
+        self.count_context();
+
+        if self.as_str().is_empty() {
+            let _ = self.write_str(msg);
+            self.end_frame();
+        } else if headline_last {
+            let start = self.len();
+
+            let _ = self.write_str(msg_prefix);
+            let _ = self.write_str(msg);
+            let _ = self.write_str(msg_suffix);
+            let _ = self.write_str(delimiter);
+
+            self.rotate_tail(start);
+        } else {
+            let _ = self.write_str(delimiter);
+            let _ = self.write_str(msg_prefix);
+            let _ = self.write_str(msg);
+            let _ = self.write_str(msg_suffix);
+            self.end_frame();
+        }
+    }
+}
+
+/// Default layout of the wrapper's `Display` and `Debug` output.
+pub fn fmt_error(
+    f: &mut Formatter,
+    prefix: &str,
+    contexts: Contexts<'_>,
+    source_prefix: &str,
+    source: &dyn Display,
+    suffix: &str,
+) -> fmt::Result {
+    if contexts.is_empty() {
+        write!(f, "{}{}{}", source_prefix, source, suffix)
+    } else {
+        write!(
+            f,
+            "{}{}\n{}{}{}",
+            prefix, contexts, source_prefix, source, suffix,
+        )
+    }
+}
+
+/// Renders `err` honoring the width, fill, alignment and precision flags of
+/// `f`, like `Formatter::pad()` does for strings.
+///
+/// `err` is rendered with a fresh `Formatter`, so the wrapper's `Display`
+/// calls this when `f` has flags and its plain layout otherwise. Precision is
+/// the maximum length in chars, ANSI escape codes don't count towards either.
+/// The alternate flag is passed on.
+pub fn fmt_padded(f: &mut Formatter, err: &dyn Display) -> fmt::Result {
+    let alternate = f.alternate();
+    let max = f.precision().unwrap_or(usize::MAX);
+    let len = count_chars(err, alternate).min(max);
+
+    let pad = f.width().unwrap_or(0).saturating_sub(len);
+    let (before, after) = match f.align() {
+        Some(fmt::Alignment::Right) => (pad, 0),
+        Some(fmt::Alignment::Center) => (pad / 2, pad - pad / 2),
+        _ => (0, pad),
+    };
+    let fill = f.fill();
+
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+
+    let mut limited =
+        Limited { inner: Some(&mut *f), remaining: max, escape: Escape::None };
+
+    if alternate {
+        write!(limited, "{:#}", err)?;
+    } else {
+        write!(limited, "{}", err)?;
+    }
+
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the length of `err`'s `Display` output in chars, without ANSI
+/// escape codes.
+pub fn display_len(err: &dyn Display) -> usize {
+    count_chars(err, false)
+}
+
+fn count_chars(err: &dyn Display, alternate: bool) -> usize {
+    let mut counter =
+        Limited { inner: None, remaining: usize::MAX, escape: Escape::None };
+
+    let _ = if alternate {
+        write!(counter, "{:#}", err)
+    } else {
+        write!(counter, "{}", err)
+    };
+
+    usize::MAX - counter.remaining
+}
+
+/// Position in an ANSI escape sequence, which takes no space on screen.
+#[derive(Clone, Copy)]
+enum Escape {
+    None,
+    /// After `ESC`.
+    Start,
+    /// In a `ESC [` sequence, up to its final byte.
+    Csi,
+}
+
+/// Writes up to `remaining` visible chars to `inner`, or only counts them
+/// without one.
+///
+/// Escape codes are always written, so colors are reset after a cut off.
+struct Limited<'a, 'f> {
+    inner: Option<&'a mut Formatter<'f>>,
+    remaining: usize,
+    escape: Escape,
+}
+
+impl Limited<'_, '_> {
+    fn flush(&mut self, s: &str) -> fmt::Result {
+        match &mut self.inner {
+            Some(f) if !s.is_empty() => f.write_str(s),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Write for Limited<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+
+        for (idx, c) in s.char_indices() {
+            let visible = match self.escape {
+                Escape::None if c == '\x1b' => {
+                    self.escape = Escape::Start;
+                    false
+                }
+                Escape::None => true,
+                Escape::Start => {
+                    self.escape =
+                        if c == '[' { Escape::Csi } else { Escape::None };
+                    false
+                }
+                Escape::Csi => {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        self.escape = Escape::None;
+                    }
+                    false
+                }
+            };
+
+            if !visible {
+                continue;
+            }
+
+            if self.remaining == 0 {
+                self.flush(&s[start..idx])?;
+                start = idx + c.len_utf8();
+            } else {
+                self.remaining -= 1;
+            }
+        }
+
+        self.flush(&s[start..])
+    }
+}
+
+/// Single-line layout of the wrapper's `{:#}` output: the context messages
+/// and the source joined by `": "`.
+///
+/// Line breaks within messages are replaced by spaces, for log aggregators
+/// that split entries on them.
+pub fn fmt_compact(
+    f: &mut Formatter,
+    contexts: ContextFrames<'_>,
+    truncated: bool,
+    source: &dyn Display,
+) -> fmt::Result {
+    let cut_first = truncated && contexts.headline_last();
+    let mut f = SingleLine(f);
+
+    for (idx, context) in contexts.enumerate() {
+        f.write_str(context)?;
+
+        if cut_first && idx == 0 {
+            f.write_str("...")?;
+        }
+
+        f.write_str(": ")?;
+    }
+
+    if truncated && !cut_first {
+        f.write_str("...: ")?;
+    }
+
+    write!(f, "{}", source)
+}
+
+/// Replaces line breaks with spaces.
+struct SingleLine<'a, 'f>(&'a mut Formatter<'f>);
+
+impl Write for SingleLine<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (idx, line) in s.split(['\n', '\r']).enumerate() {
+            if idx > 0 {
+                self.0.write_char(' ')?;
+            }
+
+            self.0.write_str(line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the secondary errors of a wrapper with the `related` option as an
+/// indented list below the main chain.
+pub fn fmt_related<E: Display>(
+    f: &mut Formatter,
+    related: &[E],
+) -> fmt::Result {
+    if related.is_empty() {
+        return Ok(());
+    }
+
+    f.write_str("\nrelated errors:")?;
+
+    for err in related {
+        f.write_str("\n  - ")?;
+        write!(Indented { f: &mut *f }, "{}", err)?;
+    }
+
+    Ok(())
+}
+
+/// Renders the notes, docs URL or help messages of a wrapper with the
+/// `notes`, `show_docs_url` or `help` option, one `label: ` line each below
+/// the main chain.
+pub fn fmt_annotations<S: AsRef<str>>(
+    f: &mut Formatter,
+    label: &str,
+    msgs: &[S],
+) -> fmt::Result {
+    for msg in msgs {
+        write!(f, "\n{}: ", label)?;
+        Indented { f: &mut *f }.write_str(msg.as_ref())?;
+    }
+
+    Ok(())
+}
+
+/// Reports a warning that context added at `location` was truncated, once
+/// per call site, through the sink set with `set_report_sink()`.
+///
+/// Called by generated wrappers in debug builds, so undersized buffers show
+/// up during development rather than in production logs.
+#[cfg(feature = "std")]
+pub fn warn_truncated<B: ContextBuf>(
+    buf: &B,
+    location: &'static core::panic::Location<'static>,
+) {
+    // Keyed by call site, which the binary has a fixed number of.
+    static WARNED: std::sync::Mutex<
+        Option<
+            std::collections::HashSet<&'static core::panic::Location<'static>>,
+        >,
+    > = std::sync::Mutex::new(None);
+
+    let first = WARNED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get_or_insert_with(std::collections::HashSet::new)
+        .insert(location);
+
+    if first {
+        crate::sink::report(&format_args!(
+            "warning: context added at {} was truncated, it needs {} bytes but the buffer holds {}, consider a larger `buf_size`",
+            location,
+            buf.required_len(),
+            B::LIMIT,
+        ));
+    }
+}
+
+/// Captures a backtrace for a new wrapper, `None` if capturing is disabled.
+#[cfg(feature = "backtrace")]
+pub fn capture_backtrace()
+-> Option<alloc::boxed::Box<std::backtrace::Backtrace>> {
+    let backtrace = std::backtrace::Backtrace::capture();
+
+    match backtrace.status() {
+        std::backtrace::BacktraceStatus::Captured => {
+            Some(alloc::boxed::Box::new(backtrace))
+        }
+        _ => None,
+    }
+}
+
+/// Appends the wrapper's backtrace to its `Debug` output if one was captured.
+#[cfg(feature = "backtrace")]
+pub fn fmt_backtrace(
+    f: &mut Formatter,
+    backtrace: Option<&std::backtrace::Backtrace>,
+) -> fmt::Result {
+    match backtrace {
+        Some(backtrace) => write!(f, "\n\nstack backtrace:\n{}", backtrace),
+        None => Ok(()),
+    }
+}
+
+/// Indents every line after the first one so multi-line errors stay inside
+/// their list item.
+struct Indented<'a, 'f> {
+    f: &'a mut Formatter<'f>,
+}
+
+impl Write for Indented<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.f.write_str("\n    ")?;
+            }
+
+            self.f.write_str(line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders `value` into `buf`, returning the bytes written, or the bytes
+/// needed if `buf` is too small.
+///
+/// Formatting keeps going after `buf` is full to count the needed bytes, so
+/// a caller can retry with a large enough buffer.
+pub fn fmt_into(value: &dyn Display, buf: &mut [u8]) -> Result<usize, usize> {
+    let len = buf.len();
+    let (written, needed) = fmt_prefix(value, buf);
+
+    if needed > len { Err(needed) } else { Ok(written) }
+}
+
+/// Renders as much of `value` into `buf` as fits at a char boundary,
+/// returning the bytes written and the bytes needed.
+pub(crate) fn fmt_prefix(
+    value: &dyn Display,
+    buf: &mut [u8],
+) -> (usize, usize) {
+    let mut writer = SliceWriter { buf, written: 0, needed: 0 };
+
+    // `SliceWriter` never fails, only `Display` impls can.
+    let _ = write!(writer, "{}", value);
+
+    (writer.written, writer.needed)
+}
+
+/// Writer filling a byte slice, counting the bytes that don't fit.
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    written: usize,
+    needed: usize,
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Once something didn't fit, nothing else is written so the
+        // output stays a prefix of the full error.
+        if self.written == self.needed {
+            let mut end = s.len().min(self.buf.len() - self.written);
+
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            self.buf[self.written..self.written + end]
+                .copy_from_slice(&s.as_bytes()[..end]);
+            self.written += end;
+        }
+
+        self.needed += s.len();
+
+        Ok(())
+    }
+}
+
+/// Shown instead of buffer contents that aren't valid UTF-8.
+///
+/// Writes only ever store whole `&str`s cut at char boundaries, so this
+/// can't happen through the public API, but `Display` must stay sound and
+/// deterministic even if a buffer gets corrupted.
+const INVALID_UTF8: &str = "<invalid utf-8>";
+
+/// Returns the buffer contents as `&str`, or [`INVALID_UTF8`].
+fn checked_str(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or(INVALID_UTF8)
+}
+
+/// Returns `ends`, or [`FrameEnds::Unknown`] if they can't index the contents
+/// because [`INVALID_UTF8`] is shown instead.
+fn checked_ends<'a>(bytes: &[u8], ends: FrameEnds<'a>) -> FrameEnds<'a> {
+    match core::str::from_utf8(bytes) {
+        Ok(_) => ends,
+        Err(_) => FrameEnds::Unknown,
+    }
+}
+
+/// Rounds `index` up to the next byte that doesn't continue a character.
+fn ceil_char_boundary(bytes: &[u8], index: usize) -> usize {
+    let mut index = index.min(bytes.len());
+
+    while index < bytes.len() && (bytes[index] & 0xC0) == 0x80 {
+        index += 1;
+    }
+
+    index
+}
+
+/// Integer type storing the write position of a context buffer.
+///
+/// `u8` and `u16` keep small buffers compact, `#[resext]` picks the smallest
+/// type the buffer size fits in.
+pub trait BufPos: Copy {
+    const ZERO: Self;
+
+    /// Largest position the type can hold.
+    const MAX: usize;
+
+    fn get(self) -> usize;
+
+    /// Converts `pos` back, saturating at the type's maximum.
+    fn saturating_from(pos: usize) -> Self;
+
+    /// Wraps the recorded ends of the context messages.
+    fn frame_ends(ends: &[Self]) -> FrameEnds<'_> {
+        let _ = ends;
+        FrameEnds::Unknown
+    }
+}
+
+impl BufPos for u8 {
+    const ZERO: Self = 0;
+    const MAX: usize = u8::MAX as usize;
+
+    fn get(self) -> usize {
+        self as usize
+    }
+
+    fn saturating_from(pos: usize) -> Self {
+        u8::try_from(pos).unwrap_or(u8::MAX)
+    }
+
+    fn frame_ends(ends: &[Self]) -> FrameEnds<'_> {
+        FrameEnds::U8(ends)
+    }
+}
+
+impl BufPos for u16 {
+    const ZERO: Self = 0;
+    const MAX: usize = u16::MAX as usize;
+
+    fn get(self) -> usize {
+        self as usize
+    }
+
+    fn saturating_from(pos: usize) -> Self {
+        u16::try_from(pos).unwrap_or(u16::MAX)
+    }
+
+    fn frame_ends(ends: &[Self]) -> FrameEnds<'_> {
+        FrameEnds::U16(ends)
+    }
+}
+
+impl BufPos for usize {
+    const ZERO: Self = 0;
+    const MAX: usize = usize::MAX;
+
+    fn get(self) -> usize {
+        self
+    }
+
+    fn saturating_from(pos: usize) -> Self {
+        pos
+    }
+
+    fn frame_ends(ends: &[Self]) -> FrameEnds<'_> {
+        FrameEnds::Usize(ends)
+    }
+}
+
+/// Context messages whose ends an inline buffer records, newer ones are
+/// merged with the message recorded last.
+const MAX_FRAMES: usize = 8;
+
+/// Ends of the context messages in a buffer, in display order.
+struct Frames<P> {
+    ends: [P; MAX_FRAMES],
+    len: u8,
+}
+
+impl<P: BufPos> Frames<P> {
+    const fn new() -> Self {
+        Self { ends: [P::ZERO; MAX_FRAMES], len: 0 }
+    }
+
+    /// Records a message ending at `end`, unless nothing was written since
+    /// the last one.
+    fn push(&mut self, end: usize) {
+        let last = match self.len {
+            0 => 0,
+            len => self.ends[len as usize - 1].get(),
+        };
+
+        if end <= last {
+            return;
+        }
+
+        if (self.len as usize) < MAX_FRAMES {
+            self.len += 1;
+        }
+
+        self.ends[self.len as usize - 1] = P::saturating_from(end);
+    }
+
+    /// Records a message of `len` bytes moved to the front of the buffer.
+    fn push_front(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        // Once full, the message is merged with the one at the front.
+        if self.is_full() {
+            for end in &mut self.ends {
+                *end = P::saturating_from(end.get() + len);
+            }
+
+            return;
+        }
+
+        for idx in (1..=self.len as usize).rev() {
+            self.ends[idx] = P::saturating_from(self.ends[idx - 1].get() + len);
+        }
+
+        self.ends[0] = P::saturating_from(len);
+        self.len += 1;
+    }
+
+    fn is_full(&self) -> bool {
+        self.len as usize == MAX_FRAMES
+    }
+
+    fn as_slice(&self) -> &[P] {
+        &self.ends[..self.len as usize]
+    }
+}
+
+/// Ends recorded by [`SpillBuf`], moved to the heap with the context once
+/// they outgrow the inline slots, so messages are never merged.
+#[cfg(feature = "alloc")]
+enum SpillFrames<P> {
+    Inline(Frames<P>),
+    Heap(alloc::vec::Vec<usize>),
+}
+
+#[cfg(feature = "alloc")]
+impl<P: BufPos> SpillFrames<P> {
+    /// Moves the ends to the heap once the slots are taken or `end` is out
+    /// of the position type's range.
+    fn spill_for(&mut self, end: usize) {
+        if let Self::Inline(frames) = self
+            && (frames.is_full() || end > P::MAX)
+        {
+            *self = Self::Heap(
+                frames.as_slice().iter().map(|end| end.get()).collect(),
+            );
+        }
+    }
+
+    fn push(&mut self, end: usize) {
+        self.spill_for(end);
+
+        match self {
+            Self::Inline(frames) => frames.push(end),
+            Self::Heap(ends) => {
+                if ends.last().is_none_or(|&last| last < end) {
+                    ends.push(end);
+                }
+            }
+        }
+    }
+
+    /// Records a message of `len` bytes moved to the front of `total` bytes.
+    fn push_front(&mut self, len: usize, total: usize) {
+        self.spill_for(total);
+
+        match self {
+            Self::Inline(frames) => frames.push_front(len),
+            Self::Heap(ends) if len > 0 => {
+                ends.iter_mut().for_each(|end| *end += len);
+                ends.insert(0, len);
+            }
+            Self::Heap(_) => {}
+        }
+    }
+
+    fn ends(&self) -> FrameEnds<'_> {
+        match self {
+            Self::Inline(frames) => P::frame_ends(frames.as_slice()),
+            Self::Heap(ends) => FrameEnds::Usize(ends),
+        }
+    }
+}
+
+/// Fixed-size, inline context buffer.
+///
+/// Writes that don't fit are cut off at the last complete UTF-8 character and
+/// the buffer is marked as truncated.
+pub struct InlineBuf<const N: usize, P: BufPos = u16> {
+    curr_pos: P,
+    buf: [u8; N],
+    frames: Frames<P>,
+    truncate: bool,
+    #[cfg(debug_assertions)]
+    dropped: usize,
+    #[cfg(feature = "diagnostics")]
+    stats: ErrorStats,
+}
+
+impl<const N: usize, P: BufPos> InlineBuf<N, P> {
+    /// Usable bytes, limited by what the position type can address.
+    const CAPACITY: usize = if N < P::MAX { N } else { P::MAX };
+
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            curr_pos: P::ZERO,
+            frames: Frames::new(),
+            truncate: false,
+            #[cfg(debug_assertions)]
+            dropped: 0,
+            #[cfg(feature = "diagnostics")]
+            stats: ErrorStats { contexts: 0, truncations: 0, spills: 0 },
+        }
+    }
+
+    pub fn get_slice(&self) -> &[u8] {
+        &self.buf[..self.curr_pos.get().min(N)]
+    }
+}
+
+impl<const N: usize, P: BufPos> ContextBuf for InlineBuf<N, P> {
+    const LIMIT: usize = Self::CAPACITY;
+
+    fn as_str(&self) -> &str {
+        checked_str(self.get_slice())
+    }
+
+    fn len(&self) -> usize {
+        self.get_slice().len()
+    }
+
+    fn rotate_tail(&mut self, start: usize) {
+        let end = self.curr_pos.get().min(N);
+        let len = end - ceil_char_boundary(&self.buf[..end], start);
+
+        self.buf[..end].rotate_right(len);
+        self.frames.push_front(len);
+    }
+
+    fn end_frame(&mut self) {
+        self.frames.push(self.curr_pos.get().min(N));
+    }
+
+    fn frame_ends(&self) -> FrameEnds<'_> {
+        checked_ends(self.get_slice(), P::frame_ends(self.frames.as_slice()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.curr_pos.get() == 0
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.truncate
+    }
+
+    fn required_len(&self) -> usize {
+        #[cfg(debug_assertions)]
+        return self.len() + self.dropped;
+
+        #[cfg(not(debug_assertions))]
+        self.len()
+    }
+
+    #[cfg(feature = "diagnostics")]
+    fn stats(&self) -> ErrorStats {
+        self.stats
+    }
+
+    #[cfg(feature = "diagnostics")]
+    fn count_context(&mut self) {
+        self.stats.contexts += 1;
+    }
+}
+
+impl<const N: usize, P: BufPos> Default for InlineBuf<N, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, P: BufPos> Write for InlineBuf<N, P> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let bytes = s.as_bytes();
+        let pos = self.curr_pos.get().min(Self::CAPACITY);
+
+        // Nothing is appended once context was cut off, so bytes left over
+        // after a partial character don't pick up pieces of later frames.
+        let cap = if self.truncate { 0 } else { Self::CAPACITY - pos };
+
+        let limit = if cap < bytes.len() {
+            self.truncate = true;
+
+            #[cfg(feature = "diagnostics")]
+            {
+                self.stats.truncations += 1;
+            }
+
+            cap
+        } else {
+            bytes.len()
+        };
+
+        let to_copy =
+            match bytes[..limit].iter().rposition(|&b| (b & 0xC0) != 0x80) {
+                Some(start_of_last_char) => {
+                    let last_char_byte = bytes[start_of_last_char];
+                    let width = match last_char_byte {
+                        0..=127 => 1,
+                        192..=223 => 2,
+                        224..=239 => 3,
+                        240..=247 => 4,
+                        _ => 1,
+                    };
+                    if start_of_last_char + width <= limit {
+                        start_of_last_char + width
+                    } else {
+                        start_of_last_char
+                    }
+                }
+                None => 0,
+            };
+
+        self.buf[pos..pos + to_copy].copy_from_slice(&bytes[..to_copy]);
+        self.curr_pos = P::saturating_from(pos + to_copy);
+
+        #[cfg(debug_assertions)]
+        {
+            self.dropped += bytes.len() - to_copy;
+        }
+
+        Ok(())
+    }
+}
+
+/// Storage that [`SpillBuf`] moves its contents to once they outgrow the
+/// inline buffer.
+///
+/// `Vec<u8>` is used by default. Set with the `spill` attribute option to
+/// keep error paths off the global allocator, e.g. with a per-thread arena
+/// or a fixed pool. Bytes that don't fit can be dropped, the buffer is then
+/// marked as truncated like an [`InlineBuf`].
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{SpillStorage, resext};
+///
+/// /// Spills into a larger inline array instead of the heap.
+/// struct Overflow {
+///     buf: [u8; 256],
+///     len: usize,
+/// }
+///
+/// impl Default for Overflow {
+///     fn default() -> Self {
+///         Self { buf: [0; 256], len: 0 }
+///     }
+/// }
+///
+/// impl SpillStorage for Overflow {
+///     fn extend_from_slice(&mut self, bytes: &[u8]) -> usize {
+///         let mut len = bytes.len().min(self.buf.len() - self.len);
+///
+///         // Cut before a character that doesn't fit completely.
+///         while len < bytes.len() && (bytes[len] & 0xC0) == 0x80 {
+///             len -= 1;
+///         }
+///
+///         self.buf[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+///         self.len += len;
+///
+///         len
+///     }
+///
+///     fn as_slice(&self) -> &[u8] {
+///         &self.buf[..self.len]
+///     }
+///
+///     fn as_mut_slice(&mut self) -> &mut [u8] {
+///         &mut self.buf[..self.len]
+///     }
+/// }
+///
+/// #[resext(alloc = true, buf_size = 8, spill = Overflow)]
+/// enum AppError {
+///     Io(std::io::Error),
+/// }
+///
+/// let err = std::fs::read("missing.txt")
+///     .context("Failed to read missing.txt")
+///     .unwrap_err();
+///
+/// assert!(err.to_string().starts_with("Failed to read missing.txt\n"));
+/// ```
+#[cfg(feature = "alloc")]
+pub trait SpillStorage: Default {
+    /// Reserves room for at least `additional` more bytes.
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Appends `bytes`, or as much of them as fits, returning the number of
+    /// bytes appended.
+    ///
+    /// `bytes` is valid UTF-8, and must be cut at a char boundary when it
+    /// doesn't fit. Otherwise the context shows as `<invalid utf-8>`.
+    fn extend_from_slice(&mut self, bytes: &[u8]) -> usize;
+
+    /// Returns the stored bytes.
+    fn as_slice(&self) -> &[u8];
+
+    /// Returns the stored bytes mutably.
+    fn as_mut_slice(&mut self) -> &mut [u8];
+}
+
+#[cfg(feature = "alloc")]
+impl SpillStorage for alloc::vec::Vec<u8> {
+    fn reserve(&mut self, additional: usize) {
+        self.reserve_exact(additional);
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) -> usize {
+        self.extend_from_slice(bytes);
+        bytes.len()
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// Context buffer that starts inline and spills to the heap, or another
+/// [`SpillStorage`], once `N` bytes are exceeded.
+///
+/// Context is only truncated if the storage refuses bytes.
+#[cfg(feature = "alloc")]
+pub struct SpillBuf<
+    const N: usize,
+    P: BufPos = u16,
+    S: SpillStorage = alloc::vec::Vec<u8>,
+> {
+    data: SpillData<N, P, S>,
+    frames: SpillFrames<P>,
+    truncate: bool,
+    #[cfg(debug_assertions)]
+    dropped: usize,
+    #[cfg(feature = "diagnostics")]
+    stats: ErrorStats,
+}
+
+#[cfg(feature = "alloc")]
+enum SpillData<const N: usize, P: BufPos, S> {
+    Stack { buf: [u8; N], curr_pos: P },
+    Heap(S),
+}
+
+#[cfg(feature = "alloc")]
+impl<const N: usize, P: BufPos, S: SpillStorage> SpillBuf<N, P, S> {
+    /// Inline bytes, limited by what the position type can address.
+    const CAPACITY: usize = if N < P::MAX { N } else { P::MAX };
+
+    pub const fn new() -> Self {
+        Self {
+            data: SpillData::Stack { buf: [0; N], curr_pos: P::ZERO },
+            frames: SpillFrames::Inline(Frames::new()),
+            truncate: false,
+            #[cfg(debug_assertions)]
+            dropped: 0,
+            #[cfg(feature = "diagnostics")]
+            stats: ErrorStats { contexts: 0, truncations: 0, spills: 0 },
+        }
+    }
+
+    pub fn get_slice(&self) -> &[u8] {
+        match &self.data {
+            SpillData::Stack { buf, curr_pos } => &buf[..curr_pos.get().min(N)],
+            SpillData::Heap(buf) => buf.as_slice(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const N: usize, P: BufPos, S: SpillStorage> ContextBuf
+    for SpillBuf<N, P, S>
+{
+    fn as_str(&self) -> &str {
+        checked_str(self.get_slice())
+    }
+
+    fn len(&self) -> usize {
+        self.get_slice().len()
+    }
+
+    fn rotate_tail(&mut self, start: usize) {
+        let buf = match &mut self.data {
+            SpillData::Stack { buf, curr_pos } => {
+                &mut buf[..curr_pos.get().min(N)]
+            }
+            SpillData::Heap(buf) => buf.as_mut_slice(),
+        };
+        let total = buf.len();
+        let len = total - ceil_char_boundary(buf, start);
+
+        buf.rotate_right(len);
+        self.frames.push_front(len, total);
+    }
+
+    fn end_frame(&mut self) {
+        self.frames.push(self.get_slice().len());
+    }
+
+    fn frame_ends(&self) -> FrameEnds<'_> {
+        checked_ends(self.get_slice(), self.frames.ends())
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.truncate
+    }
+
+    fn required_len(&self) -> usize {
+        #[cfg(debug_assertions)]
+        return self.len() + self.dropped;
+
+        #[cfg(not(debug_assertions))]
+        self.len()
+    }
+
+    #[cfg(feature = "diagnostics")]
+    fn stats(&self) -> ErrorStats {
+        self.stats
+    }
+
+    #[cfg(feature = "diagnostics")]
+    fn count_context(&mut self) {
+        self.stats.contexts += 1;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const N: usize, P: BufPos, S: SpillStorage> Default for SpillBuf<N, P, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const N: usize, P: BufPos, S: SpillStorage> Write for SpillBuf<N, P, S> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let bytes = s.as_bytes();
+
+        let (requested, written) = match &mut self.data {
+            // Like `InlineBuf`, nothing is appended once context was cut off.
+            SpillData::Heap(_) if self.truncate => (bytes.len(), 0),
+            SpillData::Heap(buf) => {
+                (bytes.len(), buf.extend_from_slice(bytes).min(bytes.len()))
+            }
+            SpillData::Stack { buf, curr_pos } => {
+                let pos = curr_pos.get().min(Self::CAPACITY);
+                let cap = Self::CAPACITY - pos;
+
+                if bytes.len() > cap {
+                    let mut heap = S::default();
+                    heap.reserve(pos + bytes.len());
+
+                    let mut written = heap.extend_from_slice(&buf[..pos]);
+
+                    // Nothing is appended after a refused byte, so the
+                    // stored context stays a prefix of what was written.
+                    if written >= pos {
+                        written = pos + heap.extend_from_slice(bytes);
+                    }
+
+                    self.data = SpillData::Heap(heap);
+
+                    #[cfg(feature = "diagnostics")]
+                    {
+                        self.stats.spills += 1;
+                    }
+
+                    (pos + bytes.len(), written.min(pos + bytes.len()))
+                } else {
+                    buf[pos..pos + bytes.len()].copy_from_slice(bytes);
+                    *curr_pos = P::saturating_from(pos + bytes.len());
+
+                    (bytes.len(), bytes.len())
+                }
+            }
+        };
+
+        if written < requested {
+            self.truncate = true;
+
+            #[cfg(feature = "diagnostics")]
+            {
+                self.stats.truncations += 1;
+            }
+
+            #[cfg(debug_assertions)]
+            {
+                self.dropped += requested - written;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ContextBuf for alloc::string::String {
+    fn as_str(&self) -> &str {
+        self
+    }
+
+    fn rotate_tail(&mut self, start: usize) {
+        let mut start = start.min(self.len());
+
+        while !self.is_char_boundary(start) {
+            start += 1;
+        }
+
+        let tail = self.split_off(start);
+        self.insert_str(0, &tail);
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::Error;
+
+/// Conversion from `anyhow::Error` into an enum generated by `#[resext]`.
+///
+/// Implemented by the proc-macro for enums with `classify = true`, by
+/// downcasting the error against each variant's wrapped type in declaration
+/// order. All wrapped types must therefore be `Send + Sync + 'static`.
+pub trait FromAnyhow: Sized {
+    /// Returns the first variant whose wrapped type matches the error, or
+    /// gives the error back if none does.
+    fn from_anyhow(err: Error) -> Result<Self, Error>;
+}
+
+/// Extension trait for moving `anyhow::Result`s over to typed errors.
+pub trait Classify<T> {
+    /// Converts the `anyhow::Error` into the generated enum `E`.
+    ///
+    /// Variants are tried in declaration order; errors that match none of
+    /// them end up in `E`'s catch-all variant wrapping `anyhow::Error`, so
+    /// codebases can migrate from anyhow one error type at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resext::{Classify, resext};
+    ///
+    /// #[resext(classify = true)]
+    /// enum AppError {
+    ///     Parse(std::num::ParseIntError),
+    ///     Other(anyhow::Error),
+    /// }
+    ///
+    /// fn legacy() -> anyhow::Result<i32> {
+    ///     Ok("x".parse::<i32>()?)
+    /// }
+    ///
+    /// let err = legacy().classify::<AppError>().unwrap_err();
+    /// assert!(matches!(err, AppError::Parse(_)));
+    /// ```
+    fn classify<E: FromAnyhow + From<Error>>(self) -> Result<T, E>;
+}
+
+impl<T> Classify<T> for Result<T, Error> {
+    fn classify<E: FromAnyhow + From<Error>>(self) -> Result<T, E> {
+        self.map_err(|err| E::from_anyhow(err).unwrap_or_else(E::from))
+    }
+}
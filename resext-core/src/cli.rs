@@ -0,0 +1,43 @@
+use core::fmt::Display;
+use std::format;
+
+use clap::error::ErrorKind;
+use clap::{Command, Error};
+
+/// Conversion of errors into `clap::Error`, so runtime failures are printed
+/// in the same style as argument-validation failures.
+///
+/// Implemented for everything that implements `Display`, including the
+/// wrapper generated by `#[resext]`.
+pub trait ToClapError: Display {
+    /// Formats the error with clap's `error:` header, using `cmd`'s styles.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resext::{ToClapError, resext};
+    ///
+    /// #[resext]
+    /// enum CliError {
+    ///     Parse(std::num::ParseIntError),
+    /// }
+    ///
+    /// let cmd = clap::Command::new("app");
+    /// let err = "x".parse::<u8>()
+    ///     .context("Failed to parse `--retries`")
+    ///     .map_err(|err| err.to_clap_error(&cmd))
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "error: Failed to parse `--retries`\nError: invalid digit found in string\n"
+    /// );
+    ///
+    /// // err.exit() prints it with the binary's styling and exits with code 2
+    /// ```
+    fn to_clap_error(&self, cmd: &Command) -> Error {
+        Error::raw(ErrorKind::Io, format!("{}\n", self)).with_cmd(cmd)
+    }
+}
+
+impl<T: Display + ?Sized> ToClapError for T {}
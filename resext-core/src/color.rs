@@ -0,0 +1,141 @@
+//! ANSI-colored rendering of the default error layout for terminals.
+
+use core::fmt::{self, Display, Formatter};
+
+use crate::ContextFrames;
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// Writes `s` in `style`, without escape codes if `s` is empty.
+fn paint(f: &mut Formatter, style: &str, s: &str) -> fmt::Result {
+    if s.is_empty() {
+        return Ok(());
+    }
+
+    write!(f, "{}{}{}", style, s, RESET)
+}
+
+/// Default layout of the wrapper's `Display` output with ANSI colors.
+///
+/// Delimiters are dimmed, the prefix is bold, context messages are cyan and
+/// the source prefix is bold red.
+pub fn fmt_colored(
+    f: &mut Formatter,
+    prefix: &str,
+    contexts: ContextFrames<'_>,
+    truncated: bool,
+    source_prefix: &str,
+    source: &dyn Display,
+    suffix: &str,
+) -> fmt::Result {
+    let delimiter = contexts.delimiter();
+    let cut_first = truncated && contexts.headline_last();
+    let mut contexts = contexts.enumerate().peekable();
+
+    if contexts.peek().is_some() {
+        paint(f, BOLD, prefix)?;
+
+        for (idx, frame) in contexts {
+            if idx > 0 {
+                paint(f, DIM, delimiter)?;
+            }
+
+            paint(f, CYAN, frame)?;
+
+            if cut_first && idx == 0 {
+                paint(f, DIM, "...")?;
+            }
+        }
+
+        if truncated && !cut_first {
+            paint(f, DIM, "...")?;
+        }
+
+        f.write_str("\n")?;
+    }
+
+    paint(f, BOLD_RED, source_prefix)?;
+    write!(f, "{}{}", source, suffix)
+}
+
+/// Returns `true` if errors printed to stderr should be colored.
+///
+/// Colors are used if stderr is a terminal and `NO_COLOR` isn't set to a
+/// non-empty value. Checked once per process.
+#[cfg(feature = "std")]
+pub fn use_color() -> bool {
+    use std::io::IsTerminal;
+
+    static COLOR: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+    *COLOR.get_or_init(|| {
+        std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            && std::io::stderr().is_terminal()
+    })
+}
+
+/// Implemented by wrappers with `color = true`, whose reports to stderr are
+/// colored if [`use_color()`] allows it.
+#[doc(hidden)]
+pub trait ColorReport {
+    fn fmt_report(&self, f: &mut Formatter) -> fmt::Result;
+}
+
+/// Wraps an error reported by code that doesn't know its type, like
+/// `#[resext::main]`, which picks the colored report with
+/// `(&&ReportProbe(&err)).report_display()` for wrappers implementing
+/// [`ColorReport`], and plain `Display` for everything else.
+#[doc(hidden)]
+pub struct ReportProbe<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait ReportColored<'a> {
+    fn report_display(&self) -> ReportDisplay<'a>;
+}
+
+impl<'a, T: ColorReport> ReportColored<'a> for &ReportProbe<'a, T> {
+    fn report_display(&self) -> ReportDisplay<'a> {
+        ReportDisplay::Colored(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait ReportPlain<'a> {
+    fn report_display(&self) -> ReportDisplay<'a>;
+}
+
+impl<'a, T: Display> ReportPlain<'a> for ReportProbe<'a, T> {
+    fn report_display(&self) -> ReportDisplay<'a> {
+        ReportDisplay::Plain(self.0)
+    }
+}
+
+/// An error as written to a report, returned by [`ReportProbe`].
+#[doc(hidden)]
+pub enum ReportDisplay<'a> {
+    Colored(&'a dyn ColorReport),
+    Plain(&'a dyn Display),
+}
+
+impl Display for ReportDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Colored(err) => err.fmt_report(f),
+            Self::Plain(err) => err.fmt(f),
+        }
+    }
+}
+
+/// `Display` implemented by a closure.
+#[doc(hidden)]
+pub struct DisplayFn<F>(pub F);
+
+impl<F: Fn(&mut Formatter) -> fmt::Result> Display for DisplayFn<F> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        (self.0)(f)
+    }
+}
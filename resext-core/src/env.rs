@@ -0,0 +1,38 @@
+//! Environment variable helpers.
+
+use std::env::VarError;
+use std::string::String;
+
+use crate::FromCtx;
+
+/// Reads the environment variable `name`, naming it in the context on
+/// failure.
+///
+/// Generic over the wrapper generated by `#[resext]`, whose enum must have a
+/// variant wrapping `std::env::VarError`.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext]
+/// enum ConfigError {
+///     Env(std::env::VarError),
+/// }
+///
+/// let err = resext::env::var_ctx::<ResErr>("RESEXT_DOC_MISSING_VAR").unwrap_err();
+///
+/// assert_eq!(
+///     err.to_string(),
+///     "Failed to read environment variable `RESEXT_DOC_MISSING_VAR`\nError: environment variable not found"
+/// );
+/// ```
+pub fn var_ctx<W: FromCtx<VarError>>(name: &str) -> Result<String, W> {
+    std::env::var(name).map_err(|err| {
+        W::from_ctx(
+            err,
+            format_args!("Failed to read environment variable `{}`", name),
+        )
+    })
+}
@@ -0,0 +1,197 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use crate::{ErrorSink, ErrorVariant};
+
+/// A collection of errors, for reporting every failure of a batch at once
+/// instead of stopping at the first one.
+///
+/// `Display` renders each error separated by a blank line.
+#[derive(Debug)]
+pub struct Errors<E>(Vec<E>);
+
+impl<E> Errors<E> {
+    /// Creates an empty collection.
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds an error to the collection.
+    pub fn push(&mut self, err: E) {
+        self.0.push(err);
+    }
+
+    /// Returns the number of collected errors.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no errors were collected.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the collected errors.
+    pub fn iter(&self) -> core::slice::Iter<'_, E> {
+        self.0.iter()
+    }
+
+    /// Returns the collected errors.
+    pub fn into_vec(self) -> Vec<E> {
+        self.0
+    }
+
+    /// Returns the errors ordered by variant, in declaration order.
+    ///
+    /// Errors of the same variant keep the order they were collected in, so
+    /// summaries and test snapshots are deterministic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resext::{CollectErrors, resext};
+    ///
+    /// #[resext]
+    /// enum RowError {
+    ///     Parse(std::num::ParseIntError),
+    ///     Empty(&'static str),
+    /// }
+    ///
+    /// let errors = ["", "x", "", "y"]
+    ///     .into_iter()
+    ///     .map(|row| match row {
+    ///         "" => Err(ResErr::new("Empty row", "row")),
+    ///         row => row.parse::<i32>().context("Invalid row"),
+    ///     })
+    ///     .collect_errors()
+    ///     .unwrap_err();
+    ///
+    /// let variants: Vec<_> = errors.sorted_by_variant().iter().map(|err| err.source.variant_name()).collect();
+    ///
+    /// assert_eq!(variants, ["Parse", "Parse", "Empty", "Empty"]);
+    /// ```
+    pub fn sorted_by_variant(&self) -> Vec<&E>
+    where
+        E: ErrorVariant,
+    {
+        let mut sorted: Vec<&E> = self.0.iter().collect();
+        sorted.sort_by_key(|err| err.variant_index());
+        sorted
+    }
+
+    /// Returns the errors ordered by their numeric code, keeping the order
+    /// they were collected in for equal codes.
+    pub fn sorted_by_code(&self) -> Vec<&E>
+    where
+        E: ErrorVariant,
+    {
+        let mut sorted: Vec<&E> = self.0.iter().collect();
+        sorted.sort_by_key(|err| err.code());
+        sorted
+    }
+
+    /// Reports every error to `sink` separately, e.g. one log line each.
+    pub fn report_to<S: ErrorSink + ?Sized>(&self, sink: &mut S)
+    where
+        E: Display,
+    {
+        for err in &self.0 {
+            sink.report(err);
+        }
+    }
+
+    /// Returns `Ok(value)` if no errors were collected, or `Err(self)`.
+    pub fn into_result<T>(self, value: T) -> Result<T, Self> {
+        if self.is_empty() { Ok(value) } else { Err(self) }
+    }
+}
+
+impl<E> Default for Errors<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Extend<E> for Errors<E> {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl<E> FromIterator<E> for Errors<E> {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<E> IntoIterator for Errors<E> {
+    type Item = E;
+    type IntoIter = alloc::vec::IntoIter<E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<E: Display> Display for Errors<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (idx, err) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("\n\n")?;
+            }
+
+            write!(f, "{}", err)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<E: core::error::Error> core::error::Error for Errors<E> {}
+
+/// Extension trait for collecting every error of an iterator of Results.
+pub trait CollectErrors<T, E>: Iterator<Item = Result<T, E>> + Sized {
+    /// Collects all values, or all errors if any item failed.
+    ///
+    /// Unlike collecting into `Result<Vec<T>, E>`, this doesn't stop at the
+    /// first error, so every bad record of a batch can be reported at once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resext::{CollectErrors, resext};
+    ///
+    /// #[resext]
+    /// enum RowError {
+    ///     Parse(std::num::ParseIntError),
+    /// }
+    ///
+    /// let errors = ["1", "x", "3", "y"]
+    ///     .into_iter()
+    ///     .enumerate()
+    ///     .map(|(idx, row)| row.parse::<i32>().with_row_context(idx + 1))
+    ///     .collect_errors()
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     errors.to_string(),
+    ///     "Record 2\nError: invalid digit found in string\n\n\
+    ///      Record 4\nError: invalid digit found in string"
+    /// );
+    /// ```
+    fn collect_errors(self) -> Result<Vec<T>, Errors<E>> {
+        let mut values = Vec::new();
+        let mut errors = Errors::new();
+
+        for item in self {
+            match item {
+                Ok(value) => values.push(value),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        errors.into_result(values)
+    }
+}
+
+impl<T, E, I: Iterator<Item = Result<T, E>>> CollectErrors<T, E> for I {}
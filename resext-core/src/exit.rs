@@ -0,0 +1,180 @@
+use core::fmt::Display;
+
+use std::string::String;
+use std::vec::Vec;
+
+/// Runtime mapping from variant names to process exit codes.
+///
+/// Lets exit-code policy come from configuration instead of compile-time
+/// attributes. Used by `.or_exit_policy()` on the generated `{alias}Ops`
+/// trait.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::ExitPolicy;
+///
+/// let policy = ExitPolicy::new().map_variant("Io", 74).default(1);
+///
+/// assert_eq!(policy.code_for("Io"), 74);
+/// assert_eq!(policy.code_for("Parse"), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExitPolicy {
+    variants: Vec<(String, i32)>,
+    default: i32,
+}
+
+impl ExitPolicy {
+    /// Creates a policy mapping every variant to exit code 1.
+    pub const fn new() -> Self {
+        Self { variants: Vec::new(), default: 1 }
+    }
+
+    /// Exits with `code` for errors of the variant named `variant`.
+    ///
+    /// Mapping the same variant again replaces its code.
+    pub fn map_variant(
+        mut self,
+        variant: impl Into<String>,
+        code: i32,
+    ) -> Self {
+        let variant = variant.into();
+
+        match self.variants.iter_mut().find(|(name, _)| *name == variant) {
+            Some((_, old)) => *old = code,
+            None => self.variants.push((variant, code)),
+        }
+
+        self
+    }
+
+    /// Sets the exit code for variants without a mapping (default: 1).
+    pub fn default(mut self, code: i32) -> Self {
+        self.default = code;
+        self
+    }
+
+    /// Returns the exit code for the variant named `variant`.
+    pub fn code_for(&self, variant: &str) -> i32 {
+        self.variants
+            .iter()
+            .find(|(name, _)| name == variant)
+            .map_or(self.default, |(_, code)| *code)
+    }
+}
+
+impl Default for ExitPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build identification appended to fatal reports by `#[resext::main]`.
+///
+/// Displayed as `version: x.y.z`, followed by `(commit ...)` if a commit
+/// is known.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::BuildInfo;
+///
+/// let info = BuildInfo::new("1.2.0", Some("abc123"));
+///
+/// assert_eq!(info.to_string(), "version: 1.2.0 (commit abc123)");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BuildInfo {
+    version: &'static str,
+    commit: Option<&'static str>,
+}
+
+impl BuildInfo {
+    /// Creates build info from a version and an optional commit hash.
+    pub const fn new(
+        version: &'static str,
+        commit: Option<&'static str>,
+    ) -> Self {
+        Self { version, commit }
+    }
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "version: {}", self.version)?;
+
+        match self.commit {
+            Some(commit) => write!(f, " (commit {})", commit),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Return type for `fn main()` that reports errors with their full context
+/// chain.
+///
+/// `fn main() -> Res<()>` prints errors with `Error: {:?}`, which repeats the
+/// `Error:` prefix. Converting the result into `MainResult` prints the
+/// `Display` output instead, to stderr or the sink set with
+/// [`crate::set_report_sink()`], and exits with code 1 like
+/// `#[resext::main]`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use resext::{MainResult, resext};
+///
+/// #[resext]
+/// enum CliError {
+///     Io(std::io::Error),
+/// }
+///
+/// fn run() -> Res<()> {
+///     std::fs::read("missing.toml").context("Failed to read config")?;
+///     Ok(())
+/// }
+///
+/// fn main() -> MainResult<ResErr> {
+///     run().into()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MainResult<E>(Result<(), E>);
+
+impl<E> From<Result<(), E>> for MainResult<E> {
+    fn from(res: Result<(), E>) -> Self {
+        Self(res)
+    }
+}
+
+impl<E: Display> std::process::Termination for MainResult<E> {
+    fn report(self) -> std::process::ExitCode {
+        match self.0 {
+            Ok(()) => std::process::ExitCode::SUCCESS,
+            Err(err) => {
+                crate::sink::report(&err);
+                std::process::ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+/// Prints `err` to stderr, or the sink set with [`crate::set_report_sink()`],
+/// and exits the process with `code`.
+///
+/// With the `test-no-exit` feature this panics with the formatted error
+/// instead, so tests can assert on fatal paths without spawning subprocesses,
+/// except inside [`crate::testing::expect_exit()`].
+#[track_caller]
+pub fn exit(code: i32, err: &dyn Display) -> ! {
+    if cfg!(feature = "test-no-exit")
+        && std::env::var_os(crate::testing::CHILD_ENV).is_none()
+    {
+        panic!("process exited with code {}:\n{}", code, err);
+    }
+
+    crate::sink::report(err);
+
+    std::process::exit(code)
+}
@@ -0,0 +1,206 @@
+//! Process exit codes following BSD's `sysexits.h`.
+//!
+//! Meant for `.or_exit()`, [`crate::ExitPolicy`] and the variant-level
+//! `#[resext(exit_code = "EX_IOERR")]`, which refers to these constants by
+//! name.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resext::{ExitPolicy, exitcode};
+//!
+//! let policy = ExitPolicy::new()
+//!     .map_variant("Io", exitcode::EX_IOERR)
+//!     .default(exitcode::EX_SOFTWARE);
+//!
+//! assert_eq!(policy.code_for("Io"), 74);
+//! assert_eq!(exitcode::from_name("EX_CONFIG"), Some(78));
+//! ```
+
+/// Successful termination.
+pub const EX_OK: i32 = 0;
+
+/// The command was used incorrectly, e.g. with the wrong number of arguments.
+pub const EX_USAGE: i32 = 64;
+
+/// The input data was incorrect in some way.
+pub const EX_DATAERR: i32 = 65;
+
+/// An input file did not exist or was not readable.
+pub const EX_NOINPUT: i32 = 66;
+
+/// The user specified did not exist.
+pub const EX_NOUSER: i32 = 67;
+
+/// The host specified did not exist.
+pub const EX_NOHOST: i32 = 68;
+
+/// A service is unavailable.
+pub const EX_UNAVAILABLE: i32 = 69;
+
+/// An internal software error has been detected.
+pub const EX_SOFTWARE: i32 = 70;
+
+/// An operating system error has been detected, e.g. "cannot fork".
+pub const EX_OSERR: i32 = 71;
+
+/// A system file did not exist, cannot be opened, or has some sort of error.
+pub const EX_OSFILE: i32 = 72;
+
+/// A user-specified output file cannot be created.
+pub const EX_CANTCREAT: i32 = 73;
+
+/// An error occurred while doing I/O on some file.
+pub const EX_IOERR: i32 = 74;
+
+/// Temporary failure, the user is invited to retry.
+pub const EX_TEMPFAIL: i32 = 75;
+
+/// The remote system returned something that was not possible during a
+/// protocol exchange.
+pub const EX_PROTOCOL: i32 = 76;
+
+/// Insufficient permission to perform the operation.
+pub const EX_NOPERM: i32 = 77;
+
+/// Something was found in an unconfigured or misconfigured state.
+pub const EX_CONFIG: i32 = 78;
+
+/// Returns the code of the constant named `name`, e.g. for exit-code policies
+/// read from configuration.
+pub fn from_name(name: &str) -> Option<i32> {
+    let code = match name {
+        "EX_OK" => EX_OK,
+        "EX_USAGE" => EX_USAGE,
+        "EX_DATAERR" => EX_DATAERR,
+        "EX_NOINPUT" => EX_NOINPUT,
+        "EX_NOUSER" => EX_NOUSER,
+        "EX_NOHOST" => EX_NOHOST,
+        "EX_UNAVAILABLE" => EX_UNAVAILABLE,
+        "EX_SOFTWARE" => EX_SOFTWARE,
+        "EX_OSERR" => EX_OSERR,
+        "EX_OSFILE" => EX_OSFILE,
+        "EX_CANTCREAT" => EX_CANTCREAT,
+        "EX_IOERR" => EX_IOERR,
+        "EX_TEMPFAIL" => EX_TEMPFAIL,
+        "EX_PROTOCOL" => EX_PROTOCOL,
+        "EX_NOPERM" => EX_NOPERM,
+        "EX_CONFIG" => EX_CONFIG,
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+/// Maps common error types onto the code a CLI would exit with, e.g.
+/// `EX_NOINPUT` for a missing file or `EX_DATAERR` for a failed parse.
+///
+/// Looks through `err`'s chain of `source()`s and returns the code of the
+/// first error it recognizes:
+///
+/// - `io::Error` by kind: `NotFound` is `EX_NOINPUT`, `PermissionDenied` is
+///   `EX_NOPERM`, `AlreadyExists` is `EX_CANTCREAT`, connection failures are
+///   `EX_UNAVAILABLE`, timeouts and interruptions are `EX_TEMPFAIL`,
+///   `InvalidData` and `UnexpectedEof` are `EX_DATAERR` and any other kind
+///   is `EX_IOERR`
+/// - Parse and UTF-8 errors of `core` and `alloc` are `EX_DATAERR`
+/// - `env::VarError` is `EX_CONFIG`
+///
+/// `.or_exit_sysexit()` on the generated `{alias}Ops` trait exits with it.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::exitcode;
+///
+/// let err = std::fs::read("missing.txt").unwrap_err();
+///
+/// assert_eq!(exitcode::for_error(&err), Some(exitcode::EX_NOINPUT));
+/// assert_eq!(
+///     exitcode::for_error(&"x".parse::<u8>().unwrap_err()),
+///     Some(exitcode::EX_DATAERR)
+/// );
+/// ```
+pub fn for_error(err: &(dyn core::error::Error + 'static)) -> Option<i32> {
+    let mut next = Some(err);
+
+    while let Some(err) = next {
+        if let Some(code) = code_of(err) {
+            return Some(code);
+        }
+
+        next = err.source();
+    }
+
+    None
+}
+
+fn code_of(err: &(dyn core::error::Error + 'static)) -> Option<i32> {
+    #[cfg(feature = "std")]
+    if let Some(err) = err.downcast_ref::<std::io::Error>() {
+        use std::io::ErrorKind;
+
+        return Some(match err.kind() {
+            ErrorKind::NotFound => EX_NOINPUT,
+            ErrorKind::PermissionDenied => EX_NOPERM,
+            ErrorKind::AlreadyExists => EX_CANTCREAT,
+            ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::AddrNotAvailable => EX_UNAVAILABLE,
+            ErrorKind::TimedOut
+            | ErrorKind::WouldBlock
+            | ErrorKind::Interrupted => EX_TEMPFAIL,
+            ErrorKind::InvalidData | ErrorKind::UnexpectedEof => EX_DATAERR,
+            _ => EX_IOERR,
+        });
+    }
+
+    #[cfg(feature = "std")]
+    if err.is::<std::env::VarError>() {
+        return Some(EX_CONFIG);
+    }
+
+    #[cfg(feature = "alloc")]
+    if err.is::<alloc::string::FromUtf8Error>() {
+        return Some(EX_DATAERR);
+    }
+
+    let data_error = err.is::<core::num::ParseIntError>()
+        || err.is::<core::num::ParseFloatError>()
+        || err.is::<core::str::ParseBoolError>()
+        || err.is::<core::char::ParseCharError>()
+        || err.is::<core::net::AddrParseError>()
+        || err.is::<core::str::Utf8Error>();
+
+    data_error.then_some(EX_DATAERR)
+}
+
+/// Wraps a variant's value for `sysexit_code()` on generated enums, which
+/// calls `(&&SysexitProbe(value)).sysexit()` so values implementing
+/// `Error` are looked up with [`for_error()`] and others fall through.
+#[doc(hidden)]
+pub struct SysexitProbe<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait SysexitError {
+    fn sysexit(&self) -> Option<i32>;
+}
+
+impl<T: core::error::Error + 'static> SysexitError for &SysexitProbe<'_, T> {
+    fn sysexit(&self) -> Option<i32> {
+        for_error(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait SysexitOther {
+    fn sysexit(&self) -> Option<i32>;
+}
+
+impl<T: ?Sized> SysexitOther for SysexitProbe<'_, T> {
+    fn sysexit(&self) -> Option<i32> {
+        None
+    }
+}
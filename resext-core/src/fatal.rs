@@ -0,0 +1,154 @@
+//! Fatal reports that can be emitted from signal handlers.
+
+use core::fmt::Display;
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering, fence};
+
+use std::io::Write;
+use std::os::fd::FromRawFd;
+
+/// Size of the buffer holding the prepared report.
+const PREPARED_SIZE: usize = 1024;
+
+/// How often a reader retries while a report is being replaced, before
+/// giving up. A signal handler interrupting `prepare_fatal()` on its own
+/// thread would otherwise wait forever.
+const READ_ATTEMPTS: usize = 64;
+
+/// The prepared report, guarded by a sequence lock.
+///
+/// The bytes are atomics, so reading them while another thread replaces the
+/// report is a torn read, not a data race. `seq` tells readers to retry.
+struct Prepared {
+    buf: [AtomicU8; PREPARED_SIZE],
+    /// Length of the report in `buf`, 0 while none is ready.
+    len: AtomicUsize,
+    /// Odd while a report is being written, bumped again once it's done.
+    seq: AtomicUsize,
+}
+
+static PREPARED: Prepared = Prepared {
+    buf: [const { AtomicU8::new(0) }; PREPARED_SIZE],
+    len: AtomicUsize::new(0),
+    seq: AtomicUsize::new(0),
+};
+
+/// Renders `err` ahead of time for [`abort_with_prepared()`], replacing the
+/// report prepared before.
+///
+/// Formatting isn't async-signal-safe, so CLIs installing signal handlers
+/// prepare the report they'd print before installing the handler, e.g.
+/// `interrupted while writing output.csv`. Reports longer than 1 KiB are
+/// truncated.
+///
+/// Returns the bytes prepared, or the bytes needed if the report was
+/// truncated, like the wrapper's `fmt_into()`.
+///
+/// # Examples
+///
+/// ```rust
+/// let len = resext::prepare_fatal(&"Interrupted while writing output.csv").unwrap();
+///
+/// assert_eq!(len, 37);
+/// ```
+pub fn prepare_fatal(err: &dyn Display) -> Result<usize, usize> {
+    // Rendered before taking the lock, so readers only wait for the copy.
+    let mut report = [0; PREPARED_SIZE];
+    let (len, needed) =
+        crate::buf::fmt_prefix(&format_args!("{}\n", err), &mut report);
+
+    let mut seq = PREPARED.seq.load(Ordering::Relaxed);
+
+    loop {
+        if seq.is_multiple_of(2) {
+            match PREPARED.seq.compare_exchange_weak(
+                seq,
+                seq + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => seq = current,
+            }
+        } else {
+            core::hint::spin_loop();
+            seq = PREPARED.seq.load(Ordering::Relaxed);
+        }
+    }
+
+    // Keeps the byte stores below from being observed before `seq` is odd.
+    fence(Ordering::Release);
+
+    for (slot, &byte) in PREPARED.buf.iter().zip(&report[..len]) {
+        slot.store(byte, Ordering::Relaxed);
+    }
+
+    PREPARED.len.store(len, Ordering::Relaxed);
+    PREPARED.seq.store(seq + 2, Ordering::Release);
+
+    if needed > PREPARED_SIZE { Err(needed) } else { Ok(len) }
+}
+
+/// Copies the prepared report into `report`, returning its length, or `None`
+/// if there is none or it is being replaced.
+///
+/// Only makes atomic loads, so it is async-signal-safe.
+#[doc(hidden)]
+pub fn read_prepared(report: &mut [u8; PREPARED_SIZE]) -> Option<usize> {
+    for _ in 0..READ_ATTEMPTS {
+        let seq = PREPARED.seq.load(Ordering::Acquire);
+
+        if !seq.is_multiple_of(2) {
+            core::hint::spin_loop();
+            continue;
+        }
+
+        let len = PREPARED.len.load(Ordering::Relaxed);
+
+        for (byte, slot) in report.iter_mut().zip(&PREPARED.buf[..len]) {
+            *byte = slot.load(Ordering::Relaxed);
+        }
+
+        // Keeps the loads above from being reordered after the check below.
+        fence(Ordering::Acquire);
+
+        if PREPARED.seq.load(Ordering::Relaxed) == seq {
+            return (len > 0).then_some(len);
+        }
+    }
+
+    None
+}
+
+/// Writes the report set with [`prepare_fatal()`] to stderr and aborts the
+/// process.
+///
+/// Only makes async-signal-safe calls (atomic loads, `write(2)` and
+/// `abort(3)`), so it can be called from a signal handler. Without a
+/// prepared report, or if the signal interrupted [`prepare_fatal()`], it
+/// aborts without writing anything. Reports prepared on another thread
+/// meanwhile are either written whole or not at all.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// resext::prepare_fatal(&"Interrupted while writing output.csv").unwrap();
+///
+/// extern "C" fn on_sigint(_: i32) {
+///     resext::abort_with_prepared();
+/// }
+/// ```
+pub fn abort_with_prepared() -> ! {
+    let mut report = [0; PREPARED_SIZE];
+
+    if let Some(len) = read_prepared(&mut report) {
+        // SAFETY: fd 2 outlives the process, and `ManuallyDrop` keeps it
+        // from being closed. Writing to a `File` is a plain `write(2)`,
+        // unlike `Stderr`, which takes a lock.
+        let mut stderr = core::mem::ManuallyDrop::new(unsafe {
+            std::fs::File::from_raw_fd(2)
+        });
+        let _ = stderr.write_all(&report[..len]);
+    }
+
+    std::process::abort()
+}
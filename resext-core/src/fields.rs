@@ -0,0 +1,61 @@
+use core::fmt::{self, Display, Formatter};
+
+/// Value of a structured field, yielded by `fields()` on generated wrappers.
+///
+/// `fields()` yields the error's metadata as key-value pairs for log,
+/// tracing or metrics backends: `variant`, `code` and `exit_code`, plus
+/// `origin` and `id` if the wrapper has them.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{FieldValue, resext};
+///
+/// #[resext(origin = "storage")]
+/// enum StorageError {
+///     Io(std::io::Error) = 74,
+/// }
+///
+/// let err = std::fs::read("missing.db").context("Failed to open database").unwrap_err();
+/// let fields: Vec<_> = err.fields().collect();
+///
+/// assert_eq!(fields[0], ("variant", FieldValue::Str("Io")));
+/// assert_eq!(fields[1], ("code", FieldValue::Int(74)));
+/// assert!(fields.contains(&("origin", FieldValue::Str("storage"))));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FieldValue<'a> {
+    /// Names, e.g. `variant` and `origin`.
+    Str(&'a str),
+    /// Signed numbers, e.g. `code` and `exit_code`.
+    Int(i64),
+    /// Unsigned numbers, e.g. `id`.
+    UInt(u64),
+}
+
+impl Display for FieldValue<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Str(value) => f.write_str(value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::UInt(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// The variant of an error, implemented by enums generated with `#[resext]`
+/// and their wrappers.
+///
+/// Lets generic code like [`Errors::sorted_by_variant()`](crate::Errors::sorted_by_variant)
+/// group errors without knowing the enum.
+pub trait ErrorVariant {
+    /// Returns the position of the variant in the enum declaration.
+    fn variant_index(&self) -> usize;
+
+    /// Returns the name of the variant as written in the enum declaration.
+    fn variant_name(&self) -> &'static str;
+
+    /// Returns the numeric error code of the variant.
+    fn code(&self) -> i32;
+}
@@ -0,0 +1,411 @@
+use core::fmt::{self, Display, Formatter, Write};
+
+use crate::buf::ContextBuf;
+
+/// Custom rendering for errors generated by `#[resext]`.
+///
+/// Implement this on a marker type and pass it to the proc-macro with
+/// `#[resext(formatter = MyFormatter)]` to replace the generated `Display`
+/// layout. Sharing one formatter type across every error enum is how a
+/// project enforces a single house style without forking the macro.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{Contexts, ErrorFormatter, resext};
+///
+/// struct OneLine;
+///
+/// impl ErrorFormatter for OneLine {
+///     fn format(
+///         contexts: Contexts<'_>,
+///         source: &dyn core::fmt::Display,
+///         f: &mut core::fmt::Formatter,
+///     ) -> core::fmt::Result {
+///         if contexts.is_empty() {
+///             write!(f, "error: {}", source)
+///         } else {
+///             write!(f, "error: {} ({})", contexts, source)
+///         }
+///     }
+/// }
+///
+/// #[resext(formatter = OneLine, delimiter = ", ")]
+/// enum AppError {
+///     Parse(std::num::ParseIntError),
+/// }
+///
+/// let err = "x".parse::<u8>()
+///     .context("Failed to parse port")
+///     .context("Failed to load config")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     err.to_string(),
+///     "error: Failed to parse port, Failed to load config (invalid digit found in string)"
+/// );
+/// ```
+pub trait ErrorFormatter {
+    /// Writes the full error, given its context messages and source error.
+    fn format(
+        contexts: Contexts<'_>,
+        source: &dyn Display,
+        f: &mut Formatter,
+    ) -> fmt::Result;
+}
+
+/// Context messages attached to an error, as passed to [`ErrorFormatter`].
+///
+/// Messages are already joined with the configured `delimiter`,
+/// `msg_prefix` and `msg_suffix`.
+#[derive(Clone, Copy, Debug)]
+pub struct Contexts<'a> {
+    msg: &'a str,
+    truncated: bool,
+    /// With `headline = "last_context"`, the cut message heading the
+    /// contexts, the delimiter and the older messages.
+    cut: Option<(&'a str, &'a str, &'a str)>,
+}
+
+impl<'a> Contexts<'a> {
+    /// Creates a view over already joined context messages.
+    pub fn new(msg: &'a str, truncated: bool) -> Self {
+        Self { msg, truncated, cut: None }
+    }
+
+    /// Creates a view over the context messages in `buf`.
+    ///
+    /// The truncation marker follows the message that was cut off: the last
+    /// one, or the first one with `headline_last`, which writes new messages
+    /// to the front.
+    #[doc(hidden)]
+    pub fn from_buf<B: ContextBuf>(
+        buf: &'a B,
+        delimiter: &'a str,
+        headline_last: bool,
+    ) -> Self {
+        let msg = buf.as_str();
+        let truncated = buf.is_truncated();
+        let ends = buf.frame_ends();
+
+        let cut = if headline_last && truncated && ends.len() > 0 {
+            let end = ends.get(0);
+
+            match (msg.get(..end), msg.get(end..)) {
+                (Some(head), Some(rest)) if !rest.is_empty() => {
+                    Some((strip_delimiter(head, delimiter), delimiter, rest))
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        Self { msg, truncated, cut }
+    }
+
+    /// Returns the joined context messages, without the truncation marker.
+    pub fn as_str(&self) -> &'a str {
+        self.msg
+    }
+
+    /// Returns `true` if no context was attached to the error.
+    pub fn is_empty(&self) -> bool {
+        self.msg.is_empty()
+    }
+
+    /// Returns `true` if context was cut off because the buffer ran out of space.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl Display for Contexts<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some((head, delimiter, rest)) = self.cut {
+            return write!(f, "{}...{}{}", head, delimiter, rest);
+        }
+
+        f.write_str(self.msg)?;
+
+        if self.truncated { f.write_str("...") } else { Ok(()) }
+    }
+}
+
+/// Strips the delimiter written after a `headline_last` message, or the part
+/// of it written before the buffer ran out of space.
+fn strip_delimiter<'a>(frame: &'a str, delimiter: &str) -> &'a str {
+    (1..=delimiter.len())
+        .rev()
+        .filter(|&len| delimiter.is_char_boundary(len))
+        .find_map(|len| frame.strip_suffix(&delimiter[..len]))
+        .unwrap_or(frame)
+}
+
+/// Iterator over the context messages of an error, returned by `contexts()`
+/// on generated wrappers.
+///
+/// Yields each message without the configured `delimiter`, `msg_prefix` and
+/// `msg_suffix`, in display order. The buffer records where each message
+/// ends, so messages containing the delimiter are yielded whole.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext(delimiter = " <- ", msg_prefix = "[", msg_suffix = "]")]
+/// enum AppError {
+///     Parse(std::num::ParseIntError),
+/// }
+///
+/// let err = "x".parse::<u8>()
+///     .context("Failed to parse port")
+///     .context("Failed to load a <- b")
+///     .unwrap_err();
+///
+/// assert!(err.contexts().eq(["Failed to parse port", "Failed to load a <- b"]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ContextFrames<'a> {
+    msg: &'a str,
+    state: FramesState<'a>,
+    delimiter: &'a str,
+    msg_prefix: &'a str,
+    msg_suffix: &'a str,
+    headline_last: bool,
+}
+
+#[derive(Clone, Debug)]
+enum FramesState<'a> {
+    /// Messages between the recorded ends, `front..back` of `count` are left.
+    Recorded { ends: FrameEnds<'a>, count: usize, front: usize, back: usize },
+    /// Messages split at the delimiter, for buffers not recording ends.
+    Split { rest: Option<&'a str> },
+}
+
+/// Ends of the context messages in a buffer, see
+/// [`ContextBuf::frame_ends()`](crate::ContextBuf::frame_ends).
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub enum FrameEnds<'a> {
+    Unknown,
+    U8(&'a [u8]),
+    U16(&'a [u16]),
+    Usize(&'a [usize]),
+}
+
+impl FrameEnds<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Unknown => 0,
+            Self::U8(ends) => ends.len(),
+            Self::U16(ends) => ends.len(),
+            Self::Usize(ends) => ends.len(),
+        }
+    }
+
+    fn get(&self, idx: usize) -> usize {
+        match self {
+            Self::Unknown => 0,
+            Self::U8(ends) => ends[idx] as usize,
+            Self::U16(ends) => ends[idx] as usize,
+            Self::Usize(ends) => ends[idx],
+        }
+    }
+}
+
+impl<'a> ContextFrames<'a> {
+    #[doc(hidden)]
+    pub fn new(
+        msg: &'a str,
+        ends: FrameEnds<'a>,
+        delimiter: &'a str,
+        msg_prefix: &'a str,
+        msg_suffix: &'a str,
+        headline_last: bool,
+    ) -> Self {
+        let state = match ends {
+            FrameEnds::Unknown => {
+                FramesState::Split { rest: (!msg.is_empty()).then_some(msg) }
+            }
+            ends => {
+                let recorded = ends.len();
+                let last = match recorded {
+                    0 => 0,
+                    len => ends.get(len - 1),
+                };
+
+                // Context written past the last recorded end, e.g. with
+                // `write!()` on the wrapper, is one more message.
+                let count = recorded + usize::from(last < msg.len());
+
+                FramesState::Recorded { ends, count, front: 0, back: count }
+            }
+        };
+
+        Self { msg, state, delimiter, msg_prefix, msg_suffix, headline_last }
+    }
+
+    pub(crate) fn delimiter(&self) -> &'a str {
+        self.delimiter
+    }
+
+    /// Returns `true` if new messages come first, so a message cut off by
+    /// truncation is the first one.
+    pub(crate) fn headline_last(&self) -> bool {
+        self.headline_last
+    }
+
+    /// Returns the recorded message at `idx`, without delimiter.
+    fn recorded(
+        &self,
+        ends: FrameEnds<'a>,
+        idx: usize,
+        count: usize,
+    ) -> &'a str {
+        let start = if idx == 0 { 0 } else { ends.get(idx - 1) };
+        let end = if idx < ends.len() { ends.get(idx) } else { self.msg.len() };
+        let frame = self.msg.get(start..end).unwrap_or("");
+
+        // Messages are written delimiter first, or with `headline_last`
+        // delimiter last, except for the first one.
+        if self.headline_last {
+            if idx + 1 < count {
+                strip_delimiter(frame, self.delimiter)
+            } else {
+                frame
+            }
+        } else if idx > 0 {
+            frame.strip_prefix(self.delimiter).unwrap_or(frame)
+        } else {
+            frame
+        }
+    }
+
+    /// Strips the affixes, which the first message written doesn't have.
+    fn strip(&self, frame: &'a str) -> &'a str {
+        let frame = frame.strip_prefix(self.msg_prefix).unwrap_or(frame);
+        frame.strip_suffix(self.msg_suffix).unwrap_or(frame)
+    }
+}
+
+impl<'a> Iterator for ContextFrames<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let frame = match &mut self.state {
+            FramesState::Recorded { ends, count, front, back } => {
+                if front == back {
+                    return None;
+                }
+
+                let (ends, count, idx) = (*ends, *count, *front);
+                *front += 1;
+
+                self.recorded(ends, idx, count)
+            }
+            FramesState::Split { rest } => {
+                match (*rest)?.split_once(self.delimiter) {
+                    Some((frame, tail)) if !self.delimiter.is_empty() => {
+                        *rest = Some(tail);
+                        frame
+                    }
+                    _ => rest.take()?,
+                }
+            }
+        };
+
+        Some(self.strip(frame))
+    }
+}
+
+impl<'a> DoubleEndedIterator for ContextFrames<'a> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        let frame = match &mut self.state {
+            FramesState::Recorded { ends, count, front, back } => {
+                if front == back {
+                    return None;
+                }
+
+                *back -= 1;
+
+                let (ends, count, idx) = (*ends, *count, *back);
+                self.recorded(ends, idx, count)
+            }
+            FramesState::Split { rest } => {
+                match (*rest)?.rsplit_once(self.delimiter) {
+                    Some((head, frame)) if !self.delimiter.is_empty() => {
+                        *rest = Some(head);
+                        frame
+                    }
+                    _ => rest.take()?,
+                }
+            }
+        };
+
+        Some(self.strip(frame))
+    }
+}
+
+impl core::iter::FusedIterator for ContextFrames<'_> {}
+
+/// Writes the error as a JSON object with `error`, `variant` and `contexts`
+/// keys, backing `to_json()` on generated wrappers.
+#[doc(hidden)]
+pub fn fmt_json(
+    w: &mut dyn fmt::Write,
+    source: &dyn Display,
+    variant: &str,
+    contexts: ContextFrames<'_>,
+) -> fmt::Result {
+    w.write_str("{\"error\":\"")?;
+    fmt::write(&mut JsonEscape(w), format_args!("{}", source))?;
+    w.write_str("\",\"variant\":\"")?;
+    JsonEscape(w).write_str(variant)?;
+    w.write_str("\",\"contexts\":[")?;
+
+    for (idx, context) in contexts.enumerate() {
+        if idx > 0 {
+            w.write_char(',')?;
+        }
+
+        w.write_char('"')?;
+        JsonEscape(w).write_str(context)?;
+        w.write_char('"')?;
+    }
+
+    w.write_str("]}")
+}
+
+/// Escapes everything written for use inside a JSON string.
+struct JsonEscape<'a>(&'a mut dyn fmt::Write);
+
+impl fmt::Write for JsonEscape<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+
+        for (idx, byte) in s.bytes().enumerate() {
+            let escaped = match byte {
+                b'"' => "\\\"",
+                b'\\' => "\\\\",
+                b'\n' => "\\n",
+                b'\r' => "\\r",
+                b'\t' => "\\t",
+                0..=0x1F => "",
+                _ => continue,
+            };
+
+            self.0.write_str(&s[start..idx])?;
+            start = idx + 1;
+
+            if escaped.is_empty() {
+                write!(self.0, "\\u{:04x}", byte)?;
+            } else {
+                self.0.write_str(escaped)?;
+            }
+        }
+
+        self.0.write_str(&s[start..])
+    }
+}
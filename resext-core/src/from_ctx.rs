@@ -0,0 +1,141 @@
+use core::fmt::Arguments;
+
+/// Construction of a generated wrapper from a source error and a context
+/// message.
+///
+/// Implemented by `#[resext]` for every error type its enum converts from,
+/// and for the wrapper itself, which appends `msg` to its existing context.
+/// Runtime helpers like [`crate::env::var_ctx()`] are generic over this
+/// trait so they can return any project's wrapper type.
+pub trait FromCtx<E>: Sized {
+    /// Wraps `source`, writing `msg` as its first context message.
+    fn from_ctx(source: E, msg: Arguments<'_>) -> Self;
+}
+
+/// Adds context to any Result whose error converts into a wrapper `W`.
+///
+/// The counterpart to [`FromCtx`] for library code that is generic over the
+/// wrapper type instead of depending on one generated `{alias}Ext` trait.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{FromCtx, IntoResExt, resext};
+///
+/// fn read_config<W: FromCtx<std::io::Error>>(path: &str) -> Result<Vec<u8>, W> {
+///     std::fs::read(path).context_into::<W>("Failed to read config")
+/// }
+///
+/// fn load<W>(path: &str) -> Result<Vec<u8>, W>
+/// where
+///     W: FromCtx<std::io::Error> + FromCtx<W>,
+/// {
+///     read_config::<W>(path).context_into::<W>("Failed to load")
+/// }
+///
+/// #[resext]
+/// enum AppError {
+///     Io(std::io::Error),
+/// }
+///
+/// let err = load::<ResErr>("missing.toml").unwrap_err();
+///
+/// assert!(err.to_string().starts_with("Failed to read config\n - Failed to load\n"));
+/// ```
+pub trait IntoResExt<T, E> {
+    /// Converts the error into `W`, adding `msg` as context.
+    ///
+    /// If `E` already is `W`, `msg` is appended to its existing context.
+    fn context_into<W: FromCtx<E>>(self, msg: &str) -> Result<T, W>;
+}
+
+impl<T, E> IntoResExt<T, E> for Result<T, E> {
+    #[track_caller]
+    fn context_into<W: FromCtx<E>>(self, msg: &str) -> Result<T, W> {
+        match self {
+            Ok(ok) => Ok(ok),
+            Err(err) => Err(W::from_ctx(err, format_args!("{}", msg))),
+        }
+    }
+}
+
+/// Turns `None` into an error converted into a wrapper `W`, with context.
+///
+/// The counterpart to the generated `Opt{alias}Ext` trait for library code
+/// that is generic over the wrapper type, named apart so both can be in
+/// scope.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{FromCtx, OptExt, resext};
+///
+/// #[derive(Debug)]
+/// struct NotFound;
+///
+/// impl core::fmt::Display for NotFound {
+///     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+///         f.write_str("not found")
+///     }
+/// }
+///
+/// impl std::error::Error for NotFound {}
+///
+/// fn port<W: FromCtx<NotFound>>(port: Option<u16>) -> Result<u16, W> {
+///     port.ok_or_context_into::<W, _>("Missing port", NotFound)
+/// }
+///
+/// #[resext]
+/// enum AppError {
+///     NotFound(NotFound),
+/// }
+///
+/// let err = port::<ResErr>(None).unwrap_err();
+///
+/// assert_eq!(err.to_string(), "Missing port\nError: not found");
+/// ```
+pub trait OptExt<T> {
+    /// Turns `None` into `err` converted into `W`, adding `msg` as context.
+    fn ok_or_context_into<W: FromCtx<E>, E>(
+        self,
+        msg: &str,
+        err: E,
+    ) -> Result<T, W>;
+
+    /// Turns `None` into `err` converted into `W`, adding the `Display`
+    /// output of the value `f` returns as context.
+    ///
+    /// `f` is only called on `None`.
+    fn ok_or_with_context_into<W, E, D, F>(self, f: F, err: E) -> Result<T, W>
+    where
+        W: FromCtx<E>,
+        D: core::fmt::Display,
+        F: FnOnce() -> D;
+}
+
+impl<T> OptExt<T> for Option<T> {
+    #[track_caller]
+    fn ok_or_context_into<W: FromCtx<E>, E>(
+        self,
+        msg: &str,
+        err: E,
+    ) -> Result<T, W> {
+        match self {
+            Some(some) => Ok(some),
+            None => Err(W::from_ctx(err, format_args!("{}", msg))),
+        }
+    }
+
+    #[track_caller]
+    fn ok_or_with_context_into<W, E, D, F>(self, f: F, err: E) -> Result<T, W>
+    where
+        W: FromCtx<E>,
+        D: core::fmt::Display,
+        F: FnOnce() -> D,
+    {
+        match self {
+            Some(some) => Ok(some),
+            None => Err(W::from_ctx(err, format_args!("{}", f()))),
+        }
+    }
+}
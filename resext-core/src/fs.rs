@@ -0,0 +1,76 @@
+//! Filesystem helpers that attach the operation and path as context.
+//!
+//! Generic over the wrapper generated by `#[resext]`, whose enum must have a
+//! variant wrapping `std::io::Error`.
+
+use std::io;
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::FromCtx;
+
+/// Reads the whole file at `path`, see [`std::fs::read()`].
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext]
+/// enum ConfigError {
+///     Io(std::io::Error),
+/// }
+///
+/// let err = resext::fs::read_ctx::<ResErr>("missing.toml").unwrap_err();
+///
+/// assert!(err.to_string().starts_with("Failed to read `missing.toml`\nError: "));
+/// ```
+pub fn read_ctx<W: FromCtx<io::Error>>(
+    path: impl AsRef<Path>,
+) -> Result<Vec<u8>, W> {
+    let path = path.as_ref();
+
+    std::fs::read(path).map_err(|err| {
+        W::from_ctx(err, format_args!("Failed to read `{}`", path.display()))
+    })
+}
+
+/// Reads the whole file at `path` into a string, see
+/// [`std::fs::read_to_string()`].
+pub fn read_to_string_ctx<W: FromCtx<io::Error>>(
+    path: impl AsRef<Path>,
+) -> Result<String, W> {
+    let path = path.as_ref();
+
+    std::fs::read_to_string(path).map_err(|err| {
+        W::from_ctx(err, format_args!("Failed to read `{}`", path.display()))
+    })
+}
+
+/// Writes `contents` to the file at `path`, see [`std::fs::write()`].
+pub fn write_ctx<W: FromCtx<io::Error>>(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<(), W> {
+    let path = path.as_ref();
+
+    std::fs::write(path, contents).map_err(|err| {
+        W::from_ctx(err, format_args!("Failed to write `{}`", path.display()))
+    })
+}
+
+/// Creates the directory at `path` and all missing parents, see
+/// [`std::fs::create_dir_all()`].
+pub fn create_dir_all_ctx<W: FromCtx<io::Error>>(
+    path: impl AsRef<Path>,
+) -> Result<(), W> {
+    let path = path.as_ref();
+
+    std::fs::create_dir_all(path).map_err(|err| {
+        W::from_ctx(
+            err,
+            format_args!("Failed to create directory `{}`", path.display()),
+        )
+    })
+}
@@ -0,0 +1,157 @@
+//! Recent errors kept in memory, for "recent errors" views in REPLs and TUIs.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resext::history::ErrorLog;
+//! use resext::resext;
+//!
+//! #[resext]
+//! enum AppError {
+//!     Io(std::io::Error),
+//!     Parse(std::num::ParseIntError),
+//! }
+//!
+//! static LOG: ErrorLog = ErrorLog::new(64);
+//!
+//! for input in ["12", "x", "7y"] {
+//!     if let Err(err) = input.parse::<u16>().context("Failed to parse port") {
+//!         err.log_to(&LOG);
+//!     }
+//! }
+//!
+//! let latest = LOG.latest(1);
+//!
+//! assert_eq!(latest.len(), 1);
+//! assert_eq!(latest[0].variant, Some("Parse"));
+//! assert_eq!(LOG.by_variant("Parse").len(), 2);
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::string::{String, ToString};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+use std::vec::Vec;
+
+use crate::ErrorSink;
+
+/// An error recorded in an [`ErrorLog`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedError {
+    /// The error's `Display` output.
+    pub message: String,
+
+    /// Name of the enum variant, for errors pushed with `.log_to()` on a
+    /// generated wrapper.
+    pub variant: Option<&'static str>,
+
+    /// When the error was recorded.
+    pub timestamp: SystemTime,
+}
+
+/// Fixed-capacity ring of the most recent errors.
+///
+/// Once full, pushing drops the oldest entry. Entries are rendered when
+/// they are pushed, so the log doesn't hold on to the errors themselves.
+///
+/// `&ErrorLog` is an [`ErrorSink`], so a `static` log can also collect the
+/// reports of `.or_exit()` and `report_warnings()` with
+/// [`crate::set_report_sink()`].
+#[derive(Debug)]
+pub struct ErrorLog {
+    capacity: usize,
+    entries: Mutex<VecDeque<LoggedError>>,
+}
+
+impl ErrorLog {
+    /// Creates an empty log keeping up to `capacity` errors.
+    pub const fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(VecDeque::new()) }
+    }
+
+    /// Returns the number of errors the log keeps.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Records `err`, dropping the oldest entry if the log is full.
+    pub fn push(&self, err: &dyn Display) {
+        self.push_entry(None, err);
+    }
+
+    /// Records `err` under the enum variant `variant`.
+    ///
+    /// Called by `.log_to()` on generated wrappers.
+    pub fn push_variant(&self, variant: &'static str, err: &dyn Display) {
+        self.push_entry(Some(variant), err);
+    }
+
+    fn push_entry(&self, variant: Option<&'static str>, err: &dyn Display) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let entry = LoggedError {
+            message: err.to_string(),
+            variant,
+            timestamp: SystemTime::now(),
+        };
+
+        let mut entries = self.lock();
+
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+
+        entries.push_back(entry);
+    }
+
+    /// Returns up to `n` of the most recent errors, newest first.
+    pub fn latest(&self, n: usize) -> Vec<LoggedError> {
+        self.lock().iter().rev().take(n).cloned().collect()
+    }
+
+    /// Returns the errors recorded under the enum variant `variant`, newest
+    /// first.
+    pub fn by_variant(&self, variant: &str) -> Vec<LoggedError> {
+        self.filtered(|entry| entry.variant == Some(variant))
+    }
+
+    /// Returns the errors recorded at or after `timestamp`, newest first.
+    pub fn since(&self, timestamp: SystemTime) -> Vec<LoggedError> {
+        self.filtered(|entry| entry.timestamp >= timestamp)
+    }
+
+    /// Returns the number of errors in the log.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no errors were recorded since the last clear.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes all errors from the log.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn filtered(
+        &self,
+        mut keep: impl FnMut(&LoggedError) -> bool,
+    ) -> Vec<LoggedError> {
+        self.lock().iter().rev().filter(|entry| keep(entry)).cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<LoggedError>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ErrorSink for &ErrorLog {
+    fn report(&mut self, err: &dyn Display) {
+        self.push(err);
+    }
+}
@@ -0,0 +1,59 @@
+use reqwest::{Error, Response};
+
+use crate::FromCtx;
+
+/// Extension trait for turning HTTP failure statuses into typed errors.
+pub trait ResponseExt: Sized {
+    /// Like [`Response::error_for_status()`], but converts failure statuses
+    /// into the generated wrapper with the URL and status as context.
+    ///
+    /// The wrapper's enum needs a variant wrapping `reqwest::Error`.
+    ///
+    /// Note that `reqwest` doesn't keep the request method on a `Response`,
+    /// add it with `.context()` where it matters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use resext::{ResponseExt, resext};
+    ///
+    /// #[resext]
+    /// enum ApiError {
+    ///     Http(reqwest::Error),
+    /// }
+    ///
+    /// async fn fetch_user(client: &reqwest::Client, id: u64) -> Res<reqwest::Response> {
+    ///     client
+    ///         .get(format!("https://example.com/users/{}", id))
+    ///         .send()
+    ///         .await
+    ///         .context("Failed to send request")?
+    ///         .error_for_status_ctx::<ResErr>()
+    ///         .context("Failed to fetch user")
+    /// }
+    /// ```
+    fn error_for_status_ctx<W: FromCtx<Error>>(self) -> Result<Self, W>;
+}
+
+impl ResponseExt for Response {
+    fn error_for_status_ctx<W: FromCtx<Error>>(self) -> Result<Self, W> {
+        let status = self.status();
+
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(self);
+        }
+
+        let url = self.url().clone();
+
+        match self.error_for_status() {
+            Ok(ok) => Ok(ok),
+            Err(err) => Err(W::from_ctx(
+                err,
+                format_args!(
+                    "Request to `{}` failed with status {}",
+                    url, status
+                ),
+            )),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use core::fmt::{self, Debug, Display, Formatter, Write};
+use core::time::Duration;
+
+const BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Formats a byte count with binary units, for use in context messages.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::ctx_bytes;
+///
+/// assert_eq!(ctx_bytes(512).to_string(), "512 B");
+/// assert_eq!(ctx_bytes(1048576).to_string(), "1.0 MiB");
+/// assert_eq!(ctx_bytes(1536).to_string(), "1.5 KiB");
+/// assert_eq!(ctx_bytes(1048575).to_string(), "1.0 MiB");
+/// ```
+///
+/// Works with `ctx!()` without allocating:
+///
+/// ```rust
+/// # use resext::{ctx, ctx_bytes, resext};
+/// # #[resext] enum Error { Io(std::io::Error) }
+/// # fn doctest(data: &[u8]) -> Res<()> {
+/// std::fs::write("out.bin", data)
+///     .context(ctx!("Failed to write {}", ctx_bytes(data.len() as u64)))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn ctx_bytes(bytes: u64) -> HumanBytes {
+    HumanBytes(bytes)
+}
+
+/// Formats a duration with the largest fitting unit, for use in context
+/// messages.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::ctx_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(ctx_duration(Duration::from_micros(250)).to_string(), "250µs");
+/// assert_eq!(ctx_duration(Duration::from_millis(1500)).to_string(), "1.5s");
+/// assert_eq!(ctx_duration(Duration::from_millis(59990)).to_string(), "1m 0s");
+/// assert_eq!(ctx_duration(Duration::from_secs(125)).to_string(), "2m 5s");
+/// assert_eq!(ctx_duration(Duration::from_secs(3720)).to_string(), "1h 2m");
+/// ```
+pub fn ctx_duration(duration: Duration) -> HumanDuration {
+    HumanDuration(duration)
+}
+
+/// Display adapter returned by [`ctx_bytes()`].
+#[derive(Clone, Copy, Debug)]
+pub struct HumanBytes(u64);
+
+impl Display for HumanBytes {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+
+        // Values that round up to 1024.0 move to the next unit.
+        while value >= 1023.95 && unit < BYTE_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        write!(f, "{:.1} {}", value, BYTE_UNITS[unit])
+    }
+}
+
+/// Display adapter returned by [`ctx_duration()`].
+#[derive(Clone, Copy, Debug)]
+pub struct HumanDuration(Duration);
+
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Rounded before picking the unit, so 59.99s shows as 1m 0s rather
+        // than 60.0s.
+        let tenths = (self.0.as_nanos() + 50_000_000) / 100_000_000;
+        let secs = tenths / 10;
+
+        if secs >= 3600 {
+            write!(f, "{}h {}m", secs / 3600, secs % 3600 / 60)
+        } else if secs >= 60 {
+            write!(f, "{}m {}s", secs / 60, secs % 60)
+        } else if self.0.as_secs() >= 1 {
+            write!(f, "{}.{}s", secs, tenths % 10)
+        } else if self.0.as_millis() >= 1 {
+            write!(f, "{}ms", self.0.as_millis())
+        } else if self.0.as_micros() >= 1 {
+            write!(f, "{}µs", self.0.as_micros())
+        } else {
+            write!(f, "{}ns", self.0.as_nanos())
+        }
+    }
+}
+
+/// Renders a variant marked `#[resext(skip_display)]`: its name, plus the
+/// payload's size as `<N bytes>` if it is byte-like.
+pub fn fmt_skipped(
+    f: &mut Formatter,
+    variant: &str,
+    len: Option<usize>,
+) -> fmt::Result {
+    match len {
+        Some(len) => write!(f, "{}: <{} bytes>", variant, len),
+        None => f.write_str(variant),
+    }
+}
+
+/// Renders the `Debug` output of a wrapper's source, cut to `limit` chars
+/// and followed by `… <N chars>` if it is longer, for variants marked
+/// `#[resext(debug_summary = 64)]`.
+#[doc(hidden)]
+pub struct DebugSummary<'a, T: ?Sized>(pub &'a T, pub Option<usize>);
+
+impl<T: Debug + ?Sized> Display for DebugSummary<'_, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let Some(limit) = self.1 else {
+            return write!(f, "{:?}", self.0);
+        };
+
+        let mut summary = Summary { f, limit, len: 0 };
+        write!(summary, "{:?}", self.0)?;
+
+        let len = summary.len;
+
+        if len > limit {
+            write!(f, "… <{} chars>", len)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Forwards the first `limit` chars written to it and counts the rest.
+struct Summary<'a, 'b> {
+    f: &'a mut Formatter<'b>,
+    limit: usize,
+    len: usize,
+}
+
+impl Write for Summary<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let room = self.limit.saturating_sub(self.len);
+
+        if room > 0 {
+            let end = s.char_indices().nth(room).map_or(s.len(), |(i, _)| i);
+            self.f.write_str(&s[..end])?;
+        }
+
+        self.len += s.chars().count();
+        Ok(())
+    }
+}
+
+/// Wraps a `skip_display` variant's payload, which generated code measures
+/// with `(&&PayloadSize(value)).payload_len()`, so payloads implementing
+/// `AsRef<[u8]>` report their length and others fall through.
+#[doc(hidden)]
+pub struct PayloadSize<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait PayloadLen {
+    fn payload_len(&self) -> Option<usize>;
+}
+
+impl<T: AsRef<[u8]> + ?Sized> PayloadLen for &PayloadSize<'_, T> {
+    fn payload_len(&self) -> Option<usize> {
+        Some(self.0.as_ref().len())
+    }
+}
+
+#[doc(hidden)]
+pub trait PayloadOther {
+    fn payload_len(&self) -> Option<usize>;
+}
+
+impl<T: ?Sized> PayloadOther for PayloadSize<'_, T> {
+    fn payload_len(&self) -> Option<usize> {
+        None
+    }
+}
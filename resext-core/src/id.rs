@@ -0,0 +1,10 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Returns the next process-wide error ID, used by the `id = true` option.
+///
+/// IDs start at 1 and wrap around after `u32::MAX`.
+pub fn next_error_id() -> u32 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
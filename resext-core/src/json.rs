@@ -0,0 +1,63 @@
+//! JSON parse helpers that attach the file path and position as context.
+
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Error;
+
+use crate::FromCtx;
+
+/// Parses `content` read from `path` as JSON, adding the path and the
+/// parser's line/column as context on failure.
+///
+/// Generic over the wrapper generated by `#[resext]`, whose enum must have a
+/// variant wrapping `serde_json::Error`.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext]
+/// enum ConfigError {
+///     Json(serde_json::Error),
+/// }
+///
+/// let err = resext::json::from_str_ctx::<serde_json::Value, ResErr>(
+///     "config.json",
+///     "{\n  \"port\": 80,\n  oops\n}",
+/// )
+/// .unwrap_err();
+///
+/// assert!(
+///     err.to_string()
+///         .starts_with("Failed to parse `config.json` at line 3, column 3\nError: ")
+/// );
+/// ```
+pub fn from_str_ctx<'a, T: Deserialize<'a>, W: FromCtx<Error>>(
+    path: impl AsRef<Path>,
+    content: &'a str,
+) -> Result<T, W> {
+    let path = path.as_ref();
+
+    serde_json::from_str(content).map_err(|err| {
+        let (line, column) = (err.line(), err.column());
+
+        if line == 0 {
+            W::from_ctx(
+                err,
+                format_args!("Failed to parse `{}`", path.display()),
+            )
+        } else {
+            W::from_ctx(
+                err,
+                format_args!(
+                    "Failed to parse `{}` at line {}, column {}",
+                    path.display(),
+                    line,
+                    column
+                ),
+            )
+        }
+    })
+}
@@ -0,0 +1,424 @@
+//! Runtime of [ResExt](https://docs.rs/resext).
+//!
+//! This crate holds the buffers, formatting, sinks and extension traits that
+//! the code generated by `#[resext]` builds on. It is not meant to be used
+//! directly - depend on `resext`, which re-exports everything here together
+//! with the procedural macros.
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+mod attach;
+mod buf;
+#[cfg(feature = "anyhow")]
+mod classify;
+#[cfg(feature = "clap")]
+mod cli;
+mod color;
+#[cfg(feature = "std")]
+pub mod env;
+#[cfg(feature = "alloc")]
+mod errors;
+#[cfg(feature = "std")]
+mod exit;
+pub mod exitcode;
+#[cfg(all(feature = "std", unix))]
+mod fatal;
+mod fields;
+mod formatter;
+mod from_ctx;
+#[cfg(feature = "std")]
+pub mod fs;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "reqwest")]
+mod http;
+mod human;
+#[cfg(target_has_atomic = "32")]
+mod id;
+#[cfg(feature = "serde_json")]
+pub mod json;
+#[cfg(feature = "std")]
+mod normalize;
+#[cfg(feature = "error-stack")]
+mod report;
+#[cfg(feature = "std")]
+mod sampler;
+#[cfg(feature = "serde")]
+mod ser;
+mod sink;
+mod source;
+mod static_context;
+#[cfg(feature = "diagnostics")]
+mod stats;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "tui")]
+mod tui;
+mod user_message;
+#[cfg(feature = "alloc")]
+mod warnings;
+
+pub use buf::ContextBuf;
+#[cfg(feature = "alloc")]
+pub use buf::SpillStorage;
+#[cfg(feature = "anyhow")]
+pub use classify::{Classify, FromAnyhow};
+#[cfg(feature = "clap")]
+pub use cli::ToClapError;
+#[cfg(feature = "alloc")]
+pub use errors::{CollectErrors, Errors};
+#[cfg(feature = "std")]
+pub use exit::{BuildInfo, ExitPolicy, MainResult};
+#[cfg(all(feature = "std", unix))]
+pub use fatal::{abort_with_prepared, prepare_fatal};
+pub use fields::{ErrorVariant, FieldValue};
+pub use formatter::{ContextFrames, Contexts, ErrorFormatter};
+pub use from_ctx::{FromCtx, IntoResExt, OptExt};
+#[cfg(feature = "reqwest")]
+pub use http::ResponseExt;
+pub use human::{HumanBytes, HumanDuration, ctx_bytes, ctx_duration};
+#[cfg(feature = "std")]
+pub use normalize::{Normalize, Normalized};
+#[cfg(feature = "std")]
+pub use sampler::Sampler;
+#[cfg(feature = "web")]
+pub use sink::Console;
+pub use sink::ErrorSink;
+#[cfg(feature = "std")]
+pub use sink::{IoSink, Stderr, reset_report_sink, set_report_sink};
+pub use static_context::StaticContext;
+#[cfg(feature = "diagnostics")]
+pub use stats::ErrorStats;
+pub use user_message::UserMessage;
+#[cfg(feature = "alloc")]
+pub use warnings::ResWithWarnings;
+
+/// Runtime items referenced by the code generated with `#[resext]`.
+///
+/// Not public API, may change between any releases.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::buf::{
+        ContextBuf, InlineBuf, display_len, fmt_annotations, fmt_compact,
+        fmt_error, fmt_into, fmt_padded, fmt_related,
+    };
+
+    pub use crate::color::{
+        ColorReport, DisplayFn, ReportColored, ReportDisplay, ReportPlain,
+        ReportProbe, fmt_colored,
+    };
+
+    #[cfg(feature = "std")]
+    pub use crate::color::use_color;
+
+    pub use crate::formatter::{FrameEnds, fmt_json};
+
+    #[cfg(target_has_atomic = "32")]
+    pub use crate::id::next_error_id;
+
+    #[cfg(feature = "alloc")]
+    pub use crate::attach::Attachments;
+
+    #[cfg(feature = "alloc")]
+    pub use crate::buf::SpillBuf;
+
+    #[cfg(feature = "alloc")]
+    pub use alloc::string::{String, ToString};
+
+    #[cfg(feature = "alloc")]
+    pub use alloc::vec::Vec;
+
+    #[cfg(feature = "alloc")]
+    pub use alloc::borrow::Cow;
+
+    #[cfg(feature = "anyhow")]
+    pub use anyhow;
+
+    #[cfg(feature = "error-stack")]
+    pub use crate::report::into_report;
+
+    #[cfg(feature = "error-stack")]
+    pub use error_stack;
+
+    #[cfg(feature = "std")]
+    pub use crate::exit::exit;
+
+    #[cfg(feature = "std")]
+    pub use crate::sink::report;
+
+    #[cfg(all(feature = "std", unix))]
+    pub use crate::fatal::read_prepared;
+
+    pub use crate::exitcode::{SysexitError, SysexitOther, SysexitProbe};
+
+    pub use crate::source::{SourceError, SourceOther, SourceProbe};
+
+    pub use crate::human::{
+        DebugSummary, PayloadLen, PayloadOther, PayloadSize, fmt_skipped,
+    };
+
+    #[cfg(feature = "serde")]
+    pub use crate::ser::SerializeDisplay;
+
+    #[cfg(feature = "serde")]
+    pub use serde;
+
+    #[cfg(feature = "tui")]
+    pub use crate::tui::error_text;
+
+    #[cfg(feature = "tui")]
+    pub use ratatui_core;
+
+    #[cfg(feature = "std")]
+    pub use crate::buf::warn_truncated;
+
+    #[cfg(feature = "std")]
+    pub use std::path::Path;
+
+    #[cfg(feature = "backtrace")]
+    pub use crate::buf::{capture_backtrace, fmt_backtrace};
+
+    #[cfg(feature = "backtrace")]
+    pub use alloc::boxed::Box;
+
+    #[cfg(feature = "backtrace")]
+    pub use std::backtrace::Backtrace;
+}
+
+#[doc(hidden)]
+pub struct Writer<W: core::fmt::Write + ?Sized>(pub W);
+
+impl<W: core::fmt::Write + ?Sized> core::fmt::Write for Writer<W> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0.write_str(s)
+    }
+}
+
+/// Creates a lazily-evaluated context message for use with `.context()`.
+///
+/// Takes a format string and optional arguments identical to `write!` or `format_args!`,
+/// but only evaluates and writes the message if an error actually occurs.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+/// use resext::ctx;
+///
+/// #[resext]
+/// enum FileError {
+///     Io(std::io::Error),
+///     Utf8(std::string::FromUtf8Error),
+/// }
+///
+/// fn read_file(path: &str) -> Res<String> {
+///     let content = std::fs::read(path)
+///         .context(ctx!("Failed to read file: {}", path))?;
+///
+///     String::from_utf8(content)
+///         .context(ctx!("Failed to parse file: {}", path))
+/// }
+/// ```
+///
+/// Static messages without arguments are also supported, but it is encouraged
+/// to use raw `&str`:
+///
+/// ```rust
+/// # use resext::resext;
+/// # use resext::ctx;
+/// # #[resext] enum Err { Io(std::io::Error) }
+/// # fn doctest() -> Res<()> {
+/// std::fs::read_to_string("config.toml")
+///     .context(ctx!("Failed to read config"))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Note
+///
+/// This macro must be used with `.context()` method generated by `#[resext]`.
+/// It cannot be used standalone.
+#[macro_export]
+macro_rules! ctx {
+    ($fmt:expr, $($args:tt)*) => {
+        {
+            |w, d, mp, ms| {
+                use core::fmt::Write;
+
+                let mut w = $crate::Writer(w);
+
+                let _ = w.write_str(d);
+                let _ = w.write_str(mp);
+                let _ = write!(w, $fmt, $($args)*);
+                let _ = w.write_str(ms);
+
+                w.0
+            }
+        }
+    };
+
+    ($fmt:expr) => {
+        {
+            |w, d, mp, ms| {
+                use core::fmt::Write;
+
+                let mut w = $crate::Writer(w);
+
+                let _ = w.write_str(d);
+                let _ = w.write_str(mp);
+                let _ = write!(w, $fmt);
+                let _ = w.write_str(ms);
+
+                w.0
+            }
+        }
+    };
+}
+
+/// Creates a context message that is checked against the wrapper's buffer at
+/// compile time.
+///
+/// With the inline buffer, messages longer than `buf_size` are cut off at
+/// runtime. `static_context!()` makes that a build error for literal messages
+/// instead, counting the `delimiter`, `msg_prefix` and `msg_suffix` written
+/// with each message. Buffers that spill (`alloc = true`) or grow never fail
+/// the check.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{resext, static_context};
+///
+/// #[resext(buf_size = 32)]
+/// enum AppError {
+///     Io(std::io::Error),
+/// }
+///
+/// let err = std::fs::read("missing.txt")
+///     .context(static_context!("Failed to read missing.txt"))
+///     .unwrap_err();
+///
+/// assert!(err.to_string().starts_with("Failed to read missing.txt\n"));
+/// ```
+///
+/// Messages that don't fit fail to build:
+///
+/// ```rust,compile_fail
+/// use resext::{resext, static_context};
+///
+/// #[resext(buf_size = 16)]
+/// enum AppError {
+///     Io(std::io::Error),
+/// }
+///
+/// let res = std::fs::read("missing.txt")
+///     .context(static_context!("Failed to read missing.txt"));
+/// ```
+#[macro_export]
+macro_rules! static_context {
+    ($msg:literal) => {
+        $crate::StaticContext::<{ $msg.len() }>::new($msg)
+    };
+}
+
+/// Returns early with an error if a condition doesn't hold.
+///
+/// `ensure!(cond, source, "msg {}", arg)` returns `Err` with `source` wrapped
+/// in the function's wrapper type and the formatted message as its first
+/// context, through [`FromCtx`]. Unlike `.or_exit()` it leaves the decision
+/// to the caller, so it fits library code.
+///
+/// Wrappers always carry a source error, so the macro takes one besides the
+/// message, e.g. a unit variant describing the failed check.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{ensure, resext};
+///
+/// #[resext]
+/// enum AppError {
+///     Io(std::io::Error),
+///     #[resext(display = "invalid config")]
+///     InvalidConfig,
+/// }
+///
+/// fn workers(count: usize) -> Res<usize> {
+///     ensure!(count > 0, AppError::InvalidConfig, "Expected at least 1 worker, got {}", count);
+///
+///     Ok(count)
+/// }
+///
+/// assert_eq!(
+///     workers(0).unwrap_err().to_string(),
+///     "Expected at least 1 worker, got 0\nError: invalid config"
+/// );
+/// assert_eq!(workers(4).unwrap(), 4);
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $source:expr, $($msg:tt)+) => {
+        if !$cond {
+            $crate::bail!($source, $($msg)+);
+        }
+    };
+}
+
+/// Returns early with an error.
+///
+/// `bail!(source, "msg {}", arg)` returns `Err` with `source` wrapped in the
+/// function's wrapper type and the formatted message as its first context,
+/// through [`FromCtx`]. The source is usually a variant of the enum, e.g. a
+/// unit variant for a hand-raised error. Without a message the error has no
+/// context.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{bail, resext};
+///
+/// #[resext]
+/// enum AppError {
+///     Io(std::io::Error),
+///     #[resext(display = "timed out")]
+///     Timeout,
+/// }
+///
+/// fn wait(secs: u64) -> Res<()> {
+///     if secs > 30 {
+///         bail!(AppError::Timeout, "Gave up after {}s", secs);
+///     }
+///
+///     Ok(())
+/// }
+///
+/// assert_eq!(
+///     wait(60).unwrap_err().to_string(),
+///     "Gave up after 60s\nError: timed out"
+/// );
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($source:expr $(,)?) => {
+        return ::core::result::Result::Err($crate::FromCtx::from_ctx(
+            $source,
+            format_args!(""),
+        ))
+    };
+
+    ($source:expr, $($msg:tt)+) => {
+        return ::core::result::Result::Err($crate::FromCtx::from_ctx(
+            $source,
+            format_args!($($msg)+),
+        ))
+    };
+}
@@ -0,0 +1,159 @@
+use alloc::string::String;
+use core::fmt::{self, Display, Write};
+
+/// Extension trait for rendering errors without volatile details.
+///
+/// Implemented for everything that implements `Display`, so it works on the
+/// wrapper generated by `#[resext]` as well as on any other error type.
+pub trait Normalize: Display {
+    /// Returns a display adapter that replaces volatile parts of the output
+    /// with stable placeholders:
+    ///
+    /// - the user's home directory is replaced with `~`
+    /// - hex addresses (`0x7ffd5e8c`) are replaced with `[addr]`
+    /// - durations (`250ms`, `1.5s`) are replaced with `[duration]`
+    ///
+    /// This keeps snapshot tests of error output from churning between runs
+    /// and machines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resext::Normalize;
+    ///
+    /// let msg = "Timed out after 1.5s at 0x7ffd5e8c";
+    ///
+    /// assert_eq!(
+    ///     msg.normalized().to_string(),
+    ///     "Timed out after [duration] at [addr]"
+    /// );
+    /// ```
+    fn normalized(&self) -> Normalized<'_, Self> {
+        Normalized { inner: self, hook: |_| {} }
+    }
+}
+
+impl<T: Display + ?Sized> Normalize for T {}
+
+/// Display adapter returned by [`Normalize::normalized()`].
+pub struct Normalized<'a, D: ?Sized, F = fn(&mut String)> {
+    inner: &'a D,
+    hook: F,
+}
+
+impl<'a, D: Display + ?Sized, F: Fn(&mut String)> Normalized<'a, D, F> {
+    /// Adds a user-supplied pass that runs after the built-in replacements,
+    /// for volatile parts only the caller knows about (request ids, temp dirs).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resext::Normalize;
+    ///
+    /// let msg = "Request req-8841 failed";
+    /// let out = msg
+    ///     .normalized()
+    ///     .with(|s| *s = s.replace("req-8841", "[request]"))
+    ///     .to_string();
+    ///
+    /// assert_eq!(out, "Request [request] failed");
+    /// ```
+    pub fn with<G: Fn(&mut String)>(self, hook: G) -> Normalized<'a, D, G> {
+        Normalized { inner: self.inner, hook }
+    }
+}
+
+impl<D: Display + ?Sized, F: Fn(&mut String)> Display for Normalized<'_, D, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut raw = String::new();
+        write!(raw, "{}", self.inner)?;
+
+        if let Some(home) = home_dir() {
+            raw = raw.replace(&home, "~");
+        }
+
+        let mut out = replace_volatile(&raw);
+        (self.hook)(&mut out);
+
+        f.write_str(&out)
+    }
+}
+
+fn home_dir() -> Option<String> {
+    let home =
+        std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).ok()?;
+
+    let home = home.trim_end_matches(['/', '\\']);
+
+    if home.is_empty() { None } else { Some(String::from(home)) }
+}
+
+const DURATION_UNITS: [&str; 5] = ["ns", "µs", "us", "ms", "s"];
+
+fn replace_volatile(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    let mut at_boundary = true;
+
+    while let Some(c) = rest.chars().next() {
+        if at_boundary {
+            if let Some(len) = hex_addr_len(rest) {
+                out.push_str("[addr]");
+                rest = &rest[len..];
+                at_boundary = false;
+                continue;
+            }
+
+            if let Some(len) = duration_len(rest) {
+                out.push_str("[duration]");
+                rest = &rest[len..];
+                at_boundary = false;
+                continue;
+            }
+        }
+
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+        at_boundary = !is_word_char(c);
+    }
+
+    out
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn ends_word(rest: &str) -> bool {
+    rest.chars().next().is_none_or(|c| !is_word_char(c))
+}
+
+fn hex_addr_len(s: &str) -> Option<usize> {
+    let digits = s.strip_prefix("0x")?;
+    let len = digits.bytes().take_while(u8::is_ascii_hexdigit).count();
+
+    (len > 0 && ends_word(&digits[len..])).then_some(2 + len)
+}
+
+fn duration_len(s: &str) -> Option<usize> {
+    let int_len = s.bytes().take_while(u8::is_ascii_digit).count();
+
+    if int_len == 0 {
+        return None;
+    }
+
+    let mut len = int_len;
+
+    if let Some(frac) = s[len..].strip_prefix('.') {
+        let frac_len = frac.bytes().take_while(u8::is_ascii_digit).count();
+
+        if frac_len > 0 {
+            len += 1 + frac_len;
+        }
+    }
+
+    DURATION_UNITS.iter().find_map(|unit| {
+        let after = s[len..].strip_prefix(unit)?;
+        ends_word(after).then_some(len + unit.len())
+    })
+}
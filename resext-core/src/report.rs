@@ -0,0 +1,47 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+
+use error_stack::Report;
+
+use crate::ContextFrames;
+
+/// Builds the `error_stack::Report` returned by `into_error_stack()` on
+/// generated wrappers.
+///
+/// The context messages are attached innermost first so the outermost
+/// message ends up on top of the report.
+#[track_caller]
+pub fn into_report<C>(
+    context: C,
+    contexts: ContextFrames<'_>,
+    truncated: bool,
+    headline_last: bool,
+) -> Report<C>
+where
+    C: Error + Send + Sync + 'static,
+{
+    let mut report = Report::new(context);
+    let mut messages: Vec<String> = contexts.map(String::from).collect();
+
+    // Like Display, put `...` after the message that was cut off, which
+    // `headline_last` writes to the front.
+    let cut = match headline_last {
+        true => messages.first_mut(),
+        false => messages.last_mut(),
+    };
+
+    if truncated && let Some(msg) = cut {
+        msg.push_str("...");
+    }
+
+    if headline_last {
+        messages.reverse();
+    }
+
+    for msg in messages {
+        report = report.attach(msg);
+    }
+
+    report
+}
@@ -0,0 +1,76 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Rate limiter for reporting errors from hot loops.
+///
+/// `.on_err_sampled()` on the generated `{alias}Ops` trait only calls its
+/// hook while the sampler admits errors. Errors over the limit are counted
+/// instead of formatted, and the count can be reported as a summary.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::Sampler;
+///
+/// let sampler = Sampler::per_second(2);
+///
+/// assert!(sampler.admit());
+/// assert!(sampler.admit());
+/// assert!(!sampler.admit());
+///
+/// assert_eq!(sampler.take_suppressed(), 1);
+/// assert_eq!(sampler.take_suppressed(), 0);
+/// ```
+#[derive(Debug)]
+pub struct Sampler {
+    limit: u32,
+    period: Duration,
+    window: Mutex<(Instant, u32)>,
+    suppressed: AtomicU64,
+}
+
+impl Sampler {
+    /// Creates a sampler admitting up to `limit` errors per `period`.
+    pub fn new(limit: u32, period: Duration) -> Self {
+        Self {
+            limit,
+            period,
+            window: Mutex::new((Instant::now(), 0)),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a sampler admitting up to `limit` errors per second.
+    pub fn per_second(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(1))
+    }
+
+    /// Returns `true` if an error should be reported, or counts it as
+    /// suppressed and returns `false` once the limit is reached.
+    pub fn admit(&self) -> bool {
+        let now = Instant::now();
+        let mut window = match self.window.lock() {
+            Ok(window) => window,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if now.duration_since(window.0) >= self.period {
+            *window = (now, 0);
+        }
+
+        if window.1 < self.limit {
+            window.1 += 1;
+            true
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            false
+        }
+    }
+
+    /// Returns the number of errors suppressed since the last call and
+    /// resets it, e.g. for a periodic `"N errors suppressed"` log line.
+    pub fn take_suppressed(&self) -> u64 {
+        self.suppressed.swap(0, Ordering::Relaxed)
+    }
+}
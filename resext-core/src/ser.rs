@@ -0,0 +1,31 @@
+//! `serde` support for generated wrappers.
+
+use core::fmt::Display;
+
+use serde::{Serialize, Serializer};
+
+use crate::ContextFrames;
+
+/// Serializes the context messages as a sequence of strings.
+impl Serialize for ContextFrames<'_> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.clone())
+    }
+}
+
+/// Serializes a value as its `Display` output, without allocating a
+/// `String` where the serializer supports it.
+#[doc(hidden)]
+pub struct SerializeDisplay<'a>(pub &'a dyn Display);
+
+impl Serialize for SerializeDisplay<'_> {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self.0)
+    }
+}
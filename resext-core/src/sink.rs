@@ -0,0 +1,126 @@
+use core::fmt::{Display, Write};
+
+/// Destination for error reports.
+///
+/// Lets reporting work without `std`: any [`core::fmt::Write`] (a UART
+/// writer, a `String`, a log buffer) is a sink, and with the `std` feature
+/// [`Stderr`] is what `.or_exit()` and `#[resext::main]` print to.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{ErrorSink, Errors};
+///
+/// let errors: Errors<&str> = ["Record 1 is empty", "Record 4 is empty"].into_iter().collect();
+/// let mut out = String::new();
+///
+/// errors.report_to(&mut out);
+///
+/// assert_eq!(out, "Record 1 is empty\nRecord 4 is empty\n");
+/// ```
+pub trait ErrorSink {
+    /// Writes `err` followed by a newline.
+    fn report(&mut self, err: &dyn Display);
+}
+
+impl<W: Write> ErrorSink for W {
+    fn report(&mut self, err: &dyn Display) {
+        let _ = writeln!(self, "{}", err);
+    }
+}
+
+/// Sink printing to the browser's developer console with `console.error`.
+///
+/// With the `web` feature on `wasm32`, `.or_exit()` and `#[resext::main]`
+/// report to it instead of stderr, which browsers don't show.
+#[cfg(feature = "web")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Console;
+
+#[cfg(feature = "web")]
+impl ErrorSink for Console {
+    fn report(&mut self, err: &dyn Display) {
+        use alloc::string::ToString;
+
+        let msg = wasm_bindgen::JsValue::from_str(&err.to_string());
+        web_sys::console::error_1(&msg);
+    }
+}
+
+/// Sink printing to the process's standard error.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stderr;
+
+#[cfg(feature = "std")]
+impl ErrorSink for Stderr {
+    fn report(&mut self, err: &dyn Display) {
+        std::eprintln!("{}", err);
+    }
+}
+
+/// Sink writing to any [`std::io::Write`], e.g. a log file.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use resext::IoSink;
+///
+/// let log = std::fs::File::create("fatal.log").unwrap();
+///
+/// resext::set_report_sink(IoSink(log));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct IoSink<W>(pub W);
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> ErrorSink for IoSink<W> {
+    fn report(&mut self, err: &dyn Display) {
+        let _ = writeln!(self.0, "{}", err);
+        let _ = self.0.flush();
+    }
+}
+
+#[cfg(feature = "std")]
+type BoxedSink = std::boxed::Box<dyn ErrorSink + Send>;
+
+#[cfg(feature = "std")]
+static REPORT_SINK: std::sync::Mutex<Option<BoxedSink>> =
+    std::sync::Mutex::new(None);
+
+/// Redirects the crate's own output, fatal reports of `.or_exit()` and
+/// `#[resext::main]`, `report_warnings()` and truncation warnings, to `sink`
+/// for the rest of the process.
+///
+/// Useful for writing fatal errors to a log file, or capturing them in a
+/// buffer in tests.
+#[cfg(feature = "std")]
+pub fn set_report_sink<S: ErrorSink + Send + 'static>(sink: S) {
+    *lock_report_sink() = Some(std::boxed::Box::new(sink));
+}
+
+/// Restores the default output of [`set_report_sink()`]: stderr, or the
+/// browser's console with the `web` feature on `wasm32`.
+#[cfg(feature = "std")]
+pub fn reset_report_sink() {
+    *lock_report_sink() = None;
+}
+
+#[cfg(feature = "std")]
+fn lock_report_sink() -> std::sync::MutexGuard<'static, Option<BoxedSink>> {
+    REPORT_SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Reports `err` to the sink set with [`set_report_sink()`], or the default.
+#[cfg(feature = "std")]
+pub fn report(err: &dyn Display) {
+    match lock_report_sink().as_mut() {
+        Some(sink) => sink.report(err),
+
+        #[cfg(all(feature = "web", target_arch = "wasm32"))]
+        None => Console.report(err),
+        #[cfg(not(all(feature = "web", target_arch = "wasm32")))]
+        None => Stderr.report(err),
+    }
+}
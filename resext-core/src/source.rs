@@ -0,0 +1,46 @@
+//! Source lookup for the `Error::source()` impl of generated enums.
+
+use core::error::Error;
+
+/// Autoref specialization for variant fields: the generated `source()` calls
+/// `(&&SourceProbe(value)).source()` so fields implementing `Error` become
+/// the enum's source and others are skipped.
+///
+/// Variants displaying their field call `.transparent()` instead, which
+/// skips to the field's own source since its message is already shown.
+#[doc(hidden)]
+pub struct SourceProbe<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait SourceError<'a> {
+    fn source(&self) -> Option<&'a (dyn Error + 'static)>;
+
+    fn transparent(&self) -> Option<&'a (dyn Error + 'static)>;
+}
+
+impl<'a, T: Error + 'static> SourceError<'a> for &SourceProbe<'a, T> {
+    fn source(&self) -> Option<&'a (dyn Error + 'static)> {
+        Some(self.0)
+    }
+
+    fn transparent(&self) -> Option<&'a (dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[doc(hidden)]
+pub trait SourceOther<'a> {
+    fn source(&self) -> Option<&'a (dyn Error + 'static)>;
+
+    fn transparent(&self) -> Option<&'a (dyn Error + 'static)>;
+}
+
+impl<'a, T: ?Sized> SourceOther<'a> for SourceProbe<'a, T> {
+    fn source(&self) -> Option<&'a (dyn Error + 'static)> {
+        None
+    }
+
+    fn transparent(&self) -> Option<&'a (dyn Error + 'static)> {
+        None
+    }
+}
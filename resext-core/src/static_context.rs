@@ -0,0 +1,21 @@
+/// Context message whose length is known at compile time, created with
+/// [`static_context!()`](crate::static_context).
+///
+/// `.context()` with a `StaticContext` fails to build if the message can't fit
+/// the wrapper's context buffer, instead of being truncated at runtime.
+#[derive(Clone, Copy, Debug)]
+pub struct StaticContext<const LEN: usize>(&'static str);
+
+impl<const LEN: usize> StaticContext<LEN> {
+    #[doc(hidden)]
+    pub const fn new(msg: &'static str) -> Self {
+        assert!(msg.len() == LEN, "`LEN` must be the length of `msg`");
+
+        Self(msg)
+    }
+
+    /// Returns the message.
+    pub const fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
@@ -0,0 +1,36 @@
+/// Counters collected while context is attached to an error.
+///
+/// Returned by `.stats()` on generated wrappers with the `diagnostics`
+/// feature. Meant for tuning `buf_size` in development builds: frequent
+/// truncations or spills mean the buffer is too small for typical messages.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext(buf_size = 8)]
+/// enum Error {
+///     Io(std::io::Error),
+/// }
+///
+/// let err = std::fs::read("missing.txt")
+///     .context("Failed to read file")
+///     .unwrap_err();
+///
+/// let stats = err.stats();
+///
+/// assert_eq!(stats.contexts, 1);
+/// assert_eq!(stats.truncations, 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ErrorStats {
+    /// Number of context messages attached.
+    pub contexts: usize,
+
+    /// Number of writes cut off because the inline buffer was full.
+    pub truncations: usize,
+
+    /// Number of times the buffer moved to the heap (`alloc = true`).
+    pub spills: usize,
+}
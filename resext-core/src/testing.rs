@@ -0,0 +1,68 @@
+//! Helpers for testing code that exits the process.
+
+use std::process::Command;
+use std::string::String;
+
+/// Set in the subprocess spawned by [`expect_exit()`].
+pub(crate) const CHILD_ENV: &str = "__RESEXT_EXPECT_EXIT";
+
+/// Runs `f` in a subprocess and asserts that it exits with `code`, returning
+/// what it printed to stderr.
+///
+/// The subprocess re-runs the current test binary filtered to the calling
+/// test, so this must be called from the test's own thread (i.e. not from a
+/// thread spawned by the test). `.or_exit()` exits for real in the
+/// subprocess, even with the `test-no-exit` feature enabled.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use resext::{resext, testing::expect_exit};
+///
+/// #[resext]
+/// enum CliError {
+///     Io(std::io::Error),
+/// }
+///
+/// #[test]
+/// fn missing_config_exits() {
+///     let stderr = expect_exit(78, || {
+///         std::fs::read("missing.toml")
+///             .context("Failed to read config")
+///             .or_exit(78);
+///     });
+///
+///     assert!(stderr.contains("Failed to read config"));
+/// }
+/// ```
+#[track_caller]
+pub fn expect_exit<F: FnOnce()>(code: i32, f: F) -> String {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        f();
+        panic!("expected the process to exit with code {}", code);
+    }
+
+    let thread = std::thread::current();
+    let test = thread
+        .name()
+        .filter(|name| *name != "main")
+        .expect("`expect_exit()` must be called from a test's own thread");
+
+    let exe = std::env::current_exe().expect("failed to locate test binary");
+    let output = Command::new(exe)
+        .args([test, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD_ENV, "1")
+        .output()
+        .expect("failed to spawn test binary");
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+    assert_eq!(
+        output.status.code(),
+        Some(code),
+        "unexpected exit status, stderr:\n{}",
+        stderr
+    );
+
+    stderr
+}
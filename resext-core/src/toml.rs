@@ -0,0 +1,70 @@
+//! TOML parse helpers that attach the file path and position as context.
+
+use std::path::Path;
+
+use ::toml::de::Error;
+use serde::de::DeserializeOwned;
+
+use crate::FromCtx;
+
+/// Parses `content` read from `path` as TOML, adding the path and the
+/// parser's line/column as context on failure.
+///
+/// Generic over the wrapper generated by `#[resext]`, whose enum must have a
+/// variant wrapping `toml::de::Error`.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext]
+/// enum ConfigError {
+///     Toml(toml::de::Error),
+/// }
+///
+/// let err = resext::toml::from_str_ctx::<toml::Table, ResErr>(
+///     "config.toml",
+///     "port = 80\nname = \n",
+/// )
+/// .unwrap_err();
+///
+/// assert!(
+///     err.to_string()
+///         .starts_with("Failed to parse `config.toml` at line 2, column 8\nError: ")
+/// );
+/// ```
+pub fn from_str_ctx<T: DeserializeOwned, W: FromCtx<Error>>(
+    path: impl AsRef<Path>,
+    content: &str,
+) -> Result<T, W> {
+    let path = path.as_ref();
+
+    ::toml::from_str(content).map_err(|err: Error| {
+        match err.span().map(|span| line_column(content, span.start)) {
+            Some((line, column)) => W::from_ctx(
+                err,
+                format_args!(
+                    "Failed to parse `{}` at line {}, column {}",
+                    path.display(),
+                    line,
+                    column
+                ),
+            ),
+            None => W::from_ctx(
+                err,
+                format_args!("Failed to parse `{}`", path.display()),
+            ),
+        }
+    })
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column =
+        before.rsplit('\n').next().map_or(0, |line| line.chars().count()) + 1;
+
+    (line, column)
+}
@@ -0,0 +1,46 @@
+//! ratatui rendering of generated wrappers.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use ratatui_core::style::{Color, Modifier, Style};
+use ratatui_core::text::{Line, Span, Text};
+
+use crate::ContextFrames;
+
+/// Builds the styled text behind `to_text()` on generated wrappers.
+///
+/// Every context message gets its own cyan line, followed by the source
+/// error with its prefix in bold red, like the colored `Display` output.
+pub fn error_text<'a>(
+    contexts: ContextFrames<'a>,
+    truncated: bool,
+    source_prefix: &'a str,
+    source: &dyn Display,
+) -> Text<'a> {
+    let mut lines: Vec<Line<'a>> = contexts
+        .map(|context| Line::styled(context, Style::new().fg(Color::Cyan)))
+        .collect();
+
+    if truncated && let Some(last) = lines.last_mut() {
+        last.push_span(Span::styled(
+            "...",
+            Style::new().add_modifier(Modifier::DIM),
+        ));
+    }
+
+    let source = source.to_string();
+    let mut source_lines = source.lines();
+
+    lines.push(Line::from(alloc::vec![
+        Span::styled(
+            source_prefix,
+            Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(source_lines.next().unwrap_or_default().to_string()),
+    ]));
+    lines.extend(source_lines.map(|line| Line::raw(line.to_string())));
+
+    Text::from(lines)
+}
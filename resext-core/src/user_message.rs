@@ -0,0 +1,67 @@
+use core::fmt::{self, Display, Formatter};
+
+/// The part of an error meant for end users, returned by `user_message()` on
+/// wrappers generated with `user_messages = true`.
+///
+/// Displays the context added with `.user_context()`, followed by the source
+/// error on its own line if its variant is marked `#[resext(user_facing)]`.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::resext;
+///
+/// #[resext(user_messages = true)]
+/// enum AppError {
+///     Io(std::io::Error),
+///     #[resext(user_facing)]
+///     Quota(&'static str),
+/// }
+///
+/// let err = std::fs::read("/etc/app/settings.toml")
+///     .context("Failed to read /etc/app/settings.toml")
+///     .user_context("Could not load your settings")
+///     .unwrap_err();
+///
+/// assert_eq!(err.user_message().to_string(), "Could not load your settings");
+/// assert!(err.to_string().starts_with("Failed to read /etc/app/settings.toml"));
+///
+/// let err = Err::<(), _>("You have used all of your storage")
+///     .user_context("Could not save the file")
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     err.user_message().to_string(),
+///     "Could not save the file\nYou have used all of your storage"
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct UserMessage<'a> {
+    contexts: &'a str,
+    source: Option<&'a dyn Display>,
+}
+
+impl<'a> UserMessage<'a> {
+    #[doc(hidden)]
+    pub fn new(contexts: &'a str, source: Option<&'a dyn Display>) -> Self {
+        Self { contexts, source }
+    }
+
+    /// Returns `true` if nothing is meant for end users, so callers can fall
+    /// back to a generic message.
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty() && self.source.is_none()
+    }
+}
+
+impl Display for UserMessage<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.contexts)?;
+
+        match self.source {
+            Some(source) if self.contexts.is_empty() => write!(f, "{}", source),
+            Some(source) => write!(f, "\n{}", source),
+            None => Ok(()),
+        }
+    }
+}
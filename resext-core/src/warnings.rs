@@ -0,0 +1,168 @@
+use core::fmt::Display;
+
+use crate::{ErrorSink, Errors};
+
+/// A value together with the non-fatal issues hit while producing it.
+///
+/// For tools that should finish their job but still tell the user what was
+/// skipped: failures that don't abort the run are recorded as warnings with
+/// [`Self::lift()`], and [`Self::report_warnings()`] prints them at the end.
+///
+/// # Examples
+///
+/// ```rust
+/// use resext::{ResWithWarnings, resext};
+///
+/// #[resext]
+/// enum ConfigError {
+///     Parse(std::num::ParseIntError),
+/// }
+///
+/// let mut ports: ResWithWarnings<Vec<u16>, ResErr> = ResWithWarnings::new(Vec::new());
+///
+/// for line in ["80", "http", "443"] {
+///     let port = ports.lift(line.parse::<u16>().context("Skipped invalid port"));
+///
+///     if let Some(port) = port {
+///         ports.value_mut().push(port);
+///     }
+/// }
+///
+/// assert_eq!(ports.warnings().len(), 1);
+///
+/// let mut out = String::new();
+/// let ports = ports.report_warnings_to(&mut out);
+///
+/// assert_eq!(ports, [80, 443]);
+/// assert_eq!(out, "warning: Skipped invalid port\nError: invalid digit found in string\n");
+/// ```
+#[derive(Debug)]
+pub struct ResWithWarnings<T, W> {
+    value: T,
+    warnings: Errors<W>,
+}
+
+impl<T, W> ResWithWarnings<T, W> {
+    /// Wraps `value` without any warnings.
+    pub const fn new(value: T) -> Self {
+        Self { value, warnings: Errors::new() }
+    }
+
+    /// Records a warning.
+    pub fn warn(&mut self, warning: W) {
+        self.warnings.push(warning);
+    }
+
+    /// Records a warning, for chaining.
+    pub fn with_warning(mut self, warning: W) -> Self {
+        self.warn(warning);
+        self
+    }
+
+    /// Returns the value of a Result, or records its error as a warning and
+    /// returns `None`.
+    pub fn lift<U, E>(&mut self, res: Result<U, E>) -> Option<U>
+    where
+        W: From<E>,
+    {
+        match res {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.warn(W::from(err));
+                None
+            }
+        }
+    }
+
+    /// Returns the value of a Result, or records its error as a warning and
+    /// returns `fallback`.
+    pub fn lift_or<U, E>(&mut self, res: Result<U, E>, fallback: U) -> U
+    where
+        W: From<E>,
+    {
+        self.lift(res).unwrap_or(fallback)
+    }
+
+    /// Maps the value, keeping the warnings.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> ResWithWarnings<U, W> {
+        ResWithWarnings { value: f(self.value), warnings: self.warnings }
+    }
+
+    /// Runs another step on the value, merging its warnings into these.
+    pub fn and_then<U, F: FnOnce(T) -> ResWithWarnings<U, W>>(
+        mut self,
+        f: F,
+    ) -> ResWithWarnings<U, W> {
+        let next = f(self.value);
+        self.warnings.extend(next.warnings);
+
+        ResWithWarnings { value: next.value, warnings: self.warnings }
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value mutably.
+    pub fn value_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Returns the recorded warnings.
+    pub fn warnings(&self) -> &Errors<W> {
+        &self.warnings
+    }
+
+    /// Returns `true` if any warning was recorded.
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    /// Splits into the value and the recorded warnings.
+    pub fn into_parts(self) -> (T, Errors<W>) {
+        (self.value, self.warnings)
+    }
+
+    /// Reports every warning to `sink`, prefixed with `warning: `, and
+    /// returns the value.
+    pub fn report_warnings_to<S: ErrorSink + ?Sized>(self, sink: &mut S) -> T
+    where
+        W: Display,
+    {
+        for warning in self.warnings.iter() {
+            sink.report(&format_args!("warning: {}", warning));
+        }
+
+        self.value
+    }
+
+    /// Prints every warning to stderr, prefixed with `warning: `, and returns
+    /// the value.
+    ///
+    /// With the `web` feature on `wasm32` the warnings go to the browser's
+    /// console instead, [`crate::set_report_sink()`] redirects them anywhere.
+    #[cfg(feature = "std")]
+    pub fn report_warnings(self) -> T
+    where
+        W: Display,
+    {
+        for warning in self.warnings.iter() {
+            crate::sink::report(&format_args!("warning: {}", warning));
+        }
+
+        self.value
+    }
+}
+
+impl<T: Default, W> Default for ResWithWarnings<T, W> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T, W> From<T> for ResWithWarnings<T, W> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
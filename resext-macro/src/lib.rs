@@ -16,11 +16,14 @@
 //! }
 //! ```
 //!
-//! This expands to approximately 300 lines of boilerplate including:
+//! This expands to boilerplate including:
 //!
 //! - `Display`, `Debug` and `Error` trait implementations
-//! - Wrapper struct with inline, zero-alloc context storage
-//! - Trait with context method
+//! - Wrapper struct with inline, zero-alloc context storage (provided by the `resext` crate)
+//! - Trait with context method, and a trait with combinators like `or_else_ctx()`
+//! - Trait with `parse_ctx()` for parsing strings with context
+//! - Trait with `ok_or_context()` for turning Options into errors with context
+//! - Trait with `context()` for futures resolving to Results (with the `async` feature)
 //! - `From<E>` implementations for automatic conversion
 //! - Type alias for `Result<T, ResErr>`
 //!
@@ -79,40 +82,47 @@ use syn::{
 /// - `source_prefix` - Prepend to underlying error (default: "Error: ")
 /// - `include_variant` - Show variant name in output (default: false)
 /// - `alias` - Custom type alias name which is used for getting the names for other items generated by the proc-macro (default: `Res`)
+/// - `wrapper` - Name of the generated wrapper struct (default: `{alias}Err`)
 /// - `buf_size` - Size for the context message byte buffer (default: 64)
 /// - `alloc` Enable heap-spilling if context exceeds `buf_size`
+/// - `spill` - Type implementing `resext::SpillStorage` that `alloc` spills context into (default: `Vec<u8>`)
+/// - `storage` - Type implementing `resext::ContextBuf` used to store context messages, e.g. `String`
+/// - `formatter` - Type implementing `resext::ErrorFormatter` used for the wrapper's `Display`
+/// - `format` - Template for the wrapper's `Display` output, see the `resext` crate docs
+/// - `headline` - `"last_context"` shows the most recent context first (default: `"first_context"`)
+/// - `non_exhaustive` - Add `#[non_exhaustive]` to the enum (default: false)
+/// - `opaque` - Hide the enum from the public API, exposing only the wrapper and its `kind()` (default: false)
+/// - `origin` - Component name shown as `[name] ` before the error, `true` uses the crate name
+/// - `id` - Attach a per-occurrence ID shown as `error id: 7f3a`, `true` uses a sequential counter,
+///   a path to a `fn() -> u32` plugs in another generator
+/// - `notes` - Let errors carry notes added with `.note()`, like request IDs, rendered as `note: `
+///   lines below the chain
+/// - `show_docs_url` - Show the variant's `docs_url` as a `see: ` line below the chain (default: false)
+/// - `help` - Let errors carry suggestions added with `.help()`, rendered as `help: ` lines below
+///   the chain
+/// - `color` - Color errors reported by `.or_exit()` and `#[resext::main]` with ANSI codes if stderr
+///   is a terminal and `NO_COLOR` isn't set, `Display` stays plain (default: false)
+/// - `locations` - Show where each context was added, as `at src/main.rs:42` (default: false)
+/// - `user_messages` - Keep user-facing context apart for `user_message()`, added with `.user_context()` (default: false)
+/// - `backtrace` - Capture a backtrace when a wrapper is created, if `RUST_BACKTRACE` enables it,
+///   and print it in the `{:?}` output. Requires `backtrace` (default: false)
+/// - `futures` - Generate `Future{alias}Ext` for `.context()` on futures resolving to Results.
+///   Requires `async` (default: false)
+/// - `serde` - Implement `serde::Serialize` for the wrapper. Requires `serde` (default: false)
+/// - `tui` - Generate `to_text()` and `From<&Wrapper>` for `ratatui`'s `Text`. Requires `tui`
+///   (default: false)
+/// - `classify` - Implement `resext::FromAnyhow` for `.classify()`, every wrapped type must be
+///   `Send + Sync + 'static`. Requires `anyhow` (default: false)
+/// - `interconvert` - Sibling `#[resext]` enums to generate `From<Self>` impls for, e.g. `[AppError]`,
+///   each must wrap every type this enum wraps
+/// - `minimal` - Only generate the enum's `Display`, `Error` and `From` impls, without the wrapper, traits or alias (default: false)
 ///
 /// ---
 ///
 /// # Examples
 ///
 /// ```rust
-/// # macro_rules! ctx {
-/// #     ($fmt:expr, $($args:tt)*) => {
-/// #         {
-/// #             struct Writer<W: core::fmt::Write + ?Sized>(W);
-/// #
-/// #             impl<W: core::fmt::Write + ?Sized> core::fmt::Write for Writer<W> {
-/// #                 fn write_str(&mut self, s: &str) -> core::fmt::Result {
-/// #                     self.0.write_str(s)
-/// #                 }
-/// #             }
-/// #
-/// #             |w, d, mp, ms| {
-/// #                 use core::fmt::Write;
-/// #
-/// #                 let mut w = Writer(w);
-/// #
-/// #                 let _ = w.write_str(d);
-/// #                 let _ = w.write_str(mp);
-/// #                 let _ = write!(w, $fmt, $($args)*);
-/// #                 let _ = w.write_str(ms);
-/// #
-/// #                 w.0
-/// #             }
-/// #         }
-/// #     };
-/// # }
+/// # use resext::ctx;
 /// # use resext_macro::resext;
 /// #[resext(alias = AppResult)]
 /// enum AppError {
@@ -132,19 +142,367 @@ use syn::{
 /// ```
 #[proc_macro_attribute]
 pub fn resext(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as DeriveInput);
+    let mut input = parse_macro_input!(item as DeriveInput);
     let args = parse_macro_input!(attr as ResExtArgs);
 
+    // Explicit discriminants on variants with fields need a primitive repr,
+    // default to `i32` so they double as error codes.
+    if let Data::Enum(data) = &input.data
+        && data.variants.iter().any(|variant| variant.discriminant.is_some())
+        && !input.attrs.iter().any(|attr| attr.path().is_ident("repr"))
+    {
+        input.attrs.push(syn::parse_quote!(#[repr(i32)]));
+    }
+
+    // `#[resext(...)]` on variants configures the enum's `Display` arms,
+    // exit codes and docs URLs and must be stripped, it isn't a real attribute
+    // there.
+    let mut variant_displays = Vec::new();
+    let mut variant_exit_codes = Vec::new();
+    let mut variant_docs_urls = Vec::new();
+    let mut variant_debug_summaries = Vec::new();
+    let mut variant_skip_from = Vec::new();
+    let mut variant_skip_display = Vec::new();
+    let mut user_facing_variants = Vec::new();
+
+    if let Data::Enum(data) = &mut input.data {
+        for variant in data.variants.iter_mut() {
+            let mut display = None;
+            let mut exit_code = None;
+            let mut docs_url = None;
+            let mut debug_summary = None;
+            let mut skip_from = false;
+            let mut skip_display = None;
+            let mut user_facing = None;
+
+            for attr in variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("resext"))
+            {
+                match attr.parse_args::<VariantArgs>() {
+                    Ok(args) => {
+                        display = args.display.or(display);
+                        exit_code = args.exit_code.or(exit_code);
+                        docs_url = args.docs_url.or(docs_url);
+                        debug_summary = args.debug_summary.or(debug_summary);
+                        skip_from |= args.skip_from;
+
+                        if args.skip_display {
+                            skip_display = Some(attr.span());
+                        }
+
+                        if args.user_facing {
+                            user_facing = Some(attr.span());
+                        }
+                    }
+                    Err(err) => return err.to_compile_error().into(),
+                }
+            }
+
+            variant.attrs.retain(|attr| !attr.path().is_ident("resext"));
+
+            if let Some(span) = skip_display
+                && display.is_some()
+            {
+                return Error::new(
+                    span,
+                    "`skip_display` and `display` can't be used together",
+                )
+                .to_compile_error()
+                .into();
+            }
+
+            variant_displays.push(display);
+            variant_skip_display.push(skip_display.is_some());
+            variant_exit_codes.push(exit_code);
+            variant_docs_urls.push(docs_url);
+            variant_debug_summaries.push(debug_summary);
+            variant_skip_from.push(skip_from);
+
+            if let Some(span) = user_facing {
+                if !args.user_messages {
+                    return Error::new(
+                        span,
+                        "`user_facing` requires `user_messages = true` on the enum",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                user_facing_variants.push(variant.ident.clone());
+            }
+        }
+    }
+
+    if args.non_exhaustive
+        && !input
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("non_exhaustive"))
+    {
+        input.attrs.push(syn::parse_quote!(#[non_exhaustive]));
+    }
+
+    let generics = match EnumGenerics::new(&input.generics) {
+        Ok(generics) => generics,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let enum_name = &input.ident;
     let vis = &input.vis;
 
     let alias = args.alias.unwrap_or_else(|| quote! { Res });
-    let struct_name = quote::format_ident!("{}Err", alias.to_string());
+    let struct_name = args
+        .wrapper
+        .clone()
+        .unwrap_or_else(|| quote::format_ident!("{}Err", alias.to_string()));
     let buf_name = quote::format_ident!("{}Buf", alias.to_string());
     let trait_name = quote::format_ident!("{}Ext", alias.to_string());
+    let ops_trait_name = quote::format_ident!("{}Ops", alias.to_string());
+    let str_trait_name = quote::format_ident!("Str{}Ext", alias.to_string());
+    let opt_trait_name = quote::format_ident!("Opt{}Ext", alias.to_string());
+    let future_trait_name =
+        quote::format_ident!("Future{}Ext", alias.to_string());
+
+    let EnumGenerics {
+        impl_generics,
+        decl_generics,
+        params: gp,
+        args: ga,
+        ty_generics,
+        alias_params,
+        where_clause,
+        where_preds,
+        ..
+    } = &generics;
+    let enum_ty = quote! { #enum_name #ty_generics };
+    let struct_ty = quote! { #struct_name #ty_generics };
 
     let alloc = args.alloc;
 
+    // With `locations`, the context methods are `#[track_caller]` so the
+    // call site can be written after each message.
+    //
+    // Debug builds with `std` track the caller too, for the warning printed
+    // when context added there gets truncated.
+    let track_caller = if args.locations {
+        quote! { #[track_caller] }
+    } else if cfg!(feature = "std") {
+        quote! { #[cfg_attr(debug_assertions, track_caller)] }
+    } else {
+        quote! {}
+    };
+    let warn_truncated = |truncated_before: proc_macro2::TokenStream| {
+        if cfg!(feature = "std") {
+            quote! {
+                #[cfg(debug_assertions)]
+                if let Err(err) = &res
+                    && !#truncated_before
+                    && ::resext::__private::ContextBuf::is_truncated(&err.msg)
+                {
+                    ::resext::__private::warn_truncated(&err.msg, core::panic::Location::caller());
+                }
+            }
+        } else {
+            quote! {}
+        }
+    };
+    let truncated_before = if cfg!(feature = "std") {
+        quote! {
+            #[cfg(debug_assertions)]
+            let truncated = matches!(&self, Err(err) if ::resext::__private::ContextBuf::is_truncated(&err.msg));
+        }
+    } else {
+        quote! {}
+    };
+    let warn_truncated_push = warn_truncated(quote! { truncated });
+    let warn_truncated_new = warn_truncated(quote! { false });
+
+    // With `id`, every wrapper gets a per-occurrence ID from the built-in
+    // counter or the user's generator, assigned wherever one is constructed.
+    let (id_field, id_init) = match &args.id {
+        Some(ErrorId::Sequential) => (
+            quote! { id: u32, },
+            quote! { id: ::resext::__private::next_error_id(), },
+        ),
+        Some(ErrorId::Generator(path)) => {
+            (quote! { id: u32, }, quote! { id: #path(), })
+        }
+        None => (quote! {}, quote! {}),
+    };
+
+    // With `related`, secondary errors are kept next to the source and
+    // rendered below the main chain.
+    let (related_field, related_init) = if args.related {
+        (
+            quote! { related: ::resext::__private::Vec<#struct_ty>, },
+            quote! { related: ::resext::__private::Vec::new(), },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // With `notes`, annotations like request IDs are kept apart from the
+    // context messages and rendered as `note: ` lines below the main chain.
+    let (notes_field, notes_init) = if args.notes {
+        (
+            quote! { notes: ::resext::__private::Vec<::resext::__private::Cow<'static, str>>, },
+            quote! { notes: ::resext::__private::Vec::new(), },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    let (notes_ops_decl, notes_ops_impl, notes_ops_impl_from) = if args.notes {
+        (
+            quote! {
+                /// Adds a note that isn't part of the causal chain, like a request ID, rendered as a
+                /// `note: ` line below the context messages.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// client.send(request)
+                ///     .context("Failed to sync orders")
+                ///     .note(format!("request id: {}", request_id))?;
+                /// ```
+                fn note<N: Into<::resext::__private::Cow<'static, str>>>(self, note: N) -> ::core::result::Result<T, #struct_ty>;
+            },
+            quote! {
+                fn note<N: Into<::resext::__private::Cow<'static, str>>>(self, note: N) -> ::core::result::Result<T, #struct_ty> {
+                    self.map_err(|err| err.note(note))
+                }
+            },
+            quote! {
+                fn note<N: Into<::resext::__private::Cow<'static, str>>>(self, note: N) -> ::core::result::Result<T, #struct_ty> {
+                    #ops_trait_name::note(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), note)
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    // With `help`, suggestions are kept apart from the context messages and
+    // rendered as `help: ` lines below the main chain.
+    let (help_field, help_init) = if args.help {
+        (
+            quote! { help: ::resext::__private::Vec<::resext::__private::Cow<'static, str>>, },
+            quote! { help: ::resext::__private::Vec::new(), },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    let (help_ops_decl, help_ops_impl, help_ops_impl_from) = if args.help {
+        (
+            quote! {
+                /// Adds a suggestion for fixing the error, rendered as a `help: ` line below the
+                /// context messages.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// std::fs::remove_dir(&path)
+                ///     .context("Failed to remove output directory")
+                ///     .help("try running with --force")?;
+                /// ```
+                fn help<H: Into<::resext::__private::Cow<'static, str>>>(self, help: H) -> ::core::result::Result<T, #struct_ty>;
+            },
+            quote! {
+                fn help<H: Into<::resext::__private::Cow<'static, str>>>(self, help: H) -> ::core::result::Result<T, #struct_ty> {
+                    self.map_err(|err| err.help(help))
+                }
+            },
+            quote! {
+                fn help<H: Into<::resext::__private::Cow<'static, str>>>(self, help: H) -> ::core::result::Result<T, #struct_ty> {
+                    #ops_trait_name::help(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), help)
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    // With `attachments`, typed values can be attached to the wrapper and
+    // looked up by type for programmatic handling.
+    let (attachments_field, attachments_init) = if args.attachments {
+        (
+            quote! { attachments: ::resext::__private::Attachments, },
+            quote! { attachments: ::resext::__private::Attachments::new(), },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    let (attach_ops_decl, attach_ops_impl, attach_ops_impl_from) = if args
+        .attachments
+    {
+        (
+            quote! {
+                /// Attaches a typed value to the error, retrievable with `get_attached()`.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// let config = std::fs::read(&path)
+                ///     .context("Failed to read config")
+                ///     .attach(path.clone())?;
+                /// ```
+                fn attach<A: core::any::Any + Send + Sync>(self, value: A) -> ::core::result::Result<T, #struct_ty>;
+            },
+            quote! {
+                fn attach<A: core::any::Any + Send + Sync>(self, value: A) -> ::core::result::Result<T, #struct_ty> {
+                    self.map_err(|err| err.attach(value))
+                }
+            },
+            quote! {
+                fn attach<A: core::any::Any + Send + Sync>(self, value: A) -> ::core::result::Result<T, #struct_ty> {
+                    #ops_trait_name::attach(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), value)
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    // With `user_messages`, context added with `.user_context()` is also
+    // written to a second buffer rendered by `user_message()`.
+    let (user_field, user_init) = if args.user_messages {
+        (
+            quote! { user_msg: #buf_name, },
+            quote! { user_msg: #buf_name::default(), },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // With `backtrace`, a backtrace is captured wherever a wrapper is
+    // constructed, subject to `RUST_BACKTRACE` like `anyhow`. It's boxed so
+    // the wrapper only grows by a pointer.
+    let (backtrace_field, backtrace_init) = if args.backtrace {
+        (
+            quote! { backtrace: Option<::resext::__private::Box<::resext::__private::Backtrace>>, },
+            quote! { backtrace: ::resext::__private::capture_backtrace(), },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    let extra_init = quote! {
+        #id_init #related_init #notes_init #help_init #attachments_init #user_init #backtrace_init
+    };
+
+    // Metadata options that `fields()` reports when set.
+    let origin_kv = args.origin.as_ref().map(|_| {
+        quote! { ("origin", ::resext::FieldValue::Str(self.origin())), }
+    });
+    let id_kv = args.id.as_ref().map(|_| {
+        quote! { ("id", ::resext::FieldValue::UInt(u64::from(self.id()))), }
+    });
+    let extra_fields = quote! { #origin_kv #id_kv };
+
     let variants = match &input.data {
         Data::Enum(data) => &data.variants,
         _ => {
@@ -159,9 +517,61 @@ pub fn resext(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let include_variant = args.include_variant;
     let mut errors: Option<Error> = None;
-    let display_match_arms = variants.iter().map(|variant| {
+    let display_match_arms = variants.iter().zip(&variant_displays).zip(&variant_skip_display).map(|((variant, display), skip_display)| {
         let variant_name = &variant.ident;
 
+        // Large payloads are summarized by their size if they are byte-like.
+        if *skip_display {
+            let pattern = match &variant.fields {
+                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    Some(quote! { #enum_name::#variant_name(value) })
+                }
+                syn::Fields::Named(fields) if fields.named.len() == 1 => {
+                    let field_name = fields.named[0].ident.as_ref().unwrap();
+
+                    Some(quote! { #enum_name::#variant_name { #field_name: value } })
+                }
+                _ => None,
+            };
+
+            if let Some(pattern) = pattern {
+                return quote! {
+                    #pattern => {
+                        use ::resext::__private::{PayloadLen as _, PayloadOther as _};
+
+                        ::resext::__private::fmt_skipped(
+                            f,
+                            stringify!(#variant_name),
+                            (&&::resext::__private::PayloadSize(value)).payload_len(),
+                        )
+                    }
+                };
+            }
+        }
+
+        if let Some(display) = display
+            && variant.fields.len() <= 1
+        {
+            let display = variant_display_format(display);
+
+            return match &variant.fields {
+                syn::Fields::Unnamed(_) => quote! {
+                    #enum_name::#variant_name(_0) => write!(f, #display),
+                },
+                syn::Fields::Named(fields) => {
+                    let variant_fields = fields.named.iter().map(|field| &field.ident);
+
+                    quote! {
+                        #[allow(unused_variables)]
+                        #enum_name::#variant_name { #(#variant_fields),* } => write!(f, #display),
+                    }
+                }
+                syn::Fields::Unit => quote! {
+                    #enum_name::#variant_name => f.write_str(#display),
+                },
+            };
+        }
+
         match &variant.fields {
             syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
                 if include_variant {
@@ -211,422 +621,2782 @@ pub fn resext(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     });
 
-    let from_impls = variants.iter().filter_map(|variant| {
+    let (enum_from_impls, struct_from_impls): (Vec<_>, Vec<_>) = variants.iter().zip(&variant_skip_from).filter_map(|(variant, skip_from)| {
         let variant_name = &variant.ident;
 
+        // Variants wrapping the same type as another one would get
+        // conflicting impls, `skip_from` leaves them to manual construction.
+        if *skip_from {
+            return None;
+        }
+
+        // Conversions from a type parameter would overlap with the blanket
+        // `From<T> for T`.
+        if variant.fields.iter().any(|field| generics.is_generic(&field.ty)) {
+            return None;
+        }
+
         match &variant.fields {
             syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
                 let field_type = &fields.unnamed[0].ty;
 
-                Some(quote! {
-                    impl From<#field_type> for #enum_name {
+                Some((quote! {
+                    impl #impl_generics From<#field_type> for #enum_ty #where_clause {
                         fn from(value: #field_type) -> Self {
                             Self::#variant_name(value)
                         }
                     }
-
-                    impl From<#field_type> for #struct_name {
+                }, quote! {
+                    impl #impl_generics From<#field_type> for #struct_ty #where_clause {
                         fn from(value: #field_type) -> Self {
-                            Self { msg: #buf_name::new(), source: #enum_name::#variant_name(value) }
+                            Self { msg: #buf_name::default(), source: #enum_name::#variant_name(value), #extra_init }
                         }
                     }
-                })
+                }))
             }
 
             syn::Fields::Named(fields) if fields.named.len() == 1 => {
                 let field_name = fields.named[0].ident.as_ref().unwrap();
                 let field_type = &fields.named[0].ty;
 
-                Some(quote! {
-                    impl From<#field_type> for #enum_name {
+                Some((quote! {
+                    impl #impl_generics From<#field_type> for #enum_ty #where_clause {
                         fn from(value: #field_type) -> Self {
                             Self::#variant_name { #field_name: value }
                         }
                     }
-
-                    impl From<#field_type> for #struct_name {
+                }, quote! {
+                    impl #impl_generics From<#field_type> for #struct_ty #where_clause {
                         fn from(value: #field_type) -> Self {
-                            Self { msg: #buf_name::new(), source: #enum_name::#variant_name { #field_name: value } }
+                            Self { msg: #buf_name::default(), source: #enum_name::#variant_name { #field_name: value }, #extra_init }
                         }
                     }
-                })
+                }))
             }
 
             _ => None,
         }
-    });
+    }).unzip();
 
-    let prefix = args.prefix.unwrap_or_default();
-    let suffix = args.suffix.unwrap_or_default();
-    let msg_prefix = args.msg_prefix.unwrap_or_default();
-    let msg_suffix = args.msg_suffix.unwrap_or_default();
-    let delimiter = args.delimiter.unwrap_or_else(|| String::from("\n - "));
-    let source_prefix =
-        args.source_prefix.unwrap_or_else(|| String::from("Error: "));
-    let buf_size = args.buf_size.unwrap_or(64);
+    // Skipped if a variant already wraps `Infallible`, which would conflict.
+    let wraps_infallible = variants.iter().any(|variant| {
+        variant.fields.iter().any(|field| match &field.ty {
+            syn::Type::Path(ty) => ty
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Infallible"),
+            _ => false,
+        })
+    });
 
-    let gen_buf = {
-        if !alloc {
+    let (enum_infallible_impl, struct_infallible_impl) = if wraps_infallible {
+        (quote! {}, quote! {})
+    } else {
+        (
             quote! {
-                struct #buf_name {
-                    curr_pos: u16,
-                    buf: [u8; #buf_size],
-                    truncate: bool,
+                impl #impl_generics From<core::convert::Infallible> for #enum_ty #where_clause {
+                    fn from(value: core::convert::Infallible) -> Self {
+                        match value {}
+                    }
                 }
-
-                impl #buf_name {
-                    fn new() -> Self {
-                        Self { buf: [0; #buf_size], curr_pos: 0, truncate: false }
+            },
+            quote! {
+                impl #impl_generics From<core::convert::Infallible> for #struct_ty #where_clause {
+                    fn from(value: core::convert::Infallible) -> Self {
+                        match value {}
                     }
+                }
+            },
+        )
+    };
 
-                    fn get_slice(&self) -> &[u8] {
-                        &self.buf[..self.curr_pos as usize]
-                    }
+    // Conversions into sibling enums go through the sibling's own `From`
+    // impls for each wrapped type, so a sibling missing one of them fails to
+    // compile at the `interconvert` list.
+    let mut interconvert_arms = Vec::new();
 
-                    fn is_empty(&self) -> bool {
-                        self.curr_pos == 0
-                    }
+    if !args.interconvert.is_empty() {
+        for variant in variants {
+            let variant_name = &variant.ident;
 
-                    fn truncate(&self) -> bool {
-                        self.truncate
-                    }
+            interconvert_arms.push(match &variant.fields {
+                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    quote! { #enum_name::#variant_name(value) => Target::from(value), }
                 }
 
-                impl core::fmt::Write for #buf_name {
-                    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-                        let bytes = s.as_bytes();
-                        let pos = self.curr_pos as usize;
-                        let cap = #buf_size - pos;
+                syn::Fields::Named(fields) if fields.named.len() == 1 => {
+                    let field_name = fields.named[0].ident.as_ref().unwrap();
 
-                        let limit = if cap < bytes.len() {
-                            self.truncate = true;
-                            cap
-                        } else {
-                            bytes.len()
-                        };
+                    quote! { #enum_name::#variant_name { #field_name } => Target::from(#field_name), }
+                }
 
-                        let to_copy = match bytes[..limit]
-                            .iter()
-                            .rposition(|&b| (b & 0xC0) != 0x80)
-                        {
-                            Some(start_of_last_char) => {
-                                let last_char_byte = bytes[start_of_last_char];
-                                let width = match last_char_byte {
-                                    0..=127 => 1,
-                                    192..=223 => 2,
-                                    224..=239 => 3,
-                                    240..=247 => 4,
-                                    _ => 1,
-                                };
-                                if start_of_last_char + width <= limit {
-                                    start_of_last_char + width
-                                } else {
-                                    start_of_last_char
-                                }
-                            }
-                            None => 0,
-                        };
+                _ => {
+                    return Error::new(
+                        variant.span(),
+                        "`interconvert` requires every variant to wrap an error type",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+            });
+        }
+    }
 
-                        self.buf[pos..pos + to_copy].copy_from_slice(&bytes[..to_copy]);
-                        self.curr_pos += to_copy as u16;
+    let interconvert_impls = args.interconvert.iter().map(|target| {
+        quote! {
+            impl #impl_generics From<#enum_ty> for #target #where_clause {
+                fn from(value: #enum_ty) -> Self {
+                    type Target = #target;
 
-                        Ok(())
+                    match value {
+                        #(#interconvert_arms)*
                     }
                 }
             }
-        } else {
-            quote! {
-                mod __private_alloc {
-                    extern crate alloc;
-                    pub(crate) use alloc::vec::Vec;
+        }
+    });
+
+    let anyhow_impl = if args.classify {
+        let downcasts = variants.iter().filter_map(|variant| {
+            let variant_name = &variant.ident;
+
+            let (field_type, construct) = match &variant.fields {
+                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => (
+                    &fields.unnamed[0].ty,
+                    quote! { #enum_name::#variant_name(value) },
+                ),
+
+                syn::Fields::Named(fields) if fields.named.len() == 1 => {
+                    let field_name = fields.named[0].ident.as_ref().unwrap();
+
+                    (
+                        &fields.named[0].ty,
+                        quote! { #enum_name::#variant_name { #field_name: value } },
+                    )
                 }
 
-                enum #buf_name {
-                    Stack { buf: [u8; #buf_size], curr_pos: u16 },
-                    Heap(__private_alloc::Vec<u8>),
+                _ => return None,
+            };
+
+            // `downcast()` needs a concrete type.
+            if generics.is_generic(field_type) {
+                return None;
+            }
+
+            Some(quote! {
+                let err = match err.downcast::<#field_type>() {
+                    Ok(value) => return Ok(#construct),
+                    Err(err) => err,
+                };
+            })
+        });
+
+        quote! {
+            impl #impl_generics ::resext::FromAnyhow for #enum_ty #where_clause {
+                fn from_anyhow(err: ::resext::__private::anyhow::Error) -> ::core::result::Result<Self, ::resext::__private::anyhow::Error> {
+                    #(#downcasts)*
+
+                    Err(err)
                 }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Methods that need `std`, which is forwarded from `resext/std`.
+    // With `color = true`, reports to stderr go through `ColorReport`.
+    let reported = if args.color {
+        quote! {
+            &::resext::__private::DisplayFn(|f: &mut core::fmt::Formatter| {
+                ::resext::__private::ColorReport::fmt_report(&err, f)
+            })
+        }
+    } else {
+        quote! { &err }
+    };
+
+    let (std_ops_decls, std_ops_impl, std_ops_impl_from) = if cfg!(
+        feature = "std"
+    ) {
+        (
+            quote! {
+                /// Returns the value, or prints the error to stderr and exits the process with `code`.
+                ///
+                /// With the `resext/test-no-exit` feature this panics with the formatted error
+                /// instead, so tests can assert on fatal paths.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// let config = std::fs::read("config.toml")
+                ///     .context("Failed to read config")
+                ///     .or_exit(78);
+                /// ```
+                #[track_caller]
+                fn or_exit(self, code: i32) -> T;
+
+                /// Like `or_exit()`, but exits with the variant's `exit_code()`.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// #[resext]
+                /// enum AppError {
+                ///     #[resext(exit_code = "EX_IOERR")]
+                ///     Io(std::io::Error),
+                /// }
+                ///
+                /// let config = std::fs::read("config.toml")
+                ///     .context("Failed to read config")
+                ///     .or_exit_auto();
+                /// ```
+                #[track_caller]
+                fn or_exit_auto(self) -> T;
+
+                /// Like `or_exit()`, but exits with the variant's `sysexit_code()`: its
+                /// `exit_code` if set, otherwise the `sysexits.h` code of the wrapped error, e.g.
+                /// `EX_NOINPUT` for a missing file or `EX_DATAERR` for a failed parse.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// let config = std::fs::read("config.toml")
+                ///     .context("Failed to read config")
+                ///     .or_exit_sysexit();
+                /// ```
+                #[track_caller]
+                fn or_exit_sysexit(self) -> T;
+
+                /// Like `or_exit()`, but exits with the code `policy` maps the error's variant to.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// let policy = resext::ExitPolicy::new().map_variant("Io", 74).default(1);
+                ///
+                /// let config = std::fs::read("config.toml")
+                ///     .context("Failed to read config")
+                ///     .or_exit_policy(&policy);
+                /// ```
+                #[track_caller]
+                fn or_exit_policy(self, policy: &::resext::ExitPolicy) -> T;
 
-                impl #buf_name {
-                    fn new() -> Self {
-                        Self::Stack { buf: [0; #buf_size], curr_pos: 0 }
+                /// Adds `msg` followed by `: ` and the path as context to an error.
+                ///
+                /// The path is written with `Path::display()` only if an error occurs, without
+                /// allocating, and non-UTF-8 paths are rendered lossily instead of failing.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// std::fs::read(&path)
+                ///     .path_context("Failed to read config", &path)?;
+                /// ```
+                fn path_context<P: AsRef<::resext::__private::Path>>(self, msg: &str, path: P) -> ::core::result::Result<T, #struct_ty>;
+
+                /// Like `on_err()`, but only calls `f` while `sampler` admits errors.
+                ///
+                /// Errors over the sampler's limit are only counted, so hot loops don't pay for
+                /// formatting every error.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// static SAMPLER: LazyLock<resext::Sampler> = LazyLock::new(|| resext::Sampler::per_second(100));
+                ///
+                /// handle(packet)
+                ///     .context("Failed to handle packet")
+                ///     .on_err_sampled(&SAMPLER, |err| log::warn!("{}", err))?;
+                /// ```
+                fn on_err_sampled<F: FnOnce(&Self::Error)>(self, sampler: &::resext::Sampler, f: F) -> Self;
+
+                /// Returns the value, or `default` if there is an error.
+                ///
+                /// With `verbose`, the error is reported with `msg` added as context, to stderr
+                /// or the sink set with `resext::set_report_sink()`, before it is dropped.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// let retries = std::fs::read_to_string("retries")
+                ///     .or_default_ctx("Failed to read retries, using 3", String::from("3"), true);
+                /// ```
+                fn or_default_ctx(self, msg: &str, default: T, verbose: bool) -> T;
+
+                /// Returns the value as `Some`, or `None` if there is an error.
+                ///
+                /// With `verbose`, the error is reported like with `or_default_ctx()`.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// let cache = std::fs::read("cache.bin").to_option_ctx("Failed to read cache", true);
+                /// ```
+                fn to_option_ctx(self, msg: &str, verbose: bool) -> Option<T>;
+            },
+            quote! {
+                #[track_caller]
+                fn or_exit(self, code: i32) -> T {
+                    match self {
+                        Ok(ok) => ok,
+                        Err(err) => ::resext::__private::exit(code, #reported),
                     }
+                }
 
-                    fn get_slice(&self) -> &[u8] {
-                        match self {
-                            Self::Stack { buf, curr_pos } => &buf[..*curr_pos as usize],
-                            Self::Heap(buf) => buf,
-                        }
+                #[track_caller]
+                fn or_exit_auto(self) -> T {
+                    match self {
+                        Ok(ok) => ok,
+                        Err(err) => ::resext::__private::exit(err.source.exit_code(), #reported),
                     }
+                }
 
-                    fn truncate(&self) -> bool {
-                        false
+                #[track_caller]
+                fn or_exit_sysexit(self) -> T {
+                    match self {
+                        Ok(ok) => ok,
+                        Err(err) => ::resext::__private::exit(err.source.sysexit_code(), #reported),
                     }
+                }
 
-                    fn is_empty(&self) -> bool {
-                        match self {
-                            Self::Heap(buf) => buf.is_empty(),
-                            Self::Stack { buf: _, curr_pos } => *curr_pos == 0,
-                        }
+                #[track_caller]
+                fn or_exit_policy(self, policy: &::resext::ExitPolicy) -> T {
+                    match self {
+                        Ok(ok) => ok,
+                        Err(err) => ::resext::__private::exit(policy.code_for(err.source.variant_name()), #reported),
                     }
                 }
 
-                impl core::fmt::Write for #buf_name {
-                    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-                        match self {
-                            Self::Heap(buf) => buf.extend_from_slice(s.as_bytes()),
-                            Self::Stack { buf, curr_pos } => {
-                                let bytes = s.as_bytes();
-                                let pos = *curr_pos as usize;
-                                let cap = #buf_size - pos;
+                #track_caller
+                fn path_context<P: AsRef<::resext::__private::Path>>(self, msg: &str, path: P) -> ::core::result::Result<T, #struct_ty> {
+                    use core::fmt::Write;
+
+                    let path = path.as_ref();
+
+                    #trait_name::context(self, |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                        let _ = write!(err, "{}{}{}: {}{}", d, mp, msg, path.display(), ms);
+                        err
+                    })
+                }
+
+                fn on_err_sampled<F: FnOnce(&Self::Error)>(self, sampler: &::resext::Sampler, f: F) -> Self {
+                    if let Err(err) = &self
+                        && sampler.admit()
+                    {
+                        f(err);
+                    }
+
+                    self
+                }
 
-                                if bytes.len() > cap {
-                                    {
-                                        extern crate alloc;
-                                        let mut vec = alloc::vec::Vec::new();
-                                        vec.reserve_exact(pos + bytes.len());
+                #track_caller
+                fn or_default_ctx(self, msg: &str, default: T, verbose: bool) -> T {
+                    #ops_trait_name::to_option_ctx(self, msg, verbose).unwrap_or(default)
+                }
 
-                                        vec.extend_from_slice(&buf[..pos]);
-                                        vec.extend_from_slice(bytes);
+                #track_caller
+                fn to_option_ctx(self, msg: &str, verbose: bool) -> Option<T> {
+                    match self {
+                        Ok(ok) => Some(ok),
+                        Err(err) => {
+                            if verbose {
+                                let err = #trait_name::context(Err::<(), #struct_ty>(err), msg).unwrap_err();
 
-                                        *self = #buf_name::Heap(vec);
-                                    }
-                                } else {
-                                    buf[pos..pos + bytes.len()].copy_from_slice(bytes);
-                                    *curr_pos += bytes.len() as u16;
-                                }
+                                ::resext::__private::report(#reported);
                             }
-                        }
 
-                        Ok(())
+                            None
+                        }
+                    }
+                }
+            },
+            quote! {
+                #[track_caller]
+                fn or_exit(self, code: i32) -> T {
+                    match self {
+                        Ok(ok) => ok,
+                        Err(err) => #ops_trait_name::or_exit(Err::<T, #struct_ty>(#struct_name::from(#enum_name::from(err))), code),
                     }
                 }
-            }
-        }
-    };
 
-    let expanded = quote! {
-        #[derive(Debug)]
-        #input
+                #[track_caller]
+                fn or_exit_auto(self) -> T {
+                    #ops_trait_name::or_exit_auto(self.map_err(|err| #struct_name::from(#enum_name::from(err))))
+                }
 
-        impl core::fmt::Display for #enum_name {
-            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-                match self {
-                    #(#display_match_arms)*
+                #[track_caller]
+                fn or_exit_sysexit(self) -> T {
+                    #ops_trait_name::or_exit_sysexit(self.map_err(|err| #struct_name::from(#enum_name::from(err))))
                 }
-            }
-        }
 
-        /// Wrapper type that holds your error with optional context messages.
-        ///
-        /// This type is automatically created when you use `.context()` or
-        /// `.context()` on a Result.
-        #[doc(hidden)]
-        #vis struct #struct_name {
-            msg: #buf_name,
-            #vis source: #enum_name
-        }
-        impl core::error::Error for #struct_name {}
+                #[track_caller]
+                fn or_exit_policy(self, policy: &::resext::ExitPolicy) -> T {
+                    #ops_trait_name::or_exit_policy(self.map_err(|err| #struct_name::from(#enum_name::from(err))), policy)
+                }
 
+                #track_caller
+                fn path_context<P: AsRef<::resext::__private::Path>>(self, msg: &str, path: P) -> ::core::result::Result<T, #struct_ty> {
+                    #ops_trait_name::path_context(self.map_err(|err| #struct_name::from(#enum_name::from(err))), msg, path)
+                }
 
-        impl core::fmt::Write for #struct_name {
-            fn write_str(&mut self, s: &str) -> core::fmt::Result {
-                if s.is_empty() {
-                    Ok(())
-                } else {
-                    self.msg.write_str(s)
+                fn on_err_sampled<F: FnOnce(&Self::Error)>(self, sampler: &::resext::Sampler, f: F) -> Self {
+                    if let Err(err) = &self
+                        && sampler.admit()
+                    {
+                        f(err);
+                    }
+
+                    self
                 }
-            }
-        }
 
-        impl core::fmt::Display for #struct_name {
-            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-                if self.msg.is_empty() {
-                    write!(f, "{}{}{}", #source_prefix, &self.source, #suffix)
-                } else {
-                    write!(
-                        f,
-                        "{}{}{}\n{}{}{}",
-                        #prefix,
-                        unsafe { core::str::from_utf8_unchecked(&self.msg.get_slice()) },
-                        if self.msg.truncate() { "..." } else { "" },
-                        #source_prefix,
-                        self.source,
-                        #suffix,
-                    )
+                #track_caller
+                fn or_default_ctx(self, msg: &str, default: T, verbose: bool) -> T {
+                    #ops_trait_name::or_default_ctx(self.map_err(|err| #struct_name::from(#enum_name::from(err))), msg, default, verbose)
                 }
-            }
-        }
 
-        impl core::fmt::Debug for #struct_name {
-            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-                if self.msg.is_empty() {
-                    write!(f, "{}{:?}{}", #source_prefix, &self.source, #suffix)
-                } else {
-                    write!(
-                        f,
-                        "{}{}{}\n{}{:?}{}",
-                        #prefix,
-                        unsafe { core::str::from_utf8_unchecked(&self.msg.get_slice()) },
-                        if self.msg.truncate() { "..." } else { "" },
-                        #source_prefix,
-                        self.source,
-                        #suffix,
-                    )
+                #track_caller
+                fn to_option_ctx(self, msg: &str, verbose: bool) -> Option<T> {
+                    #ops_trait_name::to_option_ctx(self.map_err(|err| #struct_name::from(#enum_name::from(err))), msg, verbose)
                 }
-            }
-        }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
 
-        impl #struct_name {
-            /// Helper method for constructing `ResErr` structs without using `.context()`
-            /// on a Result.
-            ///
-            /// # Examples
-            ///
-            /// ```rust,ignore
-            /// ResErr::new("Failed to read file", std::io::Error::other(""));
-            /// ```
-            #[doc(hidden)]
-            #vis fn new<E>(msg: &str, source: E) -> Self where #enum_name: From<E> {
-                use core::fmt::Write;
-                let mut buf = #buf_name::new();
-                let _ = buf.write_str(msg);
-                Self { msg: buf, source: #enum_name::from(source) }
-            }
+    let variant_index_arms =
+        variants.iter().enumerate().map(|(idx, variant)| {
+            let variant_name = &variant.ident;
 
-            /// Helper method for constructing `ResErr` structs without using `.context()`
-            /// on a Result.
-            ///
-            /// This method constructs `ResErr` structs using `ctx!()` macro for optimized, lazily
-            /// evaluated construction
-            ///
-            /// # Examples
-            ///
-            /// ```rust,ignore
-            /// ResErr::from_args(ctx!("Failed to read file: {}", &file_name), std::io::Error::other(""));
-            /// ```
-            #[doc(hidden)]
-            #vis fn from_args<E, F: FnOnce(#struct_name, &str, &str, &str) -> #struct_name>(msg: F, source: E) -> Self where #enum_name: From<E> {
-                use core::fmt::Write;
+            quote! {
+                #enum_name::#variant_name { .. } => #idx,
+            }
+        });
 
-                let err = Self { msg: #buf_name::new(), source: #enum_name::from(source) };
+    let variant_name_arms = variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
 
-                msg(err, "", "", "")
-            }
+        quote! {
+            #enum_name::#variant_name { .. } => stringify!(#variant_name),
         }
+    });
+
+    let docs_url_arms =
+        variants.iter().zip(&variant_docs_urls).map(|(variant, docs_url)| {
+            let variant_name = &variant.ident;
+            let docs_url = match docs_url {
+                Some(url) => quote! { ::core::option::Option::Some(#url) },
+                None => quote! { ::core::option::Option::None },
+            };
 
-        impl From<#enum_name> for #struct_name {
-            fn from(value: #enum_name) -> Self {
-                Self { msg: #buf_name::new(), source: value }
+            quote! {
+                #enum_name::#variant_name { .. } => #docs_url,
             }
-        }
+        });
 
-        #(#from_impls)*
+    let mut code_base: Option<&syn::Expr> = None;
+    let mut code_offset = 0i32;
+    let code_arms = variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
 
-        /// Extension trait for adding context to Result types.
-        ///
-        /// Automatically implemented for all `Result<T, E>` where `E` can be
-        /// converted into your error enum.
-        ///
-        /// # Examples
-        ///
-        /// ```rust,ignore
-        /// std::fs::read("file.txt")
-        ///     .context("Failed to read file")?;
-        /// ```
-        #[doc(hidden)]
-        #vis trait #trait_name<T, S> {
-            /// Add context to an error.
-            ///
-            /// Accepts `&str` or `core::fmt::Arguments<'_>`. The message is only allocated if an
-            /// error occurs.
+        if let Some((_, expr)) = &variant.discriminant {
+            code_base = Some(expr);
+            code_offset = 0;
+        }
+
+        let offset = proc_macro2::Literal::i32_unsuffixed(code_offset);
+        let code = match code_base {
+            Some(expr) if code_offset == 0 => quote! { (#expr) as i32 },
+            Some(expr) => quote! { (#expr) as i32 + #offset },
+            None => quote! { #offset },
+        };
+
+        code_offset += 1;
+
+        quote! {
+            #enum_name::#variant_name { .. } => #code,
+        }
+    });
+
+    let exit_code_arms =
+        variants.iter().zip(&variant_exit_codes).map(|(variant, exit_code)| {
+            let variant_name = &variant.ident;
+            let exit_code = exit_code.clone().unwrap_or_else(|| quote! { 1 });
+
+            quote! {
+                #enum_name::#variant_name { .. } => (#exit_code) as i32,
+            }
+        });
+
+    // Variants without an explicit `exit_code` map their wrapped value with
+    // `resext::exitcode::for_error()` if it is an error.
+    let sysexit_arms =
+        variants.iter().zip(&variant_exit_codes).map(|(variant, exit_code)| {
+            let variant_name = &variant.ident;
+
+            let (pattern, code) = match (exit_code, &variant.fields) {
+                (Some(exit_code), _) => (
+                    quote! { #enum_name::#variant_name { .. } },
+                    quote! { (#exit_code) as i32 },
+                ),
+
+                (None, syn::Fields::Unnamed(fields)) if fields.unnamed.len() == 1 => (
+                    quote! { #enum_name::#variant_name(value) },
+                    quote! { (&&::resext::__private::SysexitProbe(value)).sysexit().unwrap_or(::resext::exitcode::EX_SOFTWARE) },
+                ),
+
+                (None, syn::Fields::Named(fields)) if fields.named.len() == 1 => {
+                    let field_name = fields.named[0].ident.as_ref().unwrap();
+
+                    (
+                        quote! { #enum_name::#variant_name { #field_name: value } },
+                        quote! { (&&::resext::__private::SysexitProbe(value)).sysexit().unwrap_or(::resext::exitcode::EX_SOFTWARE) },
+                    )
+                }
+
+                (None, _) => (
+                    quote! { #enum_name::#variant_name { .. } },
+                    quote! { ::resext::exitcode::EX_SOFTWARE },
+                ),
+            };
+
+            quote! {
+                #pattern => #code,
+            }
+        });
+
+    // Single-field variants wrapping an error forward `source()` to it, so
+    // the chain continues past the enum. Variants displaying the error are
+    // transparent instead and skip to its source, so its message isn't
+    // printed twice by reporters walking the chain.
+    let source_arms = variants.iter().zip(&variant_displays).zip(&variant_skip_display).map(|((variant, display), skip_display)| {
+        let variant_name = &variant.ident;
+
+        let pattern = match &variant.fields {
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                quote! { #enum_name::#variant_name(value) }
+            }
+
+            syn::Fields::Named(fields) if fields.named.len() == 1 => {
+                let field_name = fields.named[0].ident.as_ref().unwrap();
+
+                quote! { #enum_name::#variant_name { #field_name: value } }
+            }
+
+            _ => {
+                return quote! {
+                    #enum_name::#variant_name { .. } => None,
+                };
+            }
+        };
+
+        if display.is_some() || *skip_display {
+            quote! {
+                #pattern => (&&::resext::__private::SourceProbe(value)).source(),
+            }
+        } else {
+            quote! {
+                #pattern => (&&::resext::__private::SourceProbe(value)).transparent(),
+            }
+        }
+    });
+
+    let prefix = args.prefix.unwrap_or_default();
+    let suffix = args.suffix.unwrap_or_default();
+    let msg_prefix = args.msg_prefix.unwrap_or_default();
+    let msg_suffix = args.msg_suffix.unwrap_or_default();
+    let template_delimiter = args.format.as_ref().and_then(|parts| {
+        parts.iter().find_map(|part| match part {
+            TemplatePart::Contexts(delimiter) => delimiter.clone(),
+            _ => None,
+        })
+    });
+    let delimiter = args
+        .delimiter
+        .or(template_delimiter)
+        .unwrap_or_else(|| String::from("\n - "));
+    let source_prefix =
+        args.source_prefix.unwrap_or_else(|| String::from("Error: "));
+    let buf_size = args.buf_size.unwrap_or(64);
+    // Positions are stored in the smallest type the buffer size fits in.
+    let buf_pos = if buf_size > u16::MAX as usize {
+        quote! { usize }
+    } else if buf_size > u8::MAX as usize {
+        quote! { u16 }
+    } else {
+        quote! { u8 }
+    };
+
+    let spill = match &args.spill {
+        Some(spill) => quote! { #spill },
+        None => quote! { ::resext::__private::Vec<u8> },
+    };
+
+    let gen_buf = if let Some(storage) = &args.storage {
+        quote! {
+            type #buf_name = #storage;
+        }
+    } else if alloc {
+        quote! {
+            type #buf_name = ::resext::__private::SpillBuf<#buf_size, #buf_pos, #spill>;
+        }
+    } else {
+        quote! {
+            type #buf_name = ::resext::__private::InlineBuf<#buf_size, #buf_pos>;
+        }
+    };
+
+    // Owned conversions copy the text out of whichever buffer the wrapper
+    // uses, so they only need the `alloc` feature, not `alloc = true`.
+    let alloc_fns = if cfg!(feature = "alloc") {
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Converts the error into its code and fully formatted message.
+                ///
+                /// Meant for bridging into embedding layers and plugin APIs that
+                /// represent errors as code + string pairs, e.g.
+                /// `result.map_err(ResErr::into_code_msg)`.
+                #vis fn into_code_msg(self) -> (i32, ::resext::__private::String) {
+                    use ::resext::__private::ToString;
+
+                    (self.code(), self.to_string())
+                }
+
+                /// Consumes the wrapper and returns its context messages and source error.
+                #vis fn into_parts(self) -> (::resext::__private::String, #enum_ty) {
+                    use ::resext::__private::ToString;
+
+                    (::resext::__private::ContextBuf::as_str(&self.msg).to_string(), self.source)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // `origin` tags the output as `[name] ` so errors from different crates
+    // of a workspace can be told apart.
+    let origin = args.origin.map(|origin| match origin {
+        Origin::Package => quote! { env!("CARGO_PKG_NAME") },
+        Origin::Name(name) => quote! { #name },
+    });
+    let (write_origin, write_origin_colored, origin_fn) = match origin {
+        Some(origin) => (
+            quote! {
+                f.write_str(concat!("[", #origin, "] "))?;
+            },
+            quote! {
+                f.write_str(concat!("\x1b[2m[", #origin, "]\x1b[0m "))?;
+            },
+            quote! {
+                impl #impl_generics #struct_ty #where_clause {
+                    /// Returns the name of the component this error type belongs to.
+                    #vis fn origin(&self) -> &'static str {
+                        #origin
+                    }
+                }
+            },
+        ),
+        None => (quote! {}, quote! {}, quote! {}),
+    };
+
+    // Owned and borrowed `String` messages are copied like `&str`, so they
+    // forward to its impls instead of needing `.as_str()` at every call site.
+    let string_context_impls = if cfg!(feature = "alloc") {
+        quote! {
+            impl<'s, T, E #gp> #trait_name<T, &'s ::resext::__private::String #ga> for ::core::result::Result<T, E>
+            where
+                Self: for<'m> #trait_name<T, &'m str #ga>,
+                #where_preds
+            {
+                #track_caller
+                fn context(self, msg: &'s ::resext::__private::String) -> ::core::result::Result<T, #struct_ty> {
+                    #trait_name::<T, &str #ga>::context(self, msg.as_str())
+                }
+            }
+
+            impl<T, E #gp> #trait_name<T, ::resext::__private::String #ga> for ::core::result::Result<T, E>
+            where
+                Self: for<'m> #trait_name<T, &'m str #ga>,
+                #where_preds
+            {
+                #track_caller
+                fn context(self, msg: ::resext::__private::String) -> ::core::result::Result<T, #struct_ty> {
+                    #trait_name::<T, &str #ga>::context(self, msg.as_str())
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // `static_context!()` messages are checked against the buffer when the
+    // impl is instantiated, counting the bytes written around every message.
+    let static_overhead = delimiter.len() + msg_prefix.len() + msg_suffix.len();
+    let static_context_impl = quote! {
+        impl<T, E, const LEN: usize #gp> #trait_name<T, ::resext::StaticContext<LEN> #ga> for ::core::result::Result<T, E>
+        where
+            Self: for<'m> #trait_name<T, &'m str #ga>,
+            #where_preds
+        {
+            #track_caller
+            fn context(self, msg: ::resext::StaticContext<LEN>) -> ::core::result::Result<T, #struct_ty> {
+                const {
+                    assert!(
+                        LEN <= <#buf_name as ::resext::__private::ContextBuf>::LIMIT.saturating_sub(#static_overhead),
+                        "`static_context!()` message doesn't fit the context buffer, increase `buf_size`",
+                    )
+                };
+
+                #trait_name::<T, &str #ga>::context(self, msg.as_str())
+            }
+        }
+    };
+
+    // Futures resolving to Results get `.context()` before `.await`, which
+    // forwards to the Result impls once the future is done.
+    let future_trait = if args.futures {
+        quote! {
+            /// Extension trait for adding context to futures resolving to Result types.
             ///
             /// # Examples
             ///
             /// ```rust,ignore
-            /// std::fs::read("config.toml")
-            ///     .context("Failed to read config")?;
+            /// let body = fetch(url).context("Failed to fetch page").await?;
             /// ```
-            #[doc(hidden)]
-            fn context(self, msg: S) -> Result<T, #struct_name>;
+            #vis trait #future_trait_name<T, M #gp>: core::future::Future + Sized #where_clause {
+                /// Adds context to the error the future resolves to, if any.
+                ///
+                /// Accepts the same messages as the Result `.context()`. The returned future is
+                /// `Send` if `self` and the message are.
+                fn context(self, msg: M) -> impl core::future::Future<Output = ::core::result::Result<T, #struct_ty>>;
+            }
+
+            impl<Fut, T, E, M #gp> #future_trait_name<T, M #ga> for Fut
+            where
+                Fut: core::future::Future<Output = ::core::result::Result<T, E>>,
+                ::core::result::Result<T, E>: #trait_name<T, M #ga>,
+                #where_preds
+            {
+                fn context(self, msg: M) -> impl core::future::Future<Output = ::core::result::Result<T, #struct_ty>> {
+                    async move { #trait_name::context(self.await, msg) }
+                }
+            }
         }
+    } else {
+        quote! {}
+    };
 
-        impl<T> #trait_name<T, &str> for Result<T, #struct_name> {
-            fn context(self, msg: &str) -> Result<T, #struct_name> {
-                match self {
-                    Ok(ok) => Ok(ok),
-                    Err(mut err) => {
-                        use core::fmt::Write;
+    // With the `diagnostics` feature every context written outside of
+    // `ContextBuf::push_context()` is counted at the call site.
+    let diagnostics = cfg!(feature = "diagnostics");
+    let counted = |err: proc_macro2::TokenStream| {
+        if diagnostics {
+            quote! {
+                {
+                    let mut err = #err;
+                    ::resext::__private::ContextBuf::count_context(&mut err.msg);
+                    err
+                }
+            }
+        } else {
+            err
+        }
+    };
 
-                        if err.msg.is_empty() {
-                            let _ = err.write_str(msg);
-                        } else {
-                            let _ = err.write_str(#delimiter);
-                            let _ = err.write_str(#msg_prefix);
-                            let _ = err.write_str(msg);
-                            let _ = err.write_str(#msg_suffix);
-                        }
+    let count_new = if diagnostics {
+        quote! {
+            if !msg.is_empty() {
+                ::resext::__private::ContextBuf::count_context(&mut buf);
+            }
+        }
+    } else {
+        quote! {}
+    };
 
-                        Err(err)
+    let headline_last = args.headline_last;
+
+    let (user_ops_decl, user_ops_impl, user_ops_impl_from) = if args
+        .user_messages
+    {
+        (
+            quote! {
+                /// Adds context that is also shown to end users by `user_message()`.
+                ///
+                /// # Examples
+                ///
+                /// ```rust,ignore
+                /// let settings = std::fs::read(&path)
+                ///     .context("Failed to read settings.toml")
+                ///     .user_context("Could not load your settings")?;
+                /// ```
+                fn user_context(self, msg: &str) -> ::core::result::Result<T, #struct_ty>;
+            },
+            quote! {
+                #track_caller
+                fn user_context(self, msg: &str) -> ::core::result::Result<T, #struct_ty> {
+                    match #trait_name::context(self, msg) {
+                        Ok(ok) => Ok(ok),
+                        Err(mut err) => {
+                            ::resext::__private::ContextBuf::push_context(
+                                &mut err.user_msg,
+                                msg,
+                                #delimiter,
+                                #msg_prefix,
+                                #msg_suffix,
+                                #headline_last,
+                            );
+                            Err(err)
+                        }
                     }
                 }
+            },
+            quote! {
+                #track_caller
+                fn user_context(self, msg: &str) -> ::core::result::Result<T, #struct_ty> {
+                    #ops_trait_name::user_context(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), msg)
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    let stats_fn = if diagnostics {
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Returns how many contexts were attached and how often the
+                /// context buffer truncated or spilled to the heap.
+                #vis fn stats(&self) -> ::resext::ErrorStats {
+                    ::resext::__private::ContextBuf::stats(&self.msg)
+                }
             }
         }
+    } else {
+        quote! {}
+    };
 
-        impl<T, E> #trait_name<T, &str> for Result<T, E> where #enum_name: From<E> {
-            fn context(self, msg: &str) -> Result<T, #struct_name> {
-                match self {
-                    Ok(ok) => Ok(ok),
-                    Err(err) => Err(#struct_name::new(msg, err)),
+    let json_fn = if cfg!(feature = "alloc") {
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Renders the error as a JSON object with the source error's `Display` output,
+                /// the variant name and the context messages, e.g. for a `--json` output mode:
+                /// `{"error":"404","variant":"Http","contexts":["Failed to fetch page"]}`.
+                #vis fn to_json(&self) -> ::resext::__private::String {
+                    let mut json = ::resext::__private::String::new();
+
+                    let _ = ::resext::__private::fmt_json(
+                        &mut json,
+                        &self.source,
+                        self.source.variant_name(),
+                        self.contexts(),
+                    );
+
+                    json
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let history_fn = if cfg!(feature = "std") {
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Records the error in `log` under its variant name, for "recent errors" views
+                /// queried with `.by_variant()`.
+                #vis fn log_to(&self, log: &::resext::history::ErrorLog) {
+                    log.push_variant(self.source.variant_name(), self);
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let tui_fn = if args.tui {
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Renders the error as styled `ratatui` text, one line per context message
+                /// followed by the source error, e.g. for an error panel in a TUI application.
+                #vis fn to_text(&self) -> ::resext::__private::ratatui_core::text::Text<'_> {
+                    ::resext::__private::error_text(
+                        self.contexts(),
+                        ::resext::__private::ContextBuf::is_truncated(&self.msg),
+                        #source_prefix,
+                        &self.source,
+                    )
+                }
+            }
+
+            impl<'a #gp> From<&'a #struct_ty> for ::resext::__private::ratatui_core::text::Text<'a> #where_clause {
+                fn from(err: &'a #struct_ty) -> Self {
+                    err.to_text()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let serde_fn = if args.serde {
+        quote! {
+            /// Serializes the error as its variant name, the source error's `Display` output and
+            /// the context messages, e.g. for structured JSON logs.
+            impl #impl_generics ::resext::__private::serde::Serialize for #struct_ty #where_clause {
+                fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+                where
+                    S: ::resext::__private::serde::Serializer,
+                {
+                    use ::resext::__private::serde::ser::SerializeStruct;
+
+                    let mut state = serializer.serialize_struct(stringify!(#struct_name), 3)?;
+                    state.serialize_field("variant", self.source.variant_name())?;
+                    state.serialize_field("source", &::resext::__private::SerializeDisplay(&self.source))?;
+                    state.serialize_field("contexts", &self.contexts())?;
+                    state.end()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let error_stack_fn = if cfg!(feature = "error-stack") {
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Converts the error into an `error_stack::Report` with the source error as its
+                /// context and every context message as a printable attachment, innermost first.
+                ///
+                /// `C` is usually the enum itself, any error type it converts into works too.
+                #vis fn into_error_stack<C>(self) -> ::resext::__private::error_stack::Report<C>
+                where
+                    C: From<#enum_ty> + core::error::Error + Send + Sync + 'static,
+                {
+                    ::resext::__private::into_report(
+                        C::from(self.source),
+                        ::resext::ContextFrames::new(
+                            ::resext::__private::ContextBuf::as_str(&self.msg),
+                            ::resext::__private::ContextBuf::frame_ends(&self.msg),
+                            #delimiter,
+                            #msg_prefix,
+                            #msg_suffix,
+                            #headline_last,
+                        ),
+                        ::resext::__private::ContextBuf::is_truncated(&self.msg),
+                        #headline_last,
+                    )
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Runs a context closure, writing the location between the message and
+    // its suffix with `locations`.
+    let call_msg = |delimiter: proc_macro2::TokenStream,
+                    msg_prefix: proc_macro2::TokenStream,
+                    msg_suffix: proc_macro2::TokenStream| {
+        if args.locations {
+            quote! {
+                {
+                    let location = core::panic::Location::caller();
+                    let mut err = msg(err, #delimiter, #msg_prefix, "");
+                    let _ = core::fmt::Write::write_fmt(
+                        &mut err,
+                        format_args!(" at {}:{}", location.file(), location.line()),
+                    );
+                    let _ = core::fmt::Write::write_str(&mut err, #msg_suffix);
+                    err
                 }
             }
+        } else {
+            quote! { msg(err, #delimiter, #msg_prefix, #msg_suffix) }
+        }
+    };
+    // Appended messages record where they end, rotated ones are recorded by
+    // `rotate_tail()`.
+    let end_frame = |call: proc_macro2::TokenStream| {
+        quote! {
+            {
+                let mut err = #call;
+                ::resext::__private::ContextBuf::end_frame(&mut err.msg);
+                err
+            }
+        }
+    };
+    let call_first =
+        end_frame(call_msg(quote! { "" }, quote! { "" }, quote! { "" }));
+
+    // With `headline = "last_context"` new context is written to the end of
+    // the buffer as usual and then rotated to the front, so the buffer stays
+    // inline and Display does not need to know about the ordering.
+    let push_fn_context = if headline_last {
+        let call = call_msg(
+            quote! { "" },
+            quote! { #msg_prefix },
+            quote! { #msg_suffix },
+        );
+
+        quote! {
+            let start = ::resext::__private::ContextBuf::len(&err.msg);
+
+            let mut err = #call;
+            let _ = err.write_str(#delimiter);
+
+            ::resext::__private::ContextBuf::rotate_tail(&mut err.msg, start);
+            err
+        }
+    } else {
+        end_frame(call_msg(
+            quote! { #delimiter },
+            quote! { #msg_prefix },
+            quote! { #msg_suffix },
+        ))
+    };
+    let push_fn_context = counted(quote! {
+        if ::resext::__private::ContextBuf::is_empty(&err.msg) {
+            #call_first
+        } else {
+            #push_fn_context
         }
+    });
+    let new_fn_context = counted(call_first.clone());
+
+    // With `locations`, `&str` context goes through the closure impls, which
+    // write the location.
+    let (str_context_body, str_context_body_from) = if args.locations {
+        let body = quote! {
+            #trait_name::context(self, |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                let _ = core::fmt::Write::write_fmt(&mut err, format_args!("{}{}{}{}", d, mp, msg, ms));
+                err
+            })
+        };
 
-        impl<'a, T, F: FnOnce(#struct_name, &'a str, &'a str, &'a str) -> #struct_name>  #trait_name<T, F> for Result<T, #struct_name> {
-            fn context(self, msg: F) -> Result<T, #struct_name> {
+        (body.clone(), body)
+    } else {
+        (
+            quote! {
                 match self {
                     Ok(ok) => Ok(ok),
                     Err(mut err) => {
-                        use core::fmt::Write;
-
-                        let err = if err.msg.is_empty() {
-                            msg(err, "", "", "")
-                        } else {
-                            msg(err, #delimiter, #msg_prefix, #msg_suffix)
-                        };
+                        ::resext::__private::ContextBuf::push_context(
+                            &mut err.msg,
+                            msg,
+                            #delimiter,
+                            #msg_prefix,
+                            #msg_suffix,
+                            #headline_last,
+                        );
 
                         Err(err)
                     }
                 }
+            },
+            quote! {
+                match self {
+                    Ok(ok) => Ok(ok),
+                    Err(err) => Err(#struct_name::new(msg, err)),
+                }
+            },
+        )
+    };
+    let contexts_view = quote! {
+        ::resext::Contexts::from_buf(&self.msg, #delimiter, #headline_last)
+    };
+
+    // The colored and the `{:#}` single-line rendering only exist for the
+    // default layout, `format` and `formatter` replace it entirely.
+    let default_layout = args.formatter.is_none() && args.format.is_none();
+    let layout_display = if default_layout {
+        quote! {
+            if f.alternate() {
+                #write_origin
+
+                return ::resext::__private::fmt_compact(
+                    f,
+                    self.contexts(),
+                    ::resext::__private::ContextBuf::is_truncated(&self.msg),
+                    &self.source,
+                );
             }
         }
+    } else {
+        quote! {}
+    };
 
-        impl<'a, T, F: FnOnce(#struct_name, &'a str, &'a str, &'a str) -> #struct_name, E> #trait_name<T, F> for Result<T, E> where #enum_name: From<E> {
-            fn context(self, msg: F) -> Result<T, #struct_name> {
-                match self {
-                    Ok(ok) => Ok(ok),
-                    Err(err) => {
-                        use core::fmt::Write;
+    let display_body = match (args.formatter, args.format) {
+        (_, Some(parts)) => {
+            let writes = parts.iter().map(|part| match part {
+                TemplatePart::Literal(text) => quote! {
+                    f.write_str(#text)?;
+                },
 
-                        let buf = #buf_name::new();
-                        let mut err = #struct_name { msg: buf, source: #enum_name::from(err) };
+                TemplatePart::Prefix => quote! {
+                    f.write_str(#prefix)?;
+                },
 
-                        let err = msg(err, "", "", "");
+                TemplatePart::Suffix => quote! {
+                    f.write_str(#suffix)?;
+                },
 
-                        Err(err)
+                TemplatePart::Contexts(_) => quote! {
+                    write!(f, "{}", #contexts_view)?;
+                },
+
+                TemplatePart::Newline => quote! {
+                    if !::resext::__private::ContextBuf::is_empty(&self.msg) {
+                        f.write_str("\n")?;
                     }
-                }
+                },
+
+                TemplatePart::Source => quote! {
+                    write!(f, "{}", self.source)?;
+                },
+
+                TemplatePart::Variant => quote! {
+                    f.write_str(self.source.variant_name())?;
+                },
+            });
+
+            quote! {
+                #(#writes)*
+
+                Ok(())
             }
         }
 
-        #vis type #alias<T> = Result<T, #struct_name>;
+        (Some(formatter), None) => quote! {
+            <#formatter as ::resext::ErrorFormatter>::format(
+                #contexts_view,
+                &self.source,
+                f,
+            )
+        },
+
+        (None, None) => quote! {
+            ::resext::__private::fmt_error(
+                f,
+                #prefix,
+                #contexts_view,
+                #source_prefix,
+                &self.source,
+                #suffix,
+            )
+        },
+    };
+
+    let debug_related = if args.related {
+        quote! { .and_then(|()| ::resext::__private::fmt_related(f, &self.related)) }
+    } else {
+        quote! {}
+    };
+
+    // Variants with `debug_summary` cut the source's `Debug` output, keeping
+    // panic messages and test failures readable with large payloads.
+    let debug_source = if variant_debug_summaries.iter().any(Option::is_some) {
+        let debug_summary_arms = variants
+            .iter()
+            .zip(&variant_debug_summaries)
+            .map(|(variant, limit)| {
+                let variant_name = &variant.ident;
+                let limit = match limit {
+                    Some(limit) => {
+                        quote! { ::core::option::Option::Some(#limit) }
+                    }
+                    None => quote! { ::core::option::Option::None },
+                };
+
+                quote! {
+                    #enum_name::#variant_name { .. } => #limit,
+                }
+            });
+
+        quote! {
+            &::resext::__private::DebugSummary(
+                &self.source,
+                match &self.source {
+                    #(#debug_summary_arms)*
+                },
+            )
+        }
+    } else {
+        quote! { &format_args!("{:?}", self.source) }
+    };
+
+    let debug_notes = if args.notes {
+        quote! { .and_then(|()| ::resext::__private::fmt_annotations(f, "note", &self.notes)) }
+    } else {
+        quote! {}
+    };
+
+    let debug_docs_url = if args.show_docs_url {
+        quote! { .and_then(|()| ::resext::__private::fmt_annotations(f, "see", self.source.docs_url().as_slice())) }
+    } else {
+        quote! {}
+    };
+
+    let debug_help = if args.help {
+        quote! { .and_then(|()| ::resext::__private::fmt_annotations(f, "help", &self.help)) }
+    } else {
+        quote! {}
+    };
+
+    let (debug_backtrace, backtrace_fn) = if args.backtrace {
+        (
+            quote! { .and_then(|()| ::resext::__private::fmt_backtrace(f, self.backtrace.as_deref())) },
+            quote! {
+                impl #impl_generics #struct_ty #where_clause {
+                    /// Returns the backtrace captured when the error was created, if
+                    /// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` enabled capturing.
+                    #vis fn backtrace(&self) -> Option<&::resext::__private::Backtrace> {
+                        self.backtrace.as_deref()
+                    }
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // Lines below the main chain, shared by `Display` and `render_colored()`.
+    let mut display_tail = Vec::new();
+
+    // Notes belong to the main error, so they come before related errors.
+    let notes_fn = if args.notes {
+        display_tail.push(quote! {
+            ::resext::__private::fmt_annotations(f, "note", &self.notes)?;
+        });
+
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Adds a note, rendered as a `note: ` line below the context messages.
+                ///
+                /// Keeps metadata like environment details or request IDs out of the
+                /// context chain, which explains what failed.
+                #vis fn note<N: Into<::resext::__private::Cow<'static, str>>>(mut self, note: N) -> Self {
+                    self.push_note(note);
+                    self
+                }
+
+                /// Adds a note in place, see [`Self::note()`].
+                #vis fn push_note<N: Into<::resext::__private::Cow<'static, str>>>(&mut self, note: N) {
+                    self.notes.push(note.into());
+                }
+
+                /// Returns the notes added with `note()`, in the order they were added.
+                #vis fn notes(&self) -> impl Iterator<Item = &str> {
+                    self.notes.iter().map(|note| note.as_ref())
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // The docs link also describes the main error, but is kept below its notes
+    // so it reads as the last line about it.
+    if args.show_docs_url {
+        display_tail.push(quote! {
+            ::resext::__private::fmt_annotations(f, "see", self.source.docs_url().as_slice())?;
+        });
+    }
+
+    let related_fn = if args.related {
+        display_tail.push(quote! {
+            ::resext::__private::fmt_related(f, &self.related)?;
+        });
+
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Attaches a secondary error, rendered in an indented
+                /// "related errors" list below the main chain.
+                ///
+                /// Useful when one failure causes or accompanies others,
+                /// e.g. a cleanup step failing after the original error.
+                #vis fn add_related<E>(mut self, err: E) -> Self where #struct_ty: From<E> {
+                    self.push_related(err);
+                    self
+                }
+
+                /// Attaches a secondary error in place, see [`Self::add_related()`].
+                #vis fn push_related<E>(&mut self, err: E) where #struct_ty: From<E> {
+                    self.related.push(#struct_name::from(err));
+                }
+
+                /// Returns the secondary errors attached to this error.
+                #vis fn related(&self) -> &[#struct_ty] {
+                    &self.related
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let help_fn = if args.help {
+        display_tail.push(quote! {
+            ::resext::__private::fmt_annotations(f, "help", &self.help)?;
+        });
+
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Adds a suggestion for fixing the error, rendered as a `help: ` line below
+                /// the context messages and any related errors.
+                ///
+                /// Kept apart from the context, which explains what failed, for guidance
+                /// like "try running with --force".
+                #vis fn help<H: Into<::resext::__private::Cow<'static, str>>>(mut self, help: H) -> Self {
+                    self.push_help(help);
+                    self
+                }
+
+                /// Adds a suggestion in place, see [`Self::help()`].
+                #vis fn push_help<H: Into<::resext::__private::Cow<'static, str>>>(&mut self, help: H) {
+                    self.help.push(help.into());
+                }
+
+                /// Returns the suggestions added with `help()`, in the order they were added.
+                #vis fn help_messages(&self) -> impl Iterator<Item = &str> {
+                    self.help.iter().map(|help| help.as_ref())
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let user_fn = if args.user_messages {
+        let user_facing_source = if user_facing_variants.is_empty() {
+            quote! { None }
+        } else {
+            quote! {
+                match &self.source {
+                    #(#enum_name::#user_facing_variants { .. })|* => Some(&self.source),
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+        };
+
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Returns the error as shown to end users: context added with
+                /// `.user_context()`, followed by the source error if its variant is marked
+                /// `#[resext(user_facing)]`.
+                ///
+                /// `Display` keeps rendering the full chain for logs.
+                #vis fn user_message(&self) -> ::resext::UserMessage<'_> {
+                    ::resext::UserMessage::new(
+                        ::resext::__private::ContextBuf::as_str(&self.user_msg),
+                        #user_facing_source,
+                    )
+                }
+
+                /// Adds user-facing context in place, see [`Self::user_message()`].
+                #vis fn push_user_context(&mut self, msg: &str) {
+                    ::resext::__private::ContextBuf::push_context(
+                        &mut self.msg,
+                        msg,
+                        #delimiter,
+                        #msg_prefix,
+                        #msg_suffix,
+                        #headline_last,
+                    );
+                    ::resext::__private::ContextBuf::push_context(
+                        &mut self.user_msg,
+                        msg,
+                        #delimiter,
+                        #msg_prefix,
+                        #msg_suffix,
+                        #headline_last,
+                    );
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let attachments_fn = if args.attachments {
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Attaches a typed value, e.g. the path or request ID involved, replacing
+                /// any earlier value of the same type.
+                #vis fn attach<A: core::any::Any + Send + Sync>(mut self, value: A) -> Self {
+                    self.attachments.insert(value);
+                    self
+                }
+
+                /// Returns the attached value of type `A`, if any.
+                #vis fn get_attached<A: core::any::Any + Send + Sync>(&self) -> Option<&A> {
+                    self.attachments.get::<A>()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let id_fn = if args.id.is_some() {
+        display_tail.push(quote! {
+            write!(f, "\nerror id: {:04x}", self.id)?;
+        });
+
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Returns the ID assigned to this error occurrence, for
+                /// correlating user-visible errors with logs.
+                #vis fn id(&self) -> u32 {
+                    self.id
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let display_body = if display_tail.is_empty() {
+        display_body
+    } else {
+        quote! {
+            { #display_body }?;
+            #(#display_tail)*
+            Ok(())
+        }
+    };
+
+    let color_fn = if default_layout {
+        // Reports to stderr, unlike `Display`, know where they end up, so
+        // they are the only place `color = true` applies.
+        let color_report = if args.color {
+            quote! {
+                impl #impl_generics ::resext::__private::ColorReport for #struct_ty #where_clause {
+                    fn fmt_report(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                        if ::resext::__private::use_color() {
+                            core::fmt::Display::fmt(&self.render_colored(), f)
+                        } else {
+                            core::fmt::Display::fmt(self, f)
+                        }
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            impl #impl_generics #struct_ty #where_clause {
+                /// Renders the error with ANSI colors, regardless of where it is printed.
+                ///
+                /// The prefix is bold, context messages are cyan with dimmed delimiters and the
+                /// source prefix is bold red. With `color = true`, `.or_exit()` and
+                /// `#[resext::main]` use it on their own when stderr is a terminal.
+                #vis fn render_colored(&self) -> impl core::fmt::Display + '_ {
+                    ::resext::__private::DisplayFn(move |f: &mut core::fmt::Formatter| {
+                        #write_origin_colored
+                        ::resext::__private::fmt_colored(
+                            f,
+                            #prefix,
+                            // The message affixes are kept and painted with the message.
+                            ::resext::ContextFrames::new(
+                                ::resext::__private::ContextBuf::as_str(&self.msg),
+                                ::resext::__private::ContextBuf::frame_ends(&self.msg),
+                                #delimiter,
+                                "",
+                                "",
+                                #headline_last,
+                            ),
+                            ::resext::__private::ContextBuf::is_truncated(&self.msg),
+                            #source_prefix,
+                            &self.source,
+                            #suffix,
+                        )?;
+                        #(#display_tail)*
+                        Ok(())
+                    })
+                }
+            }
+
+            #color_report
+        }
+    } else {
+        quote! {}
+    };
+
+    // In opaque mode the enum lives in a private module so it can't be named
+    // outside of the crate, only the wrapper and its `kind()` are public API.
+    let (enum_item, source_vis, opaque_items) = if args.opaque {
+        let mut private = input.clone();
+        private.vis = syn::parse_quote!(pub);
+
+        let mod_name = quote::format_ident!("__resext_opaque_{}", enum_name);
+        let kind_name = quote::format_ident!("{}Kind", alias.to_string());
+
+        let kind_variants = variants.iter().map(|variant| {
+            let variant_name = &variant.ident;
+            let doc = format!(" A `{}::{}` error.", enum_name, variant_name);
+
+            quote! {
+                #[doc = #doc]
+                #variant_name
+            }
+        });
+        let kind_arms = variants.iter().map(|variant| {
+            let variant_name = &variant.ident;
+
+            quote! {
+                #enum_name::#variant_name { .. } => #kind_name::#variant_name,
+            }
+        });
+
+        (
+            quote! {
+                #[allow(non_snake_case)]
+                mod #mod_name {
+                    use super::*;
+
+                    #[derive(Debug)]
+                    #private
+                }
+
+                use #mod_name::#enum_name;
+            },
+            quote! {},
+            quote! {
+                /// Kinds of errors that can occur, without their payload.
+                #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+                #[non_exhaustive]
+                #vis enum #kind_name {
+                    #(#kind_variants,)*
+                }
+
+                impl #impl_generics #struct_ty #where_clause {
+                    /// Returns the kind of the wrapped error.
+                    #vis fn kind(&self) -> #kind_name {
+                        match &self.source {
+                            #(#kind_arms)*
+                        }
+                    }
+                }
+            },
+        )
+    } else {
+        (
+            quote! {
+                #[derive(Debug)]
+                #input
+            },
+            quote! { #vis },
+            quote! {},
+        )
+    };
+
+    let enum_impls = quote! {
+        #enum_item
+
+        impl #impl_generics core::fmt::Display for #enum_ty #where_clause {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                match self {
+                    #(#display_match_arms)*
+                }
+            }
+        }
+
+        impl #impl_generics core::error::Error for #enum_ty #where_clause {
+            fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+                #[allow(unused_imports)]
+                use ::resext::__private::{SourceError as _, SourceOther as _};
+
+                match self {
+                    #(#source_arms)*
+                }
+            }
+        }
+
+        impl #impl_generics ::resext::ErrorVariant for #enum_ty #where_clause {
+            fn variant_index(&self) -> usize {
+                self.variant_index()
+            }
+
+            fn variant_name(&self) -> &'static str {
+                self.variant_name()
+            }
+
+            fn code(&self) -> i32 {
+                self.code()
+            }
+        }
+
+        impl #impl_generics #enum_ty #where_clause {
+            /// Returns the position of this variant in the enum declaration.
+            ///
+            /// Useful as a stable sort / grouping key for collections of errors.
+            #vis fn variant_index(&self) -> usize {
+                match self {
+                    #(#variant_index_arms)*
+                }
+            }
+
+            /// Returns the name of this variant as written in the enum declaration.
+            #vis fn variant_name(&self) -> &'static str {
+                match self {
+                    #(#variant_name_arms)*
+                }
+            }
+
+            /// Returns the numeric error code of this variant.
+            ///
+            /// Codes come from explicit discriminants (`Io(std::io::Error) = 10`)
+            /// and follow Rust's discriminant rules for variants without one.
+            #vis fn code(&self) -> i32 {
+                match self {
+                    #(#code_arms)*
+                }
+            }
+
+            /// Returns the process exit code for this variant, set with
+            /// `#[resext(exit_code = "EX_IOERR")]` on the variant (default: 1).
+            #vis fn exit_code(&self) -> i32 {
+                match self {
+                    #(#exit_code_arms)*
+                }
+            }
+
+            /// Returns the documentation URL of this variant, set with
+            /// `#[resext(docs_url = "https://docs.example.com/errors/E042")]` on the variant.
+            #vis fn docs_url(&self) -> ::core::option::Option<&'static str> {
+                match self {
+                    #(#docs_url_arms)*
+                }
+            }
+
+            /// Returns the `sysexits.h` code for this variant: its `exit_code` if set, otherwise
+            /// the code `resext::exitcode::for_error()` maps the wrapped error to, e.g.
+            /// `EX_NOINPUT` for a missing file (default: `EX_SOFTWARE`).
+            #vis fn sysexit_code(&self) -> i32 {
+                #[allow(unused_imports)]
+                use ::resext::__private::{SysexitError as _, SysexitOther as _};
+
+                match self {
+                    #(#sysexit_arms)*
+                }
+            }
+        }
+
+        #(#enum_from_impls)*
+
+        #enum_infallible_impl
+
+        #(#interconvert_impls)*
+
+        #anyhow_impl
+    };
+
+    // `minimal` stops at the enum impls, leaving out the wrapper, traits and alias.
+    if args.minimal {
+        if let Some(error) = errors {
+            return TokenStream::from(error.to_compile_error());
+        }
+
+        return TokenStream::from(enum_impls);
+    }
+
+    // Docs of the wrapper, alias and context trait name the user's own
+    // types, so `cargo doc` on their crate shows the API they call.
+    let example_variant = variants
+        .iter()
+        .find(|variant| variant.fields.len() == 1)
+        .map(|variant| match &variant.fields {
+            syn::Fields::Named(fields) => {
+                let field = fields.named[0].ident.as_ref().unwrap();
+                format!("{}::{} {{ {}: err }}", enum_name, variant.ident, field)
+            }
+            _ => format!("{}::{}(err)", enum_name, variant.ident),
+        })
+        .unwrap_or_else(|| String::from("err"));
+    let variant_list = variants
+        .iter()
+        .map(|variant| format!("`{}`", variant.ident))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let struct_doc = format!(
+        " Wraps one of the `{enum_name}` variants ({variant_list}) together with the context \
+         messages added while it propagated.\n\n\
+         Created by `.context()` on a `Result` whose error converts into `{enum_name}`, and \
+         returned by functions using the `{alias}<T>` alias.\n\n\
+         # Examples\n\n\
+         ```rust,ignore\n\
+         fn load(path: &str) -> {alias}<String> {{\n    \
+             let config = std::fs::read_to_string(path).context(\"Failed to read config\")?;\n\n    \
+             Ok(config)\n\
+         }}\n\n\
+         if let Err(err) = load(\"config.toml\") {{\n    \
+             eprintln!(\"{{}}\", err);\n\
+         }}\n\
+         ```"
+    );
+    let alias_doc = format!(
+        " `Result` with `{struct_name}` as its error, i.e. `{enum_name}` with context.\n\n\
+         # Examples\n\n\
+         ```rust,ignore\n\
+         fn load(path: &str) -> {alias}<Vec<u8>> {{\n    \
+             std::fs::read(path).context(\"Failed to read file\")\n\
+         }}\n\
+         ```"
+    );
+    let trait_doc = format!(
+        " Extension trait for adding context to `Result`s, turning their error into a \
+         `{struct_name}`.\n\n\
+         Implemented for every `Result<T, E>` where `E` converts into `{enum_name}`, and for \
+         `{alias}<T>` itself, which appends to the existing context.\n\n\
+         # Examples\n\n\
+         ```rust,ignore\n\
+         let bytes: {alias}<Vec<u8>> = std::fs::read(\"file.txt\")\n    \
+             .context(\"Failed to read file\");\n\
+         ```"
+    );
+    let new_doc = format!(
+        " Creates a `{struct_name}` with `msg` as its first context message, without going \
+         through `.context()` on a `Result`.\n\n\
+         # Examples\n\n\
+         ```rust,ignore\n\
+         let err = {struct_name}::new(\"Failed to read file\", {example_variant});\n\
+         ```"
+    );
+    let from_args_doc = format!(
+        " Like `{struct_name}::new()`, but takes a `ctx!()` message, which is only formatted \
+         into the buffer.\n\n\
+         # Examples\n\n\
+         ```rust,ignore\n\
+         let err = {struct_name}::from_args(ctx!(\"Failed to read {{}}\", path), {example_variant});\n\
+         ```"
+    );
+
+    let expanded = quote! {
+        #enum_impls
+
+        #[doc = #struct_doc]
+        #vis struct #struct_name #decl_generics #where_clause {
+            msg: #buf_name,
+            /// The wrapped error.
+            #source_vis source: #enum_ty,
+            #id_field
+            #related_field
+            #notes_field
+            #help_field
+            #attachments_field
+            #user_field
+            #backtrace_field
+        }
+        impl #impl_generics core::error::Error for #struct_ty where #where_preds #enum_ty: 'static {
+            // The wrapper already prints the enum, so the chain starts at
+            // the enum's source.
+            fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+                core::error::Error::source(&self.source)
+            }
+        }
+
+
+        impl #impl_generics core::fmt::Write for #struct_ty #where_clause {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                if s.is_empty() {
+                    Ok(())
+                } else {
+                    self.msg.write_str(s)
+                }
+            }
+        }
+
+        impl #impl_generics core::fmt::Display for #struct_ty #where_clause {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                if f.width().is_some() || f.precision().is_some() {
+                    return ::resext::__private::fmt_padded(f, self);
+                }
+
+                #layout_display
+
+                #write_origin
+                #display_body
+            }
+        }
+
+        impl #impl_generics core::fmt::Debug for #struct_ty #where_clause {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                #write_origin
+                ::resext::__private::fmt_error(
+                    f,
+                    #prefix,
+                    #contexts_view,
+                    #source_prefix,
+                    #debug_source,
+                    #suffix,
+                )
+                #debug_notes
+                #debug_docs_url
+                #debug_related
+                #debug_help
+                #debug_backtrace
+            }
+        }
+
+        impl #impl_generics ::resext::ErrorVariant for #struct_ty #where_clause {
+            fn variant_index(&self) -> usize {
+                self.source.variant_index()
+            }
+
+            fn variant_name(&self) -> &'static str {
+                self.source.variant_name()
+            }
+
+            fn code(&self) -> i32 {
+                self.source.code()
+            }
+        }
+
+        impl #impl_generics #struct_ty #where_clause {
+            /// Returns the numeric error code of the wrapped error.
+            #vis fn code(&self) -> i32 {
+                self.source.code()
+            }
+
+            /// Returns the process exit code of the wrapped error's variant.
+            #vis fn exit_code(&self) -> i32 {
+                self.source.exit_code()
+            }
+
+            /// Returns the `sysexits.h` code of the wrapped error, see the enum's `sysexit_code()`.
+            #vis fn sysexit_code(&self) -> i32 {
+                self.source.sysexit_code()
+            }
+
+            /// Returns the documentation URL of the wrapped error's variant, if it has one.
+            #vis fn docs_url(&self) -> ::core::option::Option<&'static str> {
+                self.source.docs_url()
+            }
+
+            /// Maps the source error to another one, keeping all context messages.
+            ///
+            /// Useful for reclassifying an error after the fact, e.g. turning a generic `Io`
+            /// into a more specific `ConfigNotFound`.
+            #vis fn map_source<F: FnOnce(#enum_ty) -> #enum_ty>(self, f: F) -> Self {
+                Self { source: f(self.source), ..self }
+            }
+
+            /// Consumes the wrapper and returns the source error, dropping all context messages.
+            ///
+            /// Useful for retry logic or APIs that need ownership of the original error.
+            #vis fn into_source(self) -> #enum_ty {
+                self.source
+            }
+
+            /// Replaces the source error, keeping all context messages.
+            #vis fn replace_source<E>(self, source: E) -> Self where #enum_ty: From<E> {
+                Self { source: #enum_name::from(source), ..self }
+            }
+
+            /// Renders the full error into `buf` without allocating.
+            ///
+            /// Returns the number of bytes written, or if `buf` is too small, the number of
+            /// bytes needed, with as much of the error as fits written to `buf`, cut at a
+            /// `char` boundary.
+            #vis fn fmt_into(&self, buf: &mut [u8]) -> ::core::result::Result<usize, usize> {
+                ::resext::__private::fmt_into(self, buf)
+            }
+
+            /// Returns the error's metadata as key-value pairs for log and tracing backends:
+            /// `variant`, `code`, `exit_code` and, if the wrapper has them, `origin` and `id`.
+            #vis fn fields(&self) -> impl Iterator<Item = (&'static str, ::resext::FieldValue<'static>)> {
+                [
+                    ("variant", ::resext::FieldValue::Str(self.source.variant_name())),
+                    ("code", ::resext::FieldValue::Int(self.code() as i64)),
+                    ("exit_code", ::resext::FieldValue::Int(self.exit_code() as i64)),
+                    #extra_fields
+                ]
+                .into_iter()
+            }
+
+            /// Returns an iterator over the context messages, without delimiters and affixes.
+            #vis fn contexts(&self) -> ::resext::ContextFrames<'_> {
+                ::resext::ContextFrames::new(
+                    ::resext::__private::ContextBuf::as_str(&self.msg),
+                    ::resext::__private::ContextBuf::frame_ends(&self.msg),
+                    #delimiter,
+                    #msg_prefix,
+                    #msg_suffix,
+                    #headline_last,
+                )
+            }
+
+            /// Returns the length of the `Display` output in chars, without ANSI escape codes.
+            ///
+            /// Counts without allocating, e.g. for laying out error panes in a TUI.
+            #vis fn display_len(&self) -> usize {
+                ::resext::__private::display_len(self)
+            }
+
+            /// Returns `true` if context was dropped because the context buffer was full.
+            ///
+            /// Truncated context is displayed with a trailing `...`.
+            #vis fn was_truncated(&self) -> bool {
+                ::resext::__private::ContextBuf::is_truncated(&self.msg)
+            }
+
+            #[doc = #new_doc]
+            #vis fn new<E>(msg: &str, source: E) -> Self where #enum_ty: From<E> {
+                use core::fmt::Write;
+                let mut buf = #buf_name::default();
+                let _ = buf.write_str(msg);
+                ::resext::__private::ContextBuf::end_frame(&mut buf);
+                #count_new
+                Self { msg: buf, source: #enum_name::from(source), #extra_init }
+            }
+
+            #[doc = #from_args_doc]
+            #vis fn from_args<E, F: FnOnce(#struct_ty, &str, &str, &str) -> #struct_ty>(msg: F, source: E) -> Self where #enum_ty: From<E> {
+                let err = Self { msg: #buf_name::default(), source: #enum_name::from(source), #extra_init };
+
+                #new_fn_context
+            }
+        }
+
+        impl #impl_generics From<#enum_ty> for #struct_ty #where_clause {
+            fn from(value: #enum_ty) -> Self {
+                Self { msg: #buf_name::default(), source: value, #extra_init }
+            }
+        }
+
+        impl<E #gp> ::resext::FromCtx<E> for #struct_ty where #enum_ty: From<E>, #where_preds {
+            #track_caller
+            fn from_ctx(source: E, msg: core::fmt::Arguments<'_>) -> Self {
+                use core::fmt::Write;
+
+                #trait_name::context(Err::<(), E>(source), |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                    let _ = write!(err, "{}{}{}{}", d, mp, msg, ms);
+                    err
+                })
+                .unwrap_err()
+            }
+        }
+
+        impl #impl_generics ::resext::FromCtx<#struct_ty> for #struct_ty #where_clause {
+            #track_caller
+            fn from_ctx(source: #struct_ty, msg: core::fmt::Arguments<'_>) -> Self {
+                use core::fmt::Write;
+
+                #trait_name::context(Err::<(), #struct_ty>(source), |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                    let _ = write!(err, "{}{}{}{}", d, mp, msg, ms);
+                    err
+                })
+                .unwrap_err()
+            }
+        }
+
+        #(#struct_from_impls)*
+
+        #struct_infallible_impl
+
+        #[doc = #trait_doc]
+        #vis trait #trait_name<T, S #gp> #where_clause {
+            /// Add context to an error.
+            ///
+            /// Accepts `&str`, `String` / `&String` (with `alloc`) or `ctx!()`. The message is only
+            /// copied into the error if an error occurs.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// std::fs::read("config.toml")
+            ///     .context("Failed to read config")?;
+            /// ```
+            fn context(self, msg: S) -> ::core::result::Result<T, #struct_ty>;
+        }
+
+        impl<T #gp> #trait_name<T, &str #ga> for ::core::result::Result<T, #struct_ty> #where_clause {
+            #track_caller
+            fn context(self, msg: &str) -> ::core::result::Result<T, #struct_ty> {
+                #truncated_before
+                let res = { #str_context_body };
+                #warn_truncated_push
+
+                res
+            }
+        }
+
+        impl<T, E #gp> #trait_name<T, &str #ga> for ::core::result::Result<T, E> where #enum_ty: From<E>, #where_preds {
+            #track_caller
+            fn context(self, msg: &str) -> ::core::result::Result<T, #struct_ty> {
+                let res = { #str_context_body_from };
+                #warn_truncated_new
+
+                res
+            }
+        }
+
+        #string_context_impls
+
+        #static_context_impl
+
+        #future_trait
+
+        impl<'a, T, F: FnOnce(#struct_ty, &'a str, &'a str, &'a str) -> #struct_ty #gp> #trait_name<T, F #ga> for ::core::result::Result<T, #struct_ty> #where_clause {
+            #track_caller
+            fn context(self, msg: F) -> ::core::result::Result<T, #struct_ty> {
+                #truncated_before
+                let res = match self {
+                    Ok(ok) => Ok(ok),
+                    Err(mut err) => {
+                        use core::fmt::Write;
+
+                        Err(#push_fn_context)
+                    }
+                };
+                #warn_truncated_push
+
+                res
+            }
+        }
+
+        impl<'a, T, F: FnOnce(#struct_ty, &'a str, &'a str, &'a str) -> #struct_ty, E #gp> #trait_name<T, F #ga> for ::core::result::Result<T, E> where #enum_ty: From<E>, #where_preds {
+            #track_caller
+            fn context(self, msg: F) -> ::core::result::Result<T, #struct_ty> {
+                let res = match self {
+                    Ok(ok) => Ok(ok),
+                    Err(err) => {
+                        let err = #struct_name { msg: #buf_name::default(), source: #enum_name::from(err), #extra_init };
+
+                        Err(#new_fn_context)
+                    }
+                };
+                #warn_truncated_new
+
+                res
+            }
+        }
+
+        /// Extension trait with combinators for Result types.
+        ///
+        /// Kept separate from the context trait since these methods don't
+        /// depend on the type of the context message.
+        #vis trait #ops_trait_name<T #gp> #where_clause {
+            /// Error type of the Result.
+            type Error;
+
+            /// Attempts to recover from an error with `f`, which gets a reference
+            /// to the original error.
+            ///
+            /// If the recovery fails too, `"recovery attempted after: "`
+            /// followed by the original error's contexts and source is added
+            /// as context to the new error.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// std::fs::read("config.toml")
+            ///     .context("Failed to read config")
+            ///     .or_else_ctx(|_| std::fs::read("default.toml").context("Failed to read defaults"))?;
+            /// ```
+            fn or_else_ctx<E, F: FnOnce(&#struct_ty) -> ::core::result::Result<T, E>>(self, f: F) -> ::core::result::Result<T, #struct_ty> where #struct_ty: From<E>;
+
+            /// Calls `f` with the error, if any, and passes the Result through unchanged.
+            ///
+            /// Useful for observability hooks (metrics, logging) without breaking a `?` chain.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// std::fs::read("config.toml")
+            ///     .context("Failed to read config")
+            ///     .on_err(|_| metrics::increment("config_errors"))?;
+            /// ```
+            fn on_err<F: FnOnce(&Self::Error)>(self, f: F) -> Self;
+
+            /// Calls `f` with the value, if any, and passes the Result through unchanged.
+            fn on_ok<F: FnOnce(&T)>(self, f: F) -> Self;
+
+            /// Adds `Record {row}` as context to an error, for per-record pipelines.
+            ///
+            /// `row` is written as is, pass a 1-based number for user-facing output. Combine with
+            /// `resext::CollectErrors` to report every bad record at once.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// let records = rows
+            ///     .enumerate()
+            ///     .map(|(idx, row)| parse_record(row).with_row_context(idx + 1))
+            ///     .collect_errors()?;
+            /// ```
+            fn with_row_context(self, row: usize) -> ::core::result::Result<T, #struct_ty>;
+
+            /// Adds the `Display` output of the value `f` returns as context.
+            ///
+            /// `f` is only called if an error occurs, and its value is written straight into the
+            /// context buffer without an intermediate `String`.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// std::fs::write(&path, &data)
+            ///     .with_context(|| resext::ctx_bytes(data.len() as u64))?;
+            /// ```
+            fn with_context<D: core::fmt::Display, F: FnOnce() -> D>(self, f: F) -> ::core::result::Result<T, #struct_ty>;
+
+            /// Adds raw bytes as context, failing if they aren't valid UTF-8.
+            ///
+            /// The bytes are only checked if an error occurs. On invalid UTF-8 the
+            /// `Utf8Error` is returned instead and the original error is dropped,
+            /// use `.lossy_context()` to keep it.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// let data = std::fs::read(path)
+            ///     .raw_context(path_bytes)??;
+            /// ```
+            fn raw_context(self, bytes: &[u8]) -> ::core::result::Result<::core::result::Result<T, #struct_ty>, core::str::Utf8Error>;
+
+            /// Adds raw bytes as context, replacing invalid UTF-8 sequences with `U+FFFD`.
+            ///
+            /// Doesn't allocate, the bytes are only converted if an error occurs.
+            fn lossy_context(self, bytes: &[u8]) -> ::core::result::Result<T, #struct_ty>;
+
+            /// Drops all context messages, leaving only the bare enum as the error.
+            ///
+            /// Lets functions whose signatures only expose the enum call helpers returning the
+            /// wrapper with `?`. A `From` impl can't do this, it would overlap with the
+            /// conversions from every type the enum wraps. Chain `.on_err()` first to log the
+            /// context before it is dropped.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// fn load() -> Result<Config, AppError> {
+            ///     let text = read_config().strip_context()?;
+            ///     // ...
+            /// }
+            /// ```
+            fn strip_context(self) -> ::core::result::Result<T, #enum_ty>;
+
+            /// Converts the error into the wrapper without adding context.
+            ///
+            /// What `?` does on return, for places that need the wrapper type without returning,
+            /// e.g. collecting results into a `Vec<Res<T>>`.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// let results: Vec<Res<Vec<u8>>> = paths.iter().map(|path| std::fs::read(path).map_err_into()).collect();
+            /// ```
+            fn map_err_into(self) -> ::core::result::Result<T, #struct_ty>;
+
+            #std_ops_decls
+
+            #attach_ops_decl
+
+            #notes_ops_decl
+
+            #help_ops_decl
+
+            #user_ops_decl
+        }
+
+        impl<T #gp> #ops_trait_name<T #ga> for ::core::result::Result<T, #struct_ty> #where_clause {
+            type Error = #struct_ty;
+
+            fn on_err<F: FnOnce(&#struct_ty)>(self, f: F) -> Self {
+                if let Err(err) = &self {
+                    f(err);
+                }
+
+                self
+            }
+
+            fn on_ok<F: FnOnce(&T)>(self, f: F) -> Self {
+                if let Ok(ok) = &self {
+                    f(ok);
+                }
+
+                self
+            }
+
+            #track_caller
+            fn or_else_ctx<E, F: FnOnce(&#struct_ty) -> ::core::result::Result<T, E>>(self, f: F) -> ::core::result::Result<T, #struct_ty> where #struct_ty: From<E> {
+                use core::fmt::Write;
+
+                let original = match self {
+                    Ok(ok) => return Ok(ok),
+                    Err(err) => err,
+                };
+
+                let chain = |err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                    let mut err = err;
+
+                    let _ = write!(err, "{}{}recovery attempted after: ", d, mp);
+
+                    if !::resext::__private::ContextBuf::is_empty(&original.msg) {
+                        let _ = err.write_str(::resext::__private::ContextBuf::as_str(&original.msg));
+                        let _ = err.write_str(#delimiter);
+                    }
+
+                    let _ = write!(err, "{}{}", original.source, ms);
+
+                    err
+                };
+
+                match f(&original) {
+                    Ok(ok) => Ok(ok),
+                    Err(err) => #trait_name::context(Err(#struct_name::from(err)), chain),
+                }
+            }
+
+            #track_caller
+            fn with_row_context(self, row: usize) -> ::core::result::Result<T, #struct_ty> {
+                use core::fmt::Write;
+
+                #trait_name::context(self, |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                    let _ = write!(err, "{}{}Record {}{}", d, mp, row, ms);
+                    err
+                })
+            }
+
+            #track_caller
+            fn with_context<D: core::fmt::Display, F: FnOnce() -> D>(self, f: F) -> ::core::result::Result<T, #struct_ty> {
+                use core::fmt::Write;
+
+                #trait_name::context(self, |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                    let _ = write!(err, "{}{}{}{}", d, mp, f(), ms);
+                    err
+                })
+            }
+
+            #track_caller
+            fn raw_context(self, bytes: &[u8]) -> ::core::result::Result<::core::result::Result<T, #struct_ty>, core::str::Utf8Error> {
+                match self {
+                    Ok(ok) => Ok(Ok(ok)),
+                    Err(err) => {
+                        let msg = core::str::from_utf8(bytes)?;
+
+                        Ok(#trait_name::context(Err::<T, #struct_ty>(err), msg))
+                    }
+                }
+            }
+
+            #track_caller
+            fn lossy_context(self, bytes: &[u8]) -> ::core::result::Result<T, #struct_ty> {
+                use core::fmt::Write;
+
+                #trait_name::context(self, |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                    let _ = err.write_str(d);
+                    let _ = err.write_str(mp);
+
+                    for chunk in bytes.utf8_chunks() {
+                        let _ = err.write_str(chunk.valid());
+
+                        if !chunk.invalid().is_empty() {
+                            let _ = err.write_str("\u{FFFD}");
+                        }
+                    }
+
+                    let _ = err.write_str(ms);
+                    err
+                })
+            }
+
+            fn strip_context(self) -> ::core::result::Result<T, #enum_ty> {
+                self.map_err(#struct_name::into_source)
+            }
+
+            fn map_err_into(self) -> ::core::result::Result<T, #struct_ty> {
+                self
+            }
+
+            #std_ops_impl
+
+            #attach_ops_impl
+
+            #notes_ops_impl
+
+            #help_ops_impl
+
+            #user_ops_impl
+        }
+
+        impl<T, E #gp> #ops_trait_name<T #ga> for ::core::result::Result<T, E> where #enum_ty: From<E>, #where_preds {
+            type Error = E;
+
+            fn on_err<F: FnOnce(&E)>(self, f: F) -> Self {
+                if let Err(err) = &self {
+                    f(err);
+                }
+
+                self
+            }
+
+            fn on_ok<F: FnOnce(&T)>(self, f: F) -> Self {
+                if let Ok(ok) = &self {
+                    f(ok);
+                }
+
+                self
+            }
+
+            #track_caller
+            fn or_else_ctx<E2, F: FnOnce(&#struct_ty) -> ::core::result::Result<T, E2>>(self, f: F) -> ::core::result::Result<T, #struct_ty> where #struct_ty: From<E2> {
+                #ops_trait_name::or_else_ctx(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), f)
+            }
+
+            #track_caller
+            fn with_row_context(self, row: usize) -> ::core::result::Result<T, #struct_ty> {
+                #ops_trait_name::with_row_context(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), row)
+            }
+
+            #track_caller
+            fn with_context<D: core::fmt::Display, F: FnOnce() -> D>(self, f: F) -> ::core::result::Result<T, #struct_ty> {
+                #ops_trait_name::with_context(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), f)
+            }
+
+            #track_caller
+            fn raw_context(self, bytes: &[u8]) -> ::core::result::Result<::core::result::Result<T, #struct_ty>, core::str::Utf8Error> {
+                #ops_trait_name::raw_context(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), bytes)
+            }
+
+            #track_caller
+            fn lossy_context(self, bytes: &[u8]) -> ::core::result::Result<T, #struct_ty> {
+                #ops_trait_name::lossy_context(self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err))), bytes)
+            }
+
+            fn strip_context(self) -> ::core::result::Result<T, #enum_ty> {
+                self.map_err(#enum_name::from)
+            }
+
+            fn map_err_into(self) -> ::core::result::Result<T, #struct_ty> {
+                self.map_err(|err| <#struct_ty as From<#enum_ty>>::from(#enum_name::from(err)))
+            }
+
+            #std_ops_impl_from
+
+            #attach_ops_impl_from
+
+            #notes_ops_impl_from
+
+            #help_ops_impl_from
+
+            #user_ops_impl_from
+        }
+
+        /// Extension trait for parsing strings with context.
+        #vis trait #str_trait_name #impl_generics #where_clause {
+            /// Parses the string into `F`, adding `msg` and the name of `F` as context on failure.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// let retries = "42".parse_ctx::<u8>("Failed to parse retry count")?;
+            /// // On failure: "Failed to parse retry count (as u8)"
+            /// ```
+            fn parse_ctx<F: core::str::FromStr>(&self, msg: &str) -> ::core::result::Result<F, #struct_ty> where #struct_ty: From<F::Err>;
+        }
+
+        impl #impl_generics #str_trait_name #ty_generics for str #where_clause {
+            #track_caller
+            fn parse_ctx<F: core::str::FromStr>(&self, msg: &str) -> ::core::result::Result<F, #struct_ty> where #struct_ty: From<F::Err> {
+                use core::fmt::Write;
+
+                match self.parse::<F>() {
+                    Ok(ok) => Ok(ok),
+                    Err(err) => #trait_name::context(Err(#struct_name::from(err)), |mut err: #struct_ty, d: &str, mp: &str, ms: &str| {
+                        let _ = write!(err, "{}{}{} (as {}){}", d, mp, msg, core::any::type_name::<F>(), ms);
+                        err
+                    }),
+                }
+            }
+        }
+
+        /// Extension trait for turning Options into errors with context.
+        #vis trait #opt_trait_name<T #gp> #where_clause {
+            /// Turns `None` into `err`, adding `msg` as context.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// let port = config.get("port").ok_or_context("Missing port", AppError::NotFound)?;
+            /// ```
+            fn ok_or_context<E>(self, msg: &str, err: E) -> ::core::result::Result<T, #struct_ty> where #enum_ty: From<E>;
+
+            /// Turns `None` into `err`, adding the `Display` output of the value `f`
+            /// returns as context.
+            ///
+            /// `f` is only called on `None`.
+            ///
+            /// # Examples
+            ///
+            /// ```rust,ignore
+            /// let user = users.get(&id).ok_or_with_context(|| format!("Missing user {}", id), AppError::NotFound)?;
+            /// ```
+            fn ok_or_with_context<E, D: core::fmt::Display, F: FnOnce() -> D>(self, f: F, err: E) -> ::core::result::Result<T, #struct_ty> where #enum_ty: From<E>;
+        }
+
+        impl<T #gp> #opt_trait_name<T #ga> for Option<T> #where_clause {
+            #track_caller
+            fn ok_or_context<E>(self, msg: &str, err: E) -> ::core::result::Result<T, #struct_ty> where #enum_ty: From<E> {
+                match self {
+                    Some(some) => Ok(some),
+                    None => #trait_name::context(Err::<T, #enum_ty>(#enum_name::from(err)), msg),
+                }
+            }
+
+            #track_caller
+            fn ok_or_with_context<E, D: core::fmt::Display, F: FnOnce() -> D>(self, f: F, err: E) -> ::core::result::Result<T, #struct_ty> where #enum_ty: From<E> {
+                match self {
+                    Some(some) => Ok(some),
+                    None => #ops_trait_name::with_context(Err::<T, #enum_ty>(#enum_name::from(err)), f),
+                }
+            }
+        }
+
+        #[doc = #alias_doc]
+        #vis type #alias<T #alias_params> = ::core::result::Result<T, #struct_ty>;
+
+        #opaque_items
+
+        #gen_buf
+
+        #alloc_fns
+
+        #stats_fn
+
+        #error_stack_fn
+
+        #serde_fn
+
+        #json_fn
+
+        #color_fn
+
+        #tui_fn
+
+        #history_fn
+
+        #origin_fn
+
+        #id_fn
+        #related_fn
+        #notes_fn
+        #help_fn
+        #attachments_fn
+        #backtrace_fn
+        #user_fn
+    };
+
+    if let Some(error) = errors {
+        TokenStream::from(error.to_compile_error())
+    } else {
+        TokenStream::from(expanded)
+    }
+}
+
+/// Marks a function returning a Result as a test, printing the error's
+/// `Display` output instead of its `Debug` output when it fails.
+///
+/// Keeps the configured format of generated wrappers (delimiters, prefixes,
+/// templates) in test failures.
+///
+/// Named `test_res` rather than `test` so `use resext::*;` doesn't make the
+/// built-in `#[test]` ambiguous.
+///
+/// # Examples
+///
+/// ```rust
+/// # use resext_macro::resext;
+/// #[resext]
+/// enum ConfigError {
+///     Io(std::io::Error),
+/// }
+///
+/// #[resext_macro::test_res]
+/// fn reads_config() -> Res<()> {
+///     std::fs::read("Cargo.toml").context("Failed to read manifest")?;
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test_res(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+
+        return TokenStream::from(
+            Error::new(
+                attr.span(),
+                "`#[resext::test_res]` doesn't take any arguments",
+            )
+            .to_compile_error(),
+        );
+    }
+
+    let ResultFn { attrs, vis, asyncness, name, output, block } =
+        parse_macro_input!(item as ResultFn);
+
+    if let Some(asyncness) = asyncness {
+        return TokenStream::from(
+            Error::new(
+                asyncness.span(),
+                "`#[resext::test_res]` doesn't support async functions",
+            )
+            .to_compile_error(),
+        );
+    }
+
+    // Named like the body of `#[resext::main]`, so it can't hide a helper.
+    let expanded = quote! {
+        #[test]
+        #(#attrs)*
+        #vis fn #name() {
+            fn __resext_body() -> #output #block
+
+            if let Err(err) = __resext_body() {
+                panic!("{}", err);
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Marks a function returning a Result as the program's entry point, printing
+/// the error's `Display` output to stderr and exiting with code 1 on failure.
+///
+/// The report ends with `version: x.y.z`, followed by `(commit ...)` if the
+/// `RESEXT_BUILD_COMMIT` environment variable was set at compile time (e.g.
+/// from a build script), so pasted error output identifies the build.
+/// `build_info = path::to::function` replaces it with the `Display` value the
+/// function returns, `build_info = false` turns it off.
+///
+/// Async entry points are supported in two ways:
+///
+/// - `#[resext::main(async)]` on an `async fn` builds a multi-threaded tokio
+///   runtime itself, so the crate needs a `tokio` dependency with the
+///   `rt-multi-thread` feature.
+/// - On an `async fn` combined with a runtime attribute such as
+///   `#[tokio::main]` or `#[async_std::main]`, the runtime attribute is kept
+///   and wraps the generated function.
+///
+/// Requires the `std` feature.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use resext_macro::resext;
+/// #[resext]
+/// enum AppError {
+///     Io(std::io::Error),
+/// }
+///
+/// #[resext_macro::main]
+/// fn main() -> Res<()> {
+///     std::fs::read("config.toml").context("Failed to read config")?;
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let MainArgs { tokio_runtime, build_info } =
+        parse_macro_input!(attr as MainArgs);
+
+    let ResultFn { attrs, vis, asyncness, name, output, block } =
+        parse_macro_input!(item as ResultFn);
+
+    if !cfg!(feature = "std") {
+        return TokenStream::from(
+            Error::new(
+                name.span(),
+                "`#[resext::main]` requires the `std` feature of `resext`",
+            )
+            .to_compile_error(),
+        );
+    }
+
+    // Wrappers with `color = true` are reported in color, picked by autoref
+    // specialization since the error type isn't known here.
+    let report = match build_info {
+        Some(info) => quote! {
+            ::resext::__private::exit(1, &format_args!("{}\n{}", err, #info));
+        },
+        None => quote! {
+            ::resext::__private::exit(1, &err);
+        },
+    };
+    let report = quote! {
+        #[allow(unused_imports)]
+        use ::resext::__private::{ReportColored as _, ReportPlain as _};
+
+        let err = (&&::resext::__private::ReportProbe(&err)).report_display();
+        #report
+    };
+
+    // The body runs in a function named so it can't hide a `run()` or
+    // similar helper the body calls.
+    let expanded = match (asyncness, tokio_runtime) {
+        (None, true) => {
+            return TokenStream::from(
+                Error::new(
+                    name.span(),
+                    "`#[resext::main(async)]` must be used on an `async fn`",
+                )
+                .to_compile_error(),
+            );
+        }
+
+        (Some(_), true) => quote! {
+            #(#attrs)*
+            #vis fn #name() {
+                async fn __resext_body() -> #output #block
+
+                let result = ::tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .build()
+                    .expect("Failed to build the tokio runtime")
+                    .block_on(__resext_body());
+
+                if let Err(err) = result {
+                    #report
+                }
+            }
+        },
+
+        (Some(_), false) => quote! {
+            #(#attrs)*
+            #vis async fn #name() {
+                async fn __resext_body() -> #output #block
+
+                if let Err(err) = __resext_body().await {
+                    #report
+                }
+            }
+        },
+
+        (None, false) => quote! {
+            #(#attrs)*
+            #vis fn #name() {
+                fn __resext_body() -> #output #block
+
+                if let Err(err) = __resext_body() {
+                    #report
+                }
+            }
+        },
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Arguments of `#[resext::main]`.
+struct MainArgs {
+    tokio_runtime: bool,
+    /// Expression displayed after the error, `None` with `build_info = false`.
+    build_info: Option<proc_macro2::TokenStream>,
+}
+
+impl Parse for MainArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = Self {
+            tokio_runtime: false,
+            build_info: Some(quote! {
+                ::resext::BuildInfo::new(
+                    env!("CARGO_PKG_VERSION"),
+                    option_env!("RESEXT_BUILD_COMMIT"),
+                )
+            }),
+        };
+
+        while !input.is_empty() {
+            if input.peek(syn::Token![async]) {
+                input.parse::<syn::Token![async]>()?;
+                args.tokio_runtime = true;
+            } else {
+                let key: Ident = input.parse()?;
+
+                if key != "build_info" {
+                    return Err(Error::new(
+                        key.span(),
+                        format!(
+                            "unknown argument passed to `#[resext::main]`: {}",
+                            key
+                        ),
+                    ));
+                }
+
+                input.parse::<syn::Token![=]>()?;
+
+                args.build_info = if input.peek(LitBool) {
+                    let value: LitBool = input.parse()?;
+
+                    if value.value() {
+                        return Err(Error::new(
+                            value.span(),
+                            "`build_info` must be `false` or a path to a function",
+                        ));
+                    }
+
+                    None
+                } else {
+                    let path: syn::Path = input.parse()?;
+                    Some(quote! { #path() })
+                };
+            }
+
+            if input.peek(syn::Token![,]) {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Result-returning function without parameters, as taken by
+/// `#[resext::test_res]` and `#[resext::main]`.
+///
+/// Parsed by hand since syn's `full` feature isn't needed anywhere else.
+struct ResultFn {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    asyncness: Option<syn::Token![async]>,
+    name: Ident,
+    output: syn::Type,
+    block: proc_macro2::TokenStream,
+}
+
+impl Parse for ResultFn {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let asyncness = input.parse()?;
+        input.parse::<syn::Token![fn]>()?;
+        let name = input.parse()?;
+
+        let params;
+        syn::parenthesized!(params in input);
+
+        if !params.is_empty() {
+            return Err(params.error("test functions can't take arguments"));
+        }
+
+        if !input.peek(syn::Token![->]) {
+            return Err(input.error(
+                "`#[resext::test_res]` functions must return a Result",
+            ));
+        }
 
-        #gen_buf
-    };
+        input.parse::<syn::Token![->]>()?;
+        let output = input.parse()?;
+        let block = input.parse()?;
 
-    if let Some(error) = errors {
-        TokenStream::from(error.to_compile_error())
-    } else {
-        TokenStream::from(expanded)
+        Ok(Self { attrs, vis, asyncness, name, output, block })
     }
 }
 
@@ -639,8 +3409,48 @@ struct ResExtArgs {
     source_prefix: Option<String>,
     include_variant: bool,
     alias: Option<proc_macro2::TokenStream>,
+    wrapper: Option<Ident>,
     buf_size: Option<usize>,
     alloc: bool,
+    formatter: Option<syn::Path>,
+    format: Option<Vec<TemplatePart>>,
+    headline_last: bool,
+    non_exhaustive: bool,
+    opaque: bool,
+    minimal: bool,
+    storage: Option<syn::Type>,
+    spill: Option<syn::Type>,
+    origin: Option<Origin>,
+    id: Option<ErrorId>,
+    related: bool,
+    notes: bool,
+    show_docs_url: bool,
+    help: bool,
+    attachments: bool,
+    user_messages: bool,
+    locations: bool,
+    color: bool,
+    backtrace: bool,
+    futures: bool,
+    serde: bool,
+    tui: bool,
+    classify: bool,
+    interconvert: Vec<syn::Path>,
+}
+
+/// Source of per-occurrence error IDs for the `id` option.
+enum ErrorId {
+    /// `id = true`, the built-in process-wide counter.
+    Sequential,
+    /// `id = path::to::function` returning a `u32`.
+    Generator(syn::Path),
+}
+
+/// Component name rendered before the error with the `origin` option.
+enum Origin {
+    /// `origin = true`, the name of the crate defining the enum.
+    Package,
+    Name(String),
 }
 
 impl Parse for ResExtArgs {
@@ -654,10 +3464,42 @@ impl Parse for ResExtArgs {
             source_prefix: None,
             include_variant: false,
             alias: None,
+            wrapper: None,
             buf_size: None,
             alloc: false,
+            formatter: None,
+            format: None,
+            headline_last: false,
+            non_exhaustive: false,
+            opaque: false,
+            minimal: false,
+            storage: None,
+            spill: None,
+            origin: None,
+            id: None,
+            related: false,
+            notes: false,
+            show_docs_url: false,
+            help: false,
+            attachments: false,
+            user_messages: false,
+            locations: false,
+            color: false,
+            backtrace: false,
+            futures: false,
+            serde: false,
+            tui: false,
+            classify: false,
+            interconvert: Vec::new(),
         };
 
+        let mut format_span = None;
+        let mut minimal_span = None;
+        let mut storage_span = None;
+        let mut spill_span = None;
+        let mut color_span = None;
+        let mut wrapper_span = None;
+
         while !input.is_empty() {
             let key: Ident = input.parse()?;
             input.parse::<syn::Token![=]>()?;
@@ -698,6 +3540,12 @@ impl Parse for ResExtArgs {
                     args.alias = Some(value.into_token_stream());
                 }
 
+                "wrapper" => {
+                    let value: Ident = input.parse()?;
+                    wrapper_span = Some(value.span());
+                    args.wrapper = Some(value);
+                }
+
                 "buf_size" => {
                     let value: syn::LitInt = input.parse()?;
                     args.buf_size = Some(value.base10_parse()?);
@@ -708,11 +3556,237 @@ impl Parse for ResExtArgs {
                     args.alloc = value.value();
                 }
 
+                "formatter" => {
+                    let value: syn::Path = input.parse()?;
+                    args.formatter = Some(value);
+                }
+
+                "storage" => {
+                    let value: syn::Type = input.parse()?;
+                    storage_span = Some(value.span());
+                    args.storage = Some(value);
+                }
+
+                "spill" => {
+                    let value: syn::Type = input.parse()?;
+                    spill_span = Some(value.span());
+                    args.spill = Some(value);
+                }
+
+                "format" => {
+                    let value: LitStr = input.parse()?;
+                    format_span = Some(value.span());
+                    args.format = Some(parse_template(&value)?);
+                }
+
+                "headline" => {
+                    let value: LitStr = input.parse()?;
+
+                    args.headline_last = match value.value().as_str() {
+                        "first_context" => false,
+                        "last_context" => true,
+                        _ => {
+                            return Err(Error::new(
+                                value.span(),
+                                "`headline` must be \"first_context\" or \"last_context\"",
+                            ));
+                        }
+                    };
+                }
+
+                "non_exhaustive" => {
+                    let value: LitBool = input.parse()?;
+                    args.non_exhaustive = value.value();
+                }
+
+                "opaque" => {
+                    let value: LitBool = input.parse()?;
+                    args.opaque = value.value();
+                }
+
                 "include_variant" => {
                     let value: LitBool = input.parse()?;
                     args.include_variant = value.value();
                 }
 
+                "origin" => match input.parse::<syn::Lit>()? {
+                    syn::Lit::Str(value) => {
+                        args.origin = Some(Origin::Name(value.value()));
+                    }
+
+                    syn::Lit::Bool(value) => {
+                        args.origin = value.value.then_some(Origin::Package);
+                    }
+
+                    lit => {
+                        return Err(Error::new(
+                            lit.span(),
+                            "`origin` must be a string or `true`",
+                        ));
+                    }
+                },
+
+                "id" => {
+                    args.id = if input.peek(LitBool) {
+                        let value: LitBool = input.parse()?;
+                        value.value().then_some(ErrorId::Sequential)
+                    } else {
+                        Some(ErrorId::Generator(input.parse()?))
+                    };
+                }
+
+                "related" => {
+                    let value: LitBool = input.parse()?;
+                    args.related = value.value();
+
+                    if args.related && !cfg!(feature = "alloc") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`related` requires the `alloc` feature of resext",
+                        ));
+                    }
+                }
+
+                "notes" => {
+                    let value: LitBool = input.parse()?;
+                    args.notes = value.value();
+
+                    if args.notes && !cfg!(feature = "alloc") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`notes` requires the `alloc` feature of resext",
+                        ));
+                    }
+                }
+
+                "show_docs_url" => {
+                    let value: LitBool = input.parse()?;
+                    args.show_docs_url = value.value();
+                }
+
+                "help" => {
+                    let value: LitBool = input.parse()?;
+                    args.help = value.value();
+
+                    if args.help && !cfg!(feature = "alloc") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`help` requires the `alloc` feature of resext",
+                        ));
+                    }
+                }
+
+                "attachments" => {
+                    let value: LitBool = input.parse()?;
+                    args.attachments = value.value();
+
+                    if args.attachments && !cfg!(feature = "alloc") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`attachments` requires the `alloc` feature of resext",
+                        ));
+                    }
+                }
+
+                "user_messages" => {
+                    let value: LitBool = input.parse()?;
+                    args.user_messages = value.value();
+                }
+
+                "locations" => {
+                    let value: LitBool = input.parse()?;
+                    args.locations = value.value();
+                }
+
+                "color" => {
+                    let value: LitBool = input.parse()?;
+                    args.color = value.value();
+                    color_span = Some(value.span());
+
+                    if args.color && !cfg!(feature = "std") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`color` requires the `std` feature of resext",
+                        ));
+                    }
+                }
+
+                "backtrace" => {
+                    let value: LitBool = input.parse()?;
+                    args.backtrace = value.value();
+
+                    if args.backtrace && !cfg!(feature = "backtrace") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`backtrace` requires the `backtrace` feature of resext",
+                        ));
+                    }
+                }
+
+                "futures" => {
+                    let value: LitBool = input.parse()?;
+                    args.futures = value.value();
+
+                    if args.futures && !cfg!(feature = "async") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`futures` requires the `async` feature of resext",
+                        ));
+                    }
+                }
+
+                "serde" => {
+                    let value: LitBool = input.parse()?;
+                    args.serde = value.value();
+
+                    if args.serde && !cfg!(feature = "serde") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`serde` requires the `serde` feature of resext",
+                        ));
+                    }
+                }
+
+                "tui" => {
+                    let value: LitBool = input.parse()?;
+                    args.tui = value.value();
+
+                    if args.tui && !cfg!(feature = "tui") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`tui` requires the `tui` feature of resext",
+                        ));
+                    }
+                }
+
+                "classify" => {
+                    let value: LitBool = input.parse()?;
+                    args.classify = value.value();
+
+                    if args.classify && !cfg!(feature = "anyhow") {
+                        return Err(Error::new(
+                            value.span(),
+                            "`classify` requires the `anyhow` feature of resext",
+                        ));
+                    }
+                }
+
+                "interconvert" => {
+                    let targets;
+                    syn::bracketed!(targets in input);
+
+                    args.interconvert = targets
+                        .parse_terminated(syn::Path::parse, syn::Token![,])?
+                        .into_iter()
+                        .collect();
+                }
+
+                "minimal" => {
+                    let value: LitBool = input.parse()?;
+                    args.minimal = value.value();
+                    minimal_span = Some(value.span());
+                }
+
                 _ => {
                     return Err(Error::new(
                         key.span(),
@@ -729,6 +3803,544 @@ impl Parse for ResExtArgs {
             }
         }
 
+        if let Some(span) = format_span {
+            if args.formatter.is_some() {
+                return Err(Error::new(
+                    span,
+                    "`format` and `formatter` cannot be used together",
+                ));
+            }
+
+            let has_template_delimiter =
+                args.format.iter().flatten().any(|part| {
+                    matches!(part, TemplatePart::Contexts(Some(_)))
+                });
+
+            if has_template_delimiter && args.delimiter.is_some() {
+                return Err(Error::new(
+                    span,
+                    "`{contexts:...}` in `format` conflicts with `delimiter`",
+                ));
+            }
+        }
+
+        if let Some(span) = storage_span
+            && (args.buf_size.is_some() || args.alloc)
+        {
+            return Err(Error::new(
+                span,
+                "`storage` cannot be used together with `buf_size` or `alloc`",
+            ));
+        }
+
+        if let Some(span) = color_span.filter(|_| args.color)
+            && (args.format.is_some() || args.formatter.is_some())
+        {
+            return Err(Error::new(
+                span,
+                "`color` colors the default layout, it can't be used together with `format` or `formatter`",
+            ));
+        }
+
+        if let Some(span) = spill_span
+            && !args.alloc
+        {
+            return Err(Error::new(span, "`spill` requires `alloc = true`"));
+        }
+
+        if let Some(span) = minimal_span.filter(|_| args.minimal) {
+            let wrapper_option = [
+                ("wrapper", wrapper_span.is_some()),
+                ("format", args.format.is_some()),
+                ("formatter", args.formatter.is_some()),
+                ("alloc", args.alloc),
+                ("storage", args.storage.is_some()),
+                ("origin", args.origin.is_some()),
+                ("id", args.id.is_some()),
+                ("related", args.related),
+                ("notes", args.notes),
+                ("show_docs_url", args.show_docs_url),
+                ("help", args.help),
+                ("attachments", args.attachments),
+                ("user_messages", args.user_messages),
+                ("locations", args.locations),
+                ("color", args.color),
+                ("backtrace", args.backtrace),
+                ("futures", args.futures),
+                ("serde", args.serde),
+                ("tui", args.tui),
+                ("headline", args.headline_last),
+                ("opaque", args.opaque),
+            ]
+            .into_iter()
+            .find(|(_, set)| *set);
+
+            if let Some((name, _)) = wrapper_option {
+                return Err(Error::new(
+                    span,
+                    format!(
+                        "`{}` configures the wrapper, which `minimal` doesn't generate",
+                        name
+                    ),
+                ));
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Generic parameters of the enum, threaded through every generated item.
+///
+/// Parameters named like the ones the generated code declares itself (`T`,
+/// `E`, `F`, ...) are renamed in the generated items. Parameters are
+/// positional, so this is invisible to users.
+struct EnumGenerics {
+    /// `<P: Bound, ...>` for items without parameters of their own.
+    impl_generics: proc_macro2::TokenStream,
+    /// `<P: Bound = Default, ...>` for the wrapper's declaration, keeping the
+    /// enum's defaults.
+    decl_generics: proc_macro2::TokenStream,
+    /// `, P: Bound, ...` appended to the parameters of generated items.
+    params: proc_macro2::TokenStream,
+    /// `, P, ...` appended to the arguments of the generated traits.
+    args: proc_macro2::TokenStream,
+    /// `<P, ...>` after the enum and wrapper names.
+    ty_generics: proc_macro2::TokenStream,
+    /// `, P = Default, const N: usize, ...` for the type alias, which ignores
+    /// bounds but keeps defaults.
+    alias_params: proc_macro2::TokenStream,
+    /// `where ...` for items without a where clause of their own.
+    where_clause: proc_macro2::TokenStream,
+    /// `Pred, ...,` appended to existing where clauses.
+    where_preds: proc_macro2::TokenStream,
+    /// Original names of the type and const parameters.
+    names: Vec<Ident>,
+}
+
+impl EnumGenerics {
+    /// Names of the parameters declared by the generated items, including
+    /// the ones behind attribute options. Every `impl<..>`, `trait ..<..>`
+    /// and `fn ..<..>` the macro emits must only declare names listed here.
+    const RESERVED: &[&str] = &[
+        // Context traits, their impls and the wrapper's constructors.
+        "T", "E", "S", "F", "LEN", "M", "Fut",
+        // `{alias}Ops` and the wrapper's methods.
+        "E2", "D", "P", "A", "C", "N", "H",
+    ];
+
+    fn new(generics: &syn::Generics) -> syn::Result<Self> {
+        let mut names = Vec::new();
+        let mut renames = Vec::new();
+
+        for param in &generics.params {
+            let ident = match param {
+                syn::GenericParam::Type(param) => &param.ident,
+                syn::GenericParam::Const(param) => &param.ident,
+                syn::GenericParam::Lifetime(param) => {
+                    return Err(Error::new(
+                        param.span(),
+                        "`#[resext]` enums can't have lifetime parameters, sources of `dyn Error` must be `'static`",
+                    ));
+                }
+            };
+
+            names.push(ident.clone());
+
+            if Self::RESERVED.contains(&ident.to_string().as_str()) {
+                renames
+                    .push((ident.clone(), quote::format_ident!("__{}", ident)));
+            }
+        }
+
+        let mut params = Vec::new();
+        let mut decl_params = Vec::new();
+        let mut args = Vec::new();
+        let mut alias_params = Vec::new();
+
+        for param in &generics.params {
+            match param {
+                syn::GenericParam::Type(param) => {
+                    let ident =
+                        substitute(param.ident.to_token_stream(), &renames);
+                    let bounds = &param.bounds;
+                    let bounds = substitute(quote! { #bounds }, &renames);
+                    let default = param.default.as_ref().map(|default| {
+                        let default =
+                            substitute(default.to_token_stream(), &renames);
+                        quote! { = #default }
+                    });
+
+                    // Sources are handed out as `dyn Error + 'static`, so
+                    // every type parameter is implicitly `'static`.
+                    let has_static = param.bounds.iter().any(|bound| {
+                        matches!(bound, syn::TypeParamBound::Lifetime(lifetime) if lifetime.ident == "static")
+                    });
+
+                    let param = if param.bounds.is_empty() {
+                        quote! { #ident: 'static }
+                    } else if has_static {
+                        quote! { #ident: #bounds }
+                    } else {
+                        quote! { #ident: #bounds + 'static }
+                    };
+
+                    decl_params.push(quote! { #param #default });
+                    params.push(param);
+                    args.push(ident.clone());
+                    alias_params.push(quote! { #ident #default });
+                }
+
+                syn::GenericParam::Const(param) => {
+                    let ident =
+                        substitute(param.ident.to_token_stream(), &renames);
+                    let ty = substitute(param.ty.to_token_stream(), &renames);
+
+                    let default = param.default.as_ref().map(|default| {
+                        let default =
+                            substitute(default.to_token_stream(), &renames);
+                        quote! { = #default }
+                    });
+
+                    params.push(quote! { const #ident: #ty });
+                    decl_params.push(quote! { const #ident: #ty #default });
+                    args.push(ident.clone());
+                    alias_params.push(quote! { const #ident: #ty #default });
+                }
+
+                syn::GenericParam::Lifetime(_) => unreachable!(),
+            }
+        }
+
+        let predicates = generics
+            .where_clause
+            .iter()
+            .flat_map(|clause| &clause.predicates)
+            .map(|predicate| substitute(predicate.to_token_stream(), &renames));
+        let where_preds = quote! { #(#predicates,)* };
+
+        if params.is_empty() {
+            return Ok(Self {
+                impl_generics: quote! {},
+                decl_generics: quote! {},
+                params: quote! {},
+                args: quote! {},
+                ty_generics: quote! {},
+                alias_params: quote! {},
+                where_clause: quote! {},
+                where_preds,
+                names,
+            });
+        }
+
+        Ok(Self {
+            impl_generics: quote! { <#(#params),*> },
+            decl_generics: quote! { <#(#decl_params),*> },
+            params: quote! { , #(#params),* },
+            args: quote! { , #(#args),* },
+            ty_generics: quote! { <#(#args),*> },
+            alias_params: quote! { , #(#alias_params),* },
+            where_clause: quote! { where #where_preds },
+            where_preds,
+            names,
+        })
+    }
+
+    /// Returns `true` if `ty` mentions one of the enum's parameters.
+    fn is_generic(&self, ty: &syn::Type) -> bool {
+        fn mentions(tokens: proc_macro2::TokenStream, names: &[Ident]) -> bool {
+            tokens.into_iter().any(|tt| match tt {
+                proc_macro2::TokenTree::Ident(ident) => names.contains(&ident),
+                proc_macro2::TokenTree::Group(group) => {
+                    mentions(group.stream(), names)
+                }
+                _ => false,
+            })
+        }
+
+        mentions(ty.to_token_stream(), &self.names)
+    }
+}
+
+/// Replaces the idents in `tokens` according to `renames`.
+fn substitute(
+    tokens: proc_macro2::TokenStream,
+    renames: &[(Ident, Ident)],
+) -> proc_macro2::TokenStream {
+    use proc_macro2::{Group, TokenTree};
+
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Ident(ident) => {
+                match renames.iter().find(|(from, _)| *from == ident) {
+                    Some((_, to)) => TokenTree::Ident(to.clone()),
+                    None => TokenTree::Ident(ident),
+                }
+            }
+
+            TokenTree::Group(group) => {
+                let stream = substitute(group.stream(), renames);
+                let mut new = Group::new(group.delimiter(), stream);
+                new.set_span(group.span());
+                TokenTree::Group(new)
+            }
+
+            tt => tt,
+        })
+        .collect()
+}
+
+/// Arguments of `#[resext(...)]` on a variant.
+struct VariantArgs {
+    display: Option<LitStr>,
+    exit_code: Option<proc_macro2::TokenStream>,
+    /// Don't generate `From` impls for the variant's field type.
+    skip_from: bool,
+    /// Show the variant in `user_message()`.
+    user_facing: bool,
+    /// Display the variant's name and payload size instead of the payload.
+    skip_display: bool,
+    docs_url: Option<LitStr>,
+    /// Max chars of the payload's `Debug` output shown in the wrapper's `Debug`.
+    debug_summary: Option<usize>,
+}
+
+impl Parse for VariantArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = VariantArgs {
+            display: None,
+            exit_code: None,
+            skip_from: false,
+            user_facing: false,
+            skip_display: false,
+            docs_url: None,
+            debug_summary: None,
+        };
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+
+            if key == "skip_from"
+                || key == "user_facing"
+                || key == "skip_display"
+            {
+                if key == "skip_from" {
+                    args.skip_from = true;
+                } else if key == "user_facing" {
+                    args.user_facing = true;
+                } else {
+                    args.skip_display = true;
+                }
+
+                if input.peek(syn::Token![,]) {
+                    input.parse::<syn::Token![,]>()?;
+                }
+
+                continue;
+            }
+
+            input.parse::<syn::Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "display" => args.display = Some(input.parse()?),
+                "docs_url" => args.docs_url = Some(input.parse()?),
+
+                "debug_summary" => {
+                    let limit: syn::LitInt = input.parse()?;
+
+                    match limit.base10_parse::<usize>() {
+                        Ok(limit @ 1..) => args.debug_summary = Some(limit),
+                        _ => {
+                            return Err(Error::new(
+                                limit.span(),
+                                "`debug_summary` must be a positive number of chars",
+                            ));
+                        }
+                    }
+                }
+
+                // A string names a `resext::exitcode` constant, anything else
+                // is used as the code itself.
+                "exit_code" => {
+                    args.exit_code = Some(if input.peek(LitStr) {
+                        let name: LitStr = input.parse()?;
+                        let name: Ident = name.parse().map_err(|_| {
+                            Error::new(
+                                name.span(),
+                                "`exit_code` must name a constant of `resext::exitcode`, e.g. \"EX_IOERR\"",
+                            )
+                        })?;
+
+                        quote! { ::resext::exitcode::#name }
+                    } else {
+                        let code = input.parse::<syn::Expr>()?;
+
+                        // Unix parents only see the low 8 bits, so codes
+                        // like 0 or 256 would report the failure as
+                        // success. Wider codes are left to the target.
+                        let (negated, lit) = match &code {
+                            syn::Expr::Lit(syn::ExprLit {
+                                lit: syn::Lit::Int(lit),
+                                ..
+                            }) => (false, Some(lit)),
+
+                            syn::Expr::Unary(syn::ExprUnary {
+                                op: syn::UnOp::Neg(_),
+                                expr,
+                                ..
+                            }) => match &**expr {
+                                syn::Expr::Lit(syn::ExprLit {
+                                    lit: syn::Lit::Int(lit),
+                                    ..
+                                }) => (true, Some(lit)),
+                                _ => (false, None),
+                            },
+
+                            _ => (false, None),
+                        };
+
+                        if let Some(lit) = lit {
+                            let value =
+                                lit.base10_parse::<i64>().map(|value| {
+                                    if negated { -value } else { value }
+                                });
+
+                            if !matches!(value, Ok(value) if value & 0xff != 0)
+                            {
+                                return Err(Error::new(
+                                    code.span(),
+                                    "`exit_code` must not be a multiple of 256, which reports success on Unix",
+                                ));
+                            }
+                        }
+
+                        code.into_token_stream()
+                    });
+                }
+
+                _ => {
+                    return Err(Error::new(
+                        key.span(),
+                        format!(
+                            "unknown argument passed to variant attribute `#[resext]`: {}",
+                            key
+                        ),
+                    ));
+                }
+            }
+
+            if input.peek(syn::Token![,]) {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
         Ok(args)
     }
 }
+
+/// Rewrites positional placeholders of a variant's `display` string, `{0}`
+/// becomes `{_0}`, so they resolve to the binding of the tuple field.
+fn variant_display_format(lit: &LitStr) -> LitStr {
+    let display = lit.value();
+    let mut format = String::with_capacity(display.len());
+    let mut chars = display.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        format.push(c);
+
+        if c == '{' {
+            match chars.peek() {
+                Some('{') => format.extend(chars.next()),
+                Some(c) if c.is_ascii_digit() => format.push('_'),
+                _ => {}
+            }
+        }
+    }
+
+    LitStr::new(&format, lit.span())
+}
+
+enum TemplatePart {
+    Literal(String),
+    Prefix,
+    Suffix,
+    Contexts(Option<String>),
+    Newline,
+    Source,
+    Variant,
+}
+
+fn parse_template(lit: &LitStr) -> syn::Result<Vec<TemplatePart>> {
+    let template = lit.value();
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                literal.push('{');
+            }
+
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                literal.push('}');
+            }
+
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest.find('}').ok_or_else(|| {
+                    Error::new(lit.span(), "unclosed `{` in `format` template")
+                })?;
+                let placeholder = &rest[..end];
+                chars = rest[end + 1..].chars();
+
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(core::mem::take(
+                        &mut literal,
+                    )));
+                }
+
+                parts.push(match placeholder.split_once(':') {
+                    Some(("contexts", delimiter)) => {
+                        TemplatePart::Contexts(Some(String::from(delimiter)))
+                    }
+                    None if placeholder == "contexts" => {
+                        TemplatePart::Contexts(None)
+                    }
+                    None if placeholder == "prefix" => TemplatePart::Prefix,
+                    None if placeholder == "suffix" => TemplatePart::Suffix,
+                    None if placeholder == "newline" => TemplatePart::Newline,
+                    None if placeholder == "source" => TemplatePart::Source,
+                    None if placeholder == "variant" => TemplatePart::Variant,
+                    _ => {
+                        return Err(Error::new(
+                            lit.span(),
+                            format!(
+                                "unknown placeholder in `format` template: {{{}}}",
+                                placeholder
+                            ),
+                        ));
+                    }
+                });
+            }
+
+            '}' => {
+                return Err(Error::new(
+                    lit.span(),
+                    "unmatched `}` in `format` template, use `}}` to escape it",
+                ));
+            }
+
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+
+    Ok(parts)
+}
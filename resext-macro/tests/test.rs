@@ -3,58 +3,18 @@
 extern crate alloc;
 use alloc::string::ToString;
 
+use resext::ctx;
 use resext_macro::resext;
 
-// Temporary macro as usage in actual projects requires resext crate
-struct Writer<W: core::fmt::Write + ?Sized>(W);
-
-impl<W: core::fmt::Write + ?Sized> core::fmt::Write for Writer<W> {
-    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        self.0.write_str(s)
-    }
-}
-
-macro_rules! ctx {
-    ($fmt:expr, $($args:tt)*) => {
-        {
-            |w, d, mp, ms| {
-                use core::fmt::Write;
-
-                let mut w = Writer(w);
-
-                let _ = w.write_str(d);
-                let _ = w.write_str(mp);
-                let _ = write!(w, $fmt, $($args)*);
-                let _ = w.write_str(ms);
-
-                w.0
-            }
-        }
-    };
-
-    ($fmt:expr) => {
-        {
-            |w, d, mp, ms| {
-                use core::fmt::Write;
-
-                let mut w = Writer(w);
-
-                let _ = w.write_str(d);
-                let _ = w.write_str(mp);
-                let _ = write!(w, $fmt);
-                let _ = w.write_str(ms);
-
-                w.0
-            }
-        }
-    };
-}
-
 #[resext(
     alias = Resext
     delimiter = " ● "
     buf_size = 24
     alloc = true
+    backtrace = true
+    futures = true
+    serde = true
+    tui = true
 )]
 enum ErrTypes {
     HttpResponse(usize),
@@ -113,6 +73,73 @@ fn test_long_context() -> Resext<()> {
     Ok(())
 }
 
+#[test]
+fn test_long_context_spills() {
+    let err = Err::<(), usize>(500)
+        .context("Failed to fetch page")
+        .context("Failed to render dashboard widgets")
+        .context("Failed to serve request")
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Failed to fetch page ● Failed to render dashboard widgets ● Failed to serve request\nError: 500"
+    );
+}
+
+#[test]
+fn test_contexts() {
+    let err = Err::<(), usize>(500)
+        .context("Failed to fetch page")
+        .context("Failed to render dashboard")
+        .unwrap_err();
+
+    assert!(
+        err.contexts()
+            .eq(["Failed to fetch page", "Failed to render dashboard"])
+    );
+    assert_eq!(err.contexts().next_back(), Some("Failed to render dashboard"));
+    assert_eq!(ResextErr::new("", 404).contexts().count(), 0);
+}
+
+#[test]
+fn test_string_context() {
+    let page = alloc::format!("Failed to fetch page {}", 7);
+
+    let err = Err::<(), usize>(404)
+        .context(&page)
+        .context(alloc::format!("Failed to load {}", "dashboard"))
+        .unwrap_err();
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "Failed to fetch page 7 ● Failed to load dashboard\nError: 404"
+    );
+}
+
+#[test]
+fn test_with_context() {
+    let mut calls = 0;
+
+    let res = Ok::<(), usize>(()).with_context(|| {
+        calls += 1;
+        "unused"
+    });
+
+    assert!(res.is_ok());
+    assert_eq!(calls, 0);
+
+    let err = Err::<(), usize>(507)
+        .context("Failed to store upload")
+        .with_context(|| resext::ctx_bytes(1536))
+        .unwrap_err();
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "Failed to store upload ● 1.5 KiB\nError: 507"
+    );
+}
+
 #[test]
 fn test_error_display_format() {
     let result: Resext<_> = core::str::from_utf8(&[0, 158, 22])
@@ -139,6 +166,37 @@ fn test_error_debug_format() {
     assert!(debug_output.contains("Error:"));
 }
 
+#[test]
+fn test_backtrace() {
+    extern crate std;
+    use std::backtrace::{Backtrace, BacktraceStatus};
+
+    let err = ResextErr::new("Failed to fetch page", 404);
+    let captured = Backtrace::capture().status() == BacktraceStatus::Captured;
+
+    assert_eq!(err.backtrace().is_some(), captured);
+    assert_eq!(
+        format_args!("{:?}", err).to_string().contains("stack backtrace:"),
+        captured
+    );
+    assert!(!format_args!("{}", err).to_string().contains("stack backtrace:"));
+
+    // Enums without `backtrace = true` don't capture one.
+    #[resext(alias = Plain, buf_size = 24, alloc = true)]
+    enum PlainErrors {
+        Http(usize),
+    }
+
+    assert!(
+        !format_args!("{:?}", PlainErr::new("Failed to fetch page", 404))
+            .to_string()
+            .contains("stack backtrace:")
+    );
+    assert!(
+        core::mem::size_of::<PlainErr>() < core::mem::size_of::<ResextErr>()
+    );
+}
+
 #[test]
 fn test_new_method() {
     let res = ResextErr::new("", 404);
@@ -151,22 +209,2618 @@ fn test_new_method() {
     );
 }
 
-mod isolated_test {
-    use alloc::string::ToString;
-    use resext_macro::resext;
+#[test]
+fn test_variant_ordering() {
+    let mut errs = alloc::vec![
+        ResextErr::from(ErrTypes::Utf8 {
+            error: core::str::from_utf8(&[0, 158, 22]).unwrap_err(),
+        }),
+        ResextErr::new("Second", 500),
+        ResextErr::new("First", 404),
+    ];
 
-    #[test]
-    fn test_msg_truncation() {
-        #[resext(buf_size = 5)]
-        enum TestErr {
-            Utf8(core::str::Utf8Error),
+    errs.sort_by_key(|err| err.source.variant_index());
+
+    assert_eq!(errs[0].source.variant_name(), "HttpResponse");
+    assert_eq!(errs[1].source.variant_name(), "HttpResponse");
+    assert_eq!(errs[2].source.variant_name(), "Utf8");
+    assert_eq!(errs[2].source.variant_index(), 1);
+}
+
+#[test]
+fn test_sorted_by_variant() {
+    use alloc::vec::Vec;
+    use resext::ErrorVariant;
+
+    let errors: resext::Errors<ResextErr> = [
+        ResextErr::from(ErrTypes::Utf8 {
+            error: core::str::from_utf8(&[0, 158, 22]).unwrap_err(),
+        }),
+        ResextErr::new("Second", 500),
+        ResextErr::new("First", 404),
+    ]
+    .into_iter()
+    .collect();
+
+    let by_variant: Vec<_> = errors
+        .sorted_by_variant()
+        .into_iter()
+        .map(|err| (err.variant_name(), err.to_string()))
+        .collect();
+
+    assert_eq!(by_variant[0], ("HttpResponse", "Second\nError: 500".into()));
+    assert_eq!(by_variant[1], ("HttpResponse", "First\nError: 404".into()));
+    assert_eq!(by_variant[2].0, "Utf8");
+
+    let codes: Vec<_> =
+        errors.sorted_by_code().into_iter().map(ErrorVariant::code).collect();
+
+    assert_eq!(codes, [0, 0, 1]);
+}
+
+#[test]
+fn test_into_code_msg() {
+    let res: Result<(), (i32, alloc::string::String)> = Err::<(), usize>(503)
+        .context("Failed to reach upstream")
+        .map_err(ResextErr::into_code_msg);
+
+    let (code, msg) = res.unwrap_err();
+
+    assert_eq!(code, 0);
+    assert_eq!(msg, "Failed to reach upstream\nError: 503");
+}
+
+#[test]
+fn test_into_source_and_parts() {
+    let err = Err::<(), usize>(503)
+        .context("Failed to fetch page")
+        .context("Failed to load")
+        .unwrap_err();
+
+    let (msg, source) = err.into_parts();
+
+    assert_eq!(msg, "Failed to fetch page ● Failed to load");
+    assert!(matches!(source, ErrTypes::HttpResponse(503)));
+
+    let err = Err::<(), usize>(429).context("Rate limited").unwrap_err();
+
+    assert!(matches!(err.into_source(), ErrTypes::HttpResponse(429)));
+}
+
+#[test]
+fn test_source_chain() {
+    use core::error::Error;
+
+    let err =
+        Err::<(), usize>(404).context("Failed to fetch page").unwrap_err();
+
+    // `usize` isn't an error, so there is nothing past the wrapper.
+    assert!(err.source().is_none());
+    assert!(Error::source(&err.source).is_none());
+}
+
+#[test]
+fn test_source_chain_prints_each_message_once() {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::error::Error;
+    use core::fmt;
+
+    #[derive(Debug)]
+    struct Inner;
+
+    impl fmt::Display for Inner {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("connection reset")
         }
+    }
 
-        let res = core::str::from_utf8(&[0, 158, 22]).context("Good💖");
+    impl Error for Inner {}
 
-        assert_eq!(
-            format_args!("{}", res.unwrap_err()).to_string(),
-            "Good...\nError: invalid utf-8 sequence of 1 bytes from index 1"
-        );
+    #[derive(Debug)]
+    struct Outer(Inner);
+
+    impl fmt::Display for Outer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("request failed")
+        }
+    }
+
+    impl Error for Outer {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[resext(alias = Chain)]
+    enum ChainErrors {
+        Outer(Outer),
+        #[resext(skip_from, display = "upstream unavailable")]
+        Upstream(Outer),
+    }
+
+    fn walk(err: &dyn Error) -> Vec<String> {
+        core::iter::successors(Some(err), |&err| err.source())
+            .map(|err| err.to_string())
+            .collect()
+    }
+
+    let err: ChainErr =
+        Err::<(), _>(Outer(Inner)).context("Failed to sync").unwrap_err();
+    let chain = walk(&err);
+
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain.join("\n").matches("request failed").count(), 1);
+    assert_eq!(chain.join("\n").matches("connection reset").count(), 1);
+    assert!(err.source().unwrap().is::<Inner>());
+
+    // Custom displays hide the wrapped error, so the chain starts at it.
+    let err: ChainErr = Err::<(), _>(ChainErrors::Upstream(Outer(Inner)))
+        .context("Failed to sync")
+        .unwrap_err();
+    let chain = walk(&err);
+
+    assert_eq!(chain.len(), 3);
+    assert_eq!(chain[1], "request failed");
+    assert_eq!(chain[2], "connection reset");
+}
+
+#[test]
+fn test_source_chain_reaches_wrapped_error() {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::error::Error;
+
+    let bytes = [0, 158, 22];
+    let err = core::str::from_utf8(&bytes)
+        .context("Failed to decode name")
+        .unwrap_err();
+    let chain: Vec<String> =
+        core::iter::successors(Some(&err as &dyn Error), |&err| err.source())
+            .map(|err| err.to_string())
+            .collect();
+    let utf8 = core::str::from_utf8(&bytes).unwrap_err().to_string();
+
+    // The wrapper already prints the `Utf8Error`, which has no source.
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].matches(utf8.as_str()).count(), 1);
+    assert!(matches!(
+        &err.source,
+        ErrTypes::Utf8 { error } if error.source().is_none()
+    ));
+}
+
+#[test]
+fn test_wrapper_as_dyn_error() {
+    use alloc::boxed::Box;
+    use core::error::Error;
+
+    fn sync() -> Result<(), Box<dyn Error + Send + Sync>> {
+        Err::<(), usize>(500).context("Failed to sync")?;
+
+        Ok(())
+    }
+
+    let err = sync().unwrap_err();
+
+    assert_eq!(err.to_string(), "Failed to sync\nError: 500");
+    assert!(err.downcast_ref::<ResextErr>().is_some());
+    assert!(err.source().is_none());
+}
+
+#[test]
+fn test_strip_context() {
+    fn fetch() -> Resext<()> {
+        Err(404).context("Failed to fetch page")
+    }
+
+    fn load() -> Result<(), ErrTypes> {
+        fetch().strip_context()?;
+
+        Ok(())
+    }
+
+    assert!(matches!(load(), Err(ErrTypes::HttpResponse(404))));
+    assert!(matches!(
+        Err::<(), usize>(500).strip_context(),
+        Err(ErrTypes::HttpResponse(500))
+    ));
+}
+
+#[test]
+fn test_map_and_replace_source() {
+    let err = Err::<(), usize>(500)
+        .context("Failed to fetch page")
+        .unwrap_err()
+        .map_source(|source| match source {
+            ErrTypes::HttpResponse(code) => ErrTypes::HttpResponse(code + 3),
+            other => other,
+        });
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "Failed to fetch page\nError: 503"
+    );
+
+    let err = err.replace_source(core::str::from_utf8(&[158]).unwrap_err());
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "Failed to fetch page\nError: invalid utf-8 sequence of 1 bytes from index 0"
+    );
+}
+
+#[test]
+fn test_or_else_ctx() {
+    let recovered = Err::<usize, usize>(500)
+        .context("Failed to fetch page")
+        .or_else_ctx(|_| Ok::<usize, usize>(200));
+
+    assert_eq!(recovered.unwrap(), 200);
+
+    let err = Err::<(), usize>(500)
+        .context("Failed to fetch page")
+        .or_else_ctx(|err| Err::<(), usize>(err.code() as usize + 404))
+        .unwrap_err();
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "recovery attempted after: Failed to fetch page ● 500\nError: 404"
+    );
+}
+
+#[test]
+fn test_on_err_and_on_ok() {
+    let mut errors = 0;
+    let mut values = 0;
+
+    let res = Err::<usize, usize>(500)
+        .context("Failed to fetch page")
+        .on_err(|err| errors += err.code() + 1)
+        .on_ok(|_| values += 1);
+
+    assert!(res.is_err());
+
+    let res = Ok::<usize, usize>(200)
+        .on_err(|_| errors += 1)
+        .on_ok(|value| values += value);
+
+    assert_eq!(res, Ok(200));
+    assert_eq!((errors, values), (1, 200));
+}
+
+#[test]
+fn test_or_exit_ok() {
+    assert_eq!(Ok::<usize, usize>(200).or_exit(1), 200);
+}
+
+#[test]
+#[should_panic(
+    expected = "process exited with code 69:\nFailed to fetch page\nError: 503"
+)]
+fn test_or_exit_panics_in_test_mode() {
+    Err::<(), usize>(503).context("Failed to fetch page").or_exit(69);
+}
+
+#[test]
+fn test_expect_exit() {
+    let stderr = resext::testing::expect_exit(69, || {
+        Err::<(), usize>(503).context("Failed to fetch page").or_exit(69);
+    });
+
+    assert!(stderr.contains("Failed to fetch page\nError: 503"));
+}
+
+#[test]
+fn test_path_context() {
+    let err = Err::<(), usize>(404)
+        .path_context("Failed to read config", "/etc/app/config.toml")
+        .path_context("Failed to load", "/etc/app")
+        .unwrap_err();
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "Failed to read config: /etc/app/config.toml ● Failed to load: /etc/app\nError: 404"
+    );
+}
+
+#[test]
+fn test_infallible() {
+    fn convert<T: TryFrom<u8>>(value: u8) -> Resext<T>
+    where
+        ResextErr: From<T::Error>,
+    {
+        Ok(T::try_from(value)?)
+    }
+
+    assert_eq!(convert::<u16>(42).unwrap(), 42);
+    assert!(convert::<u8>(42).context("Failed to convert").is_ok());
+}
+
+#[test]
+#[should_panic(
+    expected = "process exited with code 76:\nFailed to fetch page\nError: 503"
+)]
+fn test_or_exit_policy() {
+    let policy = resext::ExitPolicy::new()
+        .map_variant("Utf8", 65)
+        .map_variant("HttpResponse", 76);
+
+    Err::<(), usize>(503)
+        .context("Failed to fetch page")
+        .or_exit_policy(&policy);
+}
+
+#[test]
+fn test_stats() {
+    let err = Err::<(), usize>(500)
+        .context("Failed to fetch page")
+        .context(ctx!("Failed to load {}", "dashboard"))
+        .unwrap_err();
+
+    let stats = err.stats();
+
+    assert_eq!(stats.contexts, 2);
+    assert_eq!(stats.truncations, 0);
+    assert_eq!(stats.spills, 1);
+    assert_eq!(ResextErr::new("", 404).stats().contexts, 0);
+}
+
+#[test]
+fn test_raw_and_lossy_context() {
+    let err = Err::<(), usize>(404)
+        .raw_context(b"Failed to fetch page")
+        .unwrap()
+        .unwrap_err();
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "Failed to fetch page\nError: 404"
+    );
+    assert!(Err::<(), usize>(404).raw_context(b"bad \xFF").is_err());
+    assert!(Ok::<(), usize>(()).raw_context(b"bad \xFF").is_ok());
+
+    let err = Err::<(), usize>(404)
+        .lossy_context(b"Failed to read \xFF\xFEfile")
+        .unwrap_err();
+
+    assert_eq!(
+        format_args!("{}", err).to_string(),
+        "Failed to read \u{FFFD}\u{FFFD}file\nError: 404"
+    );
+}
+
+#[resext::test_res]
+fn test_test_attribute() -> Resext<()> {
+    Ok::<(), usize>(()).context("Failed to fetch page")?;
+    Ok(())
+}
+
+#[resext::test_res]
+#[should_panic(expected = "Failed to fetch page\nError: 503")]
+fn test_test_attribute_displays_error() -> Resext<()> {
+    Err::<(), usize>(503).context("Failed to fetch page")?;
+    Ok(())
+}
+
+fn run(code: usize) -> Resext<()> {
+    Err::<(), usize>(code).context("Failed to run")?;
+    Ok(())
+}
+
+#[resext::test_res]
+#[should_panic(expected = "Failed to run\nError: 500")]
+fn test_test_attribute_calls_user_run() -> Resext<()> {
+    run(500)
+}
+
+// A glob import must leave the built-in `#[test]` unambiguous.
+mod glob_import {
+    #[allow(unused_imports)]
+    use resext::*;
+
+    #[test]
+    fn test_glob_import_keeps_builtin_test() {}
+}
+
+#[resext::main(build_info = build_info)]
+fn sync_main() -> Resext<()> {
+    Err::<(), usize>(503).context("Failed to start")?;
+    Ok(())
+}
+
+#[resext::main(async, build_info = false)]
+async fn tokio_main() -> Resext<()> {
+    Err::<(), usize>(503).context("Failed to start")?;
+    Ok(())
+}
+
+#[resext::main]
+#[tokio::main(flavor = "current_thread")]
+async fn composed_main() -> Resext<()> {
+    Err::<(), usize>(503).context("Failed to start")?;
+    Ok(())
+}
+
+fn build_info() -> &'static str {
+    "build 42"
+}
+
+// The generated entry point must not hide a `run()` helper of the user.
+mod user_run {
+    use super::*;
+
+    fn run() -> Resext<()> {
+        Err::<(), usize>(503).context("Failed to run")?;
+        Ok(())
+    }
+
+    #[resext::main(build_info = false)]
+    pub fn main() -> Resext<()> {
+        run()
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "process exited with code 1:\nFailed to start\nError: 503\nbuild 42"
+)]
+fn test_main_attribute() {
+    sync_main();
+}
+
+#[test]
+#[should_panic(
+    expected = "process exited with code 1:\nFailed to start\nError: 503"
+)]
+fn test_main_attribute_async() {
+    tokio_main();
+}
+
+#[test]
+#[should_panic(
+    expected = "process exited with code 1:\nFailed to start\nError: 503\nversion: "
+)]
+fn test_main_attribute_composed() {
+    composed_main();
+}
+
+#[test]
+#[should_panic(
+    expected = "process exited with code 1:\nFailed to run\nError: 503"
+)]
+fn test_main_attribute_calls_user_run() {
+    user_run::main();
+}
+
+#[test]
+fn test_on_err_sampled() {
+    let sampler = resext::Sampler::per_second(2);
+    let mut reported = 0;
+
+    for code in 0..5 {
+        let _ = Err::<(), usize>(code)
+            .context("Failed to handle packet")
+            .on_err_sampled(&sampler, |_| reported += 1);
+    }
+
+    let _ = Ok::<(), usize>(()).on_err_sampled(&sampler, |_| reported += 1);
+
+    assert_eq!(reported, 2);
+    assert_eq!(sampler.take_suppressed(), 3);
+}
+
+#[test]
+fn test_into_error_stack() {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use error_stack::{AttachmentKind, FrameKind};
+
+    let report = Err::<(), usize>(503)
+        .context("Failed to sync")
+        .context("Failed to start")
+        .unwrap_err()
+        .into_error_stack::<ErrTypes>();
+
+    let attachments: Vec<String> = report
+        .frames()
+        .filter_map(|frame| match frame.kind() {
+            FrameKind::Attachment(AttachmentKind::Printable(msg)) => {
+                Some(msg.to_string())
+            }
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(attachments, ["Failed to start", "Failed to sync"]);
+    assert!(matches!(report.current_context(), ErrTypes::HttpResponse(503)));
+}
+
+/// Runs `f` with the report sink writing into a buffer, returning what was
+/// reported without truncation warnings, which concurrently running tests
+/// can add.
+fn capture_reports(f: impl FnOnce()) -> alloc::string::String {
+    capture_output(f)
+        .split_inclusive('\n')
+        .filter(|line| !line.starts_with("warning: context added at "))
+        .collect()
+}
+
+/// Runs `f` with the report sink writing into a buffer, returning everything
+/// reported. The sink is global, so captures are serialized.
+fn capture_output(f: impl FnOnce()) -> alloc::string::String {
+    extern crate std;
+
+    use alloc::string::String;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use std::sync::{Mutex, PoisonError};
+
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    static SINK: Mutex<()> = Mutex::new(());
+
+    let _guard = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+    let out = Arc::new(Mutex::new(Vec::new()));
+    resext::set_report_sink(resext::IoSink(Shared(out.clone())));
+
+    f();
+
+    resext::reset_report_sink();
+
+    String::from_utf8(out.lock().unwrap().clone()).unwrap()
+}
+
+#[test]
+fn test_report_sink() {
+    extern crate std;
+
+    let mut ports = 0;
+    let mut code = std::process::ExitCode::SUCCESS;
+    let mut page = 0;
+
+    let out = capture_reports(|| {
+        ports = resext::ResWithWarnings::<_, &str>::new(8080)
+            .with_warning("Skipped invalid port")
+            .report_warnings();
+
+        // `MainResult` reports through the same sink.
+        let main: resext::MainResult<ResextErr> =
+            Err(ResextErr::new("Failed to fetch page", 404)).into();
+        code = std::process::Termination::report(main);
+
+        // So do verbose `or_default_ctx()` and `to_option_ctx()`.
+        page = Err::<u16, usize>(503).or_default_ctx(
+            "Failed to fetch page",
+            1,
+            true,
+        );
+    });
+
+    assert_eq!(ports, 8080);
+    assert_eq!(code, std::process::ExitCode::FAILURE);
+    assert_eq!(page, 1);
+    assert_eq!(
+        out,
+        "warning: Skipped invalid port\nFailed to fetch page\nError: 404\nFailed to fetch page\nError: 503\n"
+    );
+}
+
+#[test]
+fn test_fmt_into() {
+    let err =
+        Err::<(), _>(404usize).context("Failed to fetch page").unwrap_err();
+
+    let mut buf = [0u8; 64];
+    let len = err.fmt_into(&mut buf).unwrap();
+
+    assert_eq!(&buf[..len], b"Failed to fetch page\nError: 404");
+
+    let mut buf = [0u8; 8];
+
+    assert_eq!(err.fmt_into(&mut buf), Err(len));
+    assert_eq!(&buf, b"Failed t");
+}
+
+#[test]
+fn test_prepare_fatal() {
+    let err = Err::<(), usize>(130)
+        .context("Interrupted while writing output.csv")
+        .unwrap_err();
+
+    assert_eq!(resext::prepare_fatal(&err), Ok(48));
+
+    let long = "x".repeat(2000);
+
+    assert_eq!(resext::prepare_fatal(&long), Err(2001));
+}
+
+#[test]
+fn test_main_result() {
+    extern crate std;
+    use std::process::{ExitCode, Termination};
+
+    let ok: resext::MainResult<ResextErr> = Ok(()).into();
+
+    assert_eq!(ok.report(), ExitCode::SUCCESS);
+}
+
+#[test]
+fn test_or_default_and_option_ctx() {
+    assert_eq!(
+        Ok::<u16, usize>(8080).or_default_ctx("unused", 80, false),
+        8080
+    );
+    assert_eq!(
+        Err::<u16, usize>(404).or_default_ctx("Failed to fetch", 80, false),
+        80
+    );
+    assert_eq!(
+        Ok::<u16, usize>(8080).to_option_ctx("unused", false),
+        Some(8080)
+    );
+    assert_eq!(
+        Err::<u16, ResextErr>(ResextErr::new("", 404))
+            .to_option_ctx("Failed to fetch", false),
+        None
+    );
+}
+
+#[test]
+fn test_map_err_into() {
+    let results: alloc::vec::Vec<Resext<u16>> = [Ok(80), Err(404usize)]
+        .into_iter()
+        .map(|res| res.map_err_into())
+        .collect();
+
+    assert_eq!(results[0].as_ref().ok(), Some(&80));
+    assert_eq!(results[1].as_ref().unwrap_err().to_string(), "Error: 404");
+
+    let err = Err::<(), usize>(500)
+        .context("Failed to fetch")
+        .map_err_into()
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Failed to fetch\nError: 500");
+}
+
+#[tokio::test]
+async fn test_future_context() {
+    async fn fetch(code: usize) -> Result<u16, usize> {
+        if code == 200 { Ok(200) } else { Err(code) }
+    }
+
+    assert_eq!(fetch(200).context("Failed to fetch page").await.unwrap(), 200);
+
+    // Futures with context are values that can be spawned like any other.
+    let task = tokio::spawn(fetch(503).context("Failed to fetch page"));
+    let err = task.await.unwrap().unwrap_err();
+
+    assert_eq!(err.to_string(), "Failed to fetch page\nError: 503");
+
+    let err = async { Err::<(), ResextErr>(err) }
+        .context(alloc::format!("Failed to load {}", "dashboard"))
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Failed to fetch page ● Failed to load dashboard\nError: 503"
+    );
+}
+
+#[test]
+fn test_serialize() {
+    let err = Err::<(), usize>(503)
+        .context("Failed to fetch page")
+        .context("Failed to load \"dashboard\"")
+        .unwrap_err();
+
+    assert_eq!(
+        serde_json::to_string(&err).unwrap(),
+        r#"{"variant":"HttpResponse","source":"503","contexts":["Failed to fetch page","Failed to load \"dashboard\""]}"#
+    );
+    assert_eq!(
+        serde_json::to_string(&ResextErr::new("", 404)).unwrap(),
+        r#"{"variant":"HttpResponse","source":"404","contexts":[]}"#
+    );
+}
+
+#[test]
+fn test_to_json() {
+    let err = Err::<(), usize>(503)
+        .context("Failed to fetch \"page\"\tC:\\cache")
+        .context("Failed to load\n\u{1}dashboard 💥")
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_json(),
+        r#"{"error":"503","variant":"HttpResponse","contexts":["Failed to fetch \"page\"\tC:\\cache","Failed to load\n\u0001dashboard 💥"]}"#
+    );
+
+    let parsed: serde_json::Value =
+        serde_json::from_str(&err.to_json()).unwrap();
+
+    assert_eq!(parsed["contexts"][1], "Failed to load\n\u{1}dashboard 💥");
+}
+
+#[test]
+fn test_alternate_display() {
+    let err = Err::<(), usize>(404)
+        .context("Failed to fetch page")
+        .context("Failed to load\ndashboard")
+        .unwrap_err();
+
+    assert_eq!(
+        alloc::format!("{:#}", err),
+        "Failed to fetch page: Failed to load dashboard: 404"
+    );
+    assert_eq!(alloc::format!("{:#}", ResextErr::new("", 404)), "404");
+    assert_eq!(alloc::format!("{:#.20}", err), "Failed to fetch page");
+}
+
+#[test]
+fn test_display_padding() {
+    let err = ResextErr::new("Failed ● fetch", 404);
+
+    assert_eq!(alloc::format!("{:.10}", err), "Failed ● f");
+    assert_eq!(
+        alloc::format!("[{:<30}]", err),
+        "[Failed ● fetch\nError: 404     ]"
+    );
+    assert_eq!(
+        alloc::format!("[{:>27}]", err),
+        "[  Failed ● fetch\nError: 404]"
+    );
+    assert_eq!(alloc::format!("[{:*^12.6}]", err), "[***Failed***]");
+    assert_eq!(alloc::format!("[{:5}]", err), "[Failed ● fetch\nError: 404]");
+}
+
+#[test]
+fn test_render_colored() {
+    let err = Err::<(), usize>(404)
+        .context("Failed to fetch page")
+        .context("Failed to load")
+        .unwrap_err();
+
+    assert_eq!(
+        alloc::format!("{}", err.render_colored()),
+        "\x1b[36mFailed to fetch page\x1b[0m\x1b[2m ● \x1b[0m\x1b[36mFailed to load\x1b[0m\n\x1b[1;31mError: \x1b[0m404"
+    );
+    assert_eq!(
+        alloc::format!("{}", ResextErr::new("", 404).render_colored()),
+        "\x1b[1;31mError: \x1b[0m404"
+    );
+
+    // Escape codes take no space on screen.
+    assert_eq!(err.display_len(), err.to_string().chars().count());
+    assert_eq!(
+        resext::__private::display_len(&err.render_colored()),
+        err.display_len()
+    );
+}
+
+#[test]
+fn test_to_text() {
+    use ratatui_core::style::{Color, Modifier};
+    use ratatui_core::text::Text;
+
+    let err = Err::<(), usize>(404)
+        .context("Failed to fetch page")
+        .context("Failed to load")
+        .unwrap_err();
+
+    let text = err.to_text();
+
+    assert_eq!(text.lines.len(), 3);
+    assert_eq!(text.lines[0].to_string(), "Failed to fetch page");
+    assert_eq!(text.lines[1].to_string(), "Failed to load");
+    assert_eq!(text.lines[2].to_string(), "Error: 404");
+    assert_eq!(text.lines[0].style.fg, Some(Color::Cyan));
+    assert_eq!(text.lines[2].spans[0].style.fg, Some(Color::Red));
+    assert!(text.lines[2].spans[0].style.add_modifier.contains(Modifier::BOLD));
+
+    assert_eq!(Text::from(&err), text);
+    assert_eq!(ResextErr::new("", 404).to_text().lines.len(), 1);
+}
+
+#[test]
+fn test_error_log() {
+    extern crate std;
+    use resext::ErrorSink;
+    use resext::history::ErrorLog;
+    use std::time::{Duration, SystemTime};
+
+    let log = ErrorLog::new(2);
+    let start = SystemTime::now() - Duration::from_secs(1);
+
+    for code in [500, 502, 503] {
+        Err::<(), usize>(code)
+            .context("Failed to fetch page")
+            .unwrap_err()
+            .log_to(&log);
+    }
+    (&log).report(&"Lost connection");
+
+    assert_eq!(log.len(), 2);
+
+    let latest = log.latest(5);
+
+    assert_eq!(latest.len(), 2);
+    assert_eq!(latest[0].message, "Lost connection");
+    assert_eq!(latest[0].variant, None);
+    assert_eq!(latest[1].message, "Failed to fetch page\nError: 503");
+    assert_eq!(latest[1].variant, Some("HttpResponse"));
+
+    assert_eq!(log.by_variant("HttpResponse").len(), 1);
+    assert!(log.by_variant("Utf8").is_empty());
+    assert_eq!(log.since(start).len(), 2);
+    assert!(log.since(SystemTime::now() + Duration::from_secs(60)).is_empty());
+
+    log.clear();
+
+    assert!(log.is_empty());
+}
+
+mod isolated_test {
+    use alloc::string::ToString;
+    use resext::ctx;
+    use resext_macro::resext;
+
+    #[test]
+    fn test_msg_truncation() {
+        #[resext(buf_size = 5)]
+        enum TestErr {
+            Utf8(core::str::Utf8Error),
+        }
+
+        let err =
+            core::str::from_utf8(&[0, 158, 22]).context("Good💖").unwrap_err();
+
+        assert!(err.was_truncated());
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Good...\nError: invalid utf-8 sequence of 1 bytes from index 1"
+        );
+
+        // Debug builds count the cut off bytes for the truncation warning.
+        let required = if cfg!(debug_assertions) { 8 } else { 4 };
+
+        assert_eq!(resext::ContextBuf::required_len(&err.msg), required);
+
+        let err =
+            core::str::from_utf8(&[0, 158, 22]).context("Good").unwrap_err();
+
+        assert!(!err.was_truncated());
+    }
+
+    #[test]
+    fn test_nothing_appended_after_truncation() {
+        #[resext(alias = Stopped, buf_size = 6, delimiter = "/")]
+        enum StoppedErrors {
+            Http(usize),
+        }
+
+        // The emoji doesn't fit, the bytes left after "Good" stay unused.
+        let err =
+            Err::<(), usize>(500).context("Good💖").context("ab").unwrap_err();
+
+        assert!(err.was_truncated());
+        assert_eq!(err.to_string(), "Good...\nError: 500");
+        assert!(err.contexts().eq(["Good"]));
+    }
+
+    #[test]
+    fn test_into_code_msg_and_parts_inline_buf() {
+        #[resext(alias = Embedded, buf_size = 16)]
+        enum EmbeddedErrors {
+            Http(usize) = 7,
+        }
+
+        let (code, msg) = Err::<(), usize>(503)
+            .context("Failed to reach upstream")
+            .map_err(EmbeddedErr::into_code_msg)
+            .unwrap_err();
+
+        assert_eq!(code, 7);
+        assert_eq!(msg, "Failed to reach ...\nError: 503");
+
+        let (msg, source) = Err::<(), usize>(429)
+            .context("Rate limited")
+            .unwrap_err()
+            .into_parts();
+
+        assert_eq!(msg, "Rate limited");
+        assert!(matches!(source, EmbeddedErrors::Http(429)));
+    }
+
+    #[test]
+    fn test_truncation_warning() {
+        #[resext(alias = Cut, buf_size = 4)]
+        enum CutErrors {
+            Http(usize),
+        }
+
+        let mut line = 0;
+        let out = crate::capture_output(|| {
+            for _ in 0..2 {
+                line = line!() + 1;
+                let _ = Err::<(), usize>(500).context("Too long");
+            }
+        });
+
+        if cfg!(debug_assertions) {
+            let at = alloc::format!(
+                "warning: context added at {}:{}:",
+                file!(),
+                line
+            );
+            let warnings = out.lines().filter(|warning| {
+                warning.starts_with(&at)
+                    && warning.ends_with(" was truncated, it needs 8 bytes but the buffer holds 4, consider a larger `buf_size`")
+            });
+
+            assert_eq!(warnings.count(), 1);
+        } else {
+            assert!(!out.contains("was truncated"));
+        }
+    }
+
+    #[test]
+    fn test_discriminant_codes() {
+        #[resext(alias = Coded)]
+        enum CodedErrors {
+            NotFound(usize) = 10,
+            Utf8(core::str::Utf8Error),
+            Timeout = 20,
+        }
+
+        let not_found = CodedErr::from(404usize);
+        let utf8 = core::str::from_utf8(&[0, 158, 22])
+            .context("Failed to decode")
+            .unwrap_err();
+
+        assert_eq!(not_found.code(), 10);
+        assert_eq!(utf8.code(), 11);
+        assert_eq!(CodedErrors::Timeout.code(), 20);
+    }
+
+    #[test]
+    fn test_format_template() {
+        #[resext(
+            alias = Tmpl,
+            prefix = "oops: ",
+            format = "{prefix}{contexts:, }{newline}caused by {variant}: {source} {{end}}"
+        )]
+        enum TmplErrors {
+            Utf8(core::str::Utf8Error),
+        }
+
+        let err = core::str::from_utf8(&[0, 158, 22])
+            .context("Failed to decode")
+            .context("Failed to load")
+            .unwrap_err();
+
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "oops: Failed to decode, Failed to load\ncaused by Utf8: invalid utf-8 sequence of 1 bytes from index 1 {end}"
+        );
+
+        let bare = TmplErr::from(core::str::from_utf8(&[158]).unwrap_err());
+
+        assert_eq!(
+            format_args!("{}", bare).to_string(),
+            "oops: caused by Utf8: invalid utf-8 sequence of 1 bytes from index 0 {end}"
+        );
+    }
+
+    #[test]
+    fn test_headline_last_context() {
+        #[resext(alias = Headline, headline = "last_context", alloc = true, buf_size = 16)]
+        enum HeadlineErrors {
+            Utf8(core::str::Utf8Error),
+        }
+
+        let err = core::str::from_utf8(&[0, 158, 22])
+            .context("Failed to read file")
+            .context(ctx!("Failed to load {}", "config"))
+            .context("Failed to deploy")
+            .unwrap_err();
+
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Failed to deploy\n - Failed to load config\n - Failed to read file\nError: invalid utf-8 sequence of 1 bytes from index 1"
+        );
+    }
+
+    #[test]
+    fn test_headline_last_truncation() {
+        #[resext(alias = Clipped, headline = "last_context", buf_size = 24)]
+        enum ClippedErrors {
+            Http(usize),
+        }
+
+        // The new message is cut off, the older one is kept whole.
+        let err = Err::<(), usize>(500)
+            .context("Failed to read file")
+            .context("Failed to deploy")
+            .context("Failed to retry")
+            .unwrap_err();
+
+        assert!(err.was_truncated());
+        assert_eq!(
+            err.to_string(),
+            "Faile...\n - Failed to read file\nError: 500"
+        );
+        assert_eq!(
+            alloc::format!("{:#}", err),
+            "Faile...: Failed to read file: 500"
+        );
+        assert!(err.contexts().eq(["Faile", "Failed to read file"]));
+
+        // The message fits, but its delimiter doesn't.
+        let err = Err::<(), usize>(500)
+            .context("0123456789abcdef")
+            .context("Retry")
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Retry...\n - 0123456789abcdef\nError: 500"
+        );
+        assert!(err.contexts().eq(["Retry", "0123456789abcdef"]));
+    }
+
+    #[test]
+    fn test_contexts_containing_delimiter() {
+        {
+            #[resext(alias = Framed, delimiter = ": ", msg_prefix = "[", msg_suffix = "]")]
+            enum FramedErrors {
+                Http(usize),
+            }
+
+            let err = Err::<(), usize>(500)
+                .context("Failed: fetch")
+                .context("Failed: render")
+                .unwrap_err();
+
+            assert!(err.contexts().eq(["Failed: fetch", "Failed: render"]));
+            assert!(
+                err.contexts().rev().eq(["Failed: render", "Failed: fetch"])
+            );
+
+            // Context written with `write!()` is one more message.
+            let mut err = FramedErr::new("Failed: fetch", 500usize);
+            let _ = core::fmt::Write::write_str(&mut err, ": [Failed: retry]");
+
+            assert!(err.contexts().eq(["Failed: fetch", "Failed: retry"]));
+        }
+
+        {
+            #[resext(alias = Rotated, delimiter = ": ", headline = "last_context", alloc = true, buf_size = 8)]
+            enum RotatedErrors {
+                Http(usize),
+            }
+
+            let err = Err::<(), usize>(500)
+                .context("Failed: fetch")
+                .context("Failed: render")
+                .context("Failed: deploy")
+                .unwrap_err();
+
+            assert_eq!(
+                err.to_string(),
+                "Failed: deploy: Failed: render: Failed: fetch\nError: 500"
+            );
+            assert!(err.contexts().eq([
+                "Failed: deploy",
+                "Failed: render",
+                "Failed: fetch"
+            ]));
+            assert_eq!(err.contexts().next_back(), Some("Failed: fetch"));
+            assert_eq!(
+                err.to_json(),
+                r#"{"error":"500","variant":"Http","contexts":["Failed: deploy","Failed: render","Failed: fetch"]}"#
+            );
+        }
+    }
+
+    #[test]
+    fn test_non_exhaustive() {
+        #[resext(alias = Exhaust, non_exhaustive = true)]
+        #[derive(PartialEq)]
+        enum ExhaustErrors {
+            NotFound,
+            Utf8(core::str::Utf8Error),
+        }
+
+        #[resext(alias = ManualExhaust, non_exhaustive = true)]
+        #[non_exhaustive]
+        enum ManualExhaustErrors {
+            Timeout,
+        }
+
+        let err = ExhaustErr::from(ExhaustErrors::NotFound);
+
+        assert!(err.source == ExhaustErrors::NotFound);
+        assert_eq!(format_args!("{}", err).to_string(), "Error: NotFound");
+        assert_eq!(ManualExhaustErrors::Timeout.variant_name(), "Timeout");
+    }
+
+    #[test]
+    fn test_opaque() {
+        #[resext(alias = Opaque, opaque = true)]
+        pub enum OpaqueErrors {
+            Utf8(core::str::Utf8Error),
+            Timeout,
+        }
+
+        let err = core::str::from_utf8(&[158])
+            .context("Failed to decode")
+            .unwrap_err();
+
+        assert_eq!(err.kind(), OpaqueKind::Utf8);
+        assert_eq!(
+            OpaqueErr::from(OpaqueErrors::Timeout).kind(),
+            OpaqueKind::Timeout
+        );
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Failed to decode\nError: invalid utf-8 sequence of 1 bytes from index 0"
+        );
+    }
+
+    #[test]
+    fn test_classify_is_opt_in() {
+        // Enums without `classify` may wrap types that `anyhow` can't hold,
+        // even with the `anyhow` feature enabled elsewhere in the build.
+        #[resext(alias = Local)]
+        enum LocalErrors {
+            Shared(alloc::rc::Rc<str>),
+        }
+
+        let err = Err::<(), _>(alloc::rc::Rc::<str>::from("poisoned"))
+            .context("Failed to read cache")
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to read cache\nError: poisoned");
+    }
+
+    #[test]
+    fn test_classify() {
+        use resext::Classify;
+
+        #[resext(alias = Classified, classify = true)]
+        enum ClassifiedErrors {
+            Utf8(core::str::Utf8Error),
+            Parse { error: core::num::ParseIntError },
+            Other(anyhow::Error),
+        }
+
+        let err = Err::<(), _>(anyhow::Error::new(
+            core::str::from_utf8(&[158]).unwrap_err(),
+        ))
+        .classify::<ClassifiedErrors>()
+        .unwrap_err();
+
+        assert_eq!(err.variant_name(), "Utf8");
+
+        let err =
+            Err::<(), _>(anyhow::Error::new("x".parse::<u8>().unwrap_err()))
+                .classify::<ClassifiedErrors>()
+                .unwrap_err();
+
+        assert_eq!(err.variant_name(), "Parse");
+
+        let err = Err::<(), _>(anyhow::anyhow!("Unknown failure"))
+            .classify::<ClassifiedErrors>()
+            .context("Failed to run legacy task")
+            .unwrap_err();
+
+        assert_eq!(err.source.variant_name(), "Other");
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Failed to run legacy task\nError: Unknown failure"
+        );
+    }
+
+    #[test]
+    fn test_error_for_status_ctx() {
+        use resext::ResponseExt;
+
+        #[resext(alias = Api, buf_size = 128)]
+        enum ApiErrors {
+            Http(reqwest::Error),
+        }
+
+        fn response(status: u16) -> reqwest::Response {
+            http::Response::builder().status(status).body("").unwrap().into()
+        }
+
+        let err = response(404)
+            .error_for_status_ctx::<ApiErr>()
+            .context("Failed to fetch user")
+            .unwrap_err();
+
+        assert_eq!(
+            err.contexts().collect::<alloc::vec::Vec<_>>(),
+            [
+                "Request to `http://no.url.provided.local/` failed with status 404 Not Found",
+                "Failed to fetch user",
+            ]
+        );
+        assert!(
+            matches!(&err.source, ApiErrors::Http(http) if http.status() == Some(reqwest::StatusCode::NOT_FOUND))
+        );
+
+        let ok = response(204).error_for_status_ctx::<ApiErr>().unwrap();
+
+        assert_eq!(ok.status(), reqwest::StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    fn test_parse_ctx() {
+        #[resext(alias = Parsed)]
+        enum ParsedErrors {
+            Int(core::num::ParseIntError),
+        }
+
+        let input = alloc::string::String::from("42");
+        let value: u8 = input.parse_ctx("Failed to parse retry count").unwrap();
+
+        assert_eq!(value, 42);
+
+        let err =
+            "x".parse_ctx::<u8>("Failed to parse retry count").unwrap_err();
+
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Failed to parse retry count (as u8)\nError: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_minimal() {
+        #[resext(minimal = true, include_variant = true)]
+        enum MinimalErrors {
+            Utf8(core::str::Utf8Error),
+            Timeout,
+        }
+
+        fn decode(bytes: &[u8]) -> Result<&str, MinimalErrors> {
+            Ok(core::str::from_utf8(bytes)?)
+        }
+
+        let err = decode(&[158]).unwrap_err();
+
+        assert_eq!(err.variant_name(), "Utf8");
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Utf8: invalid utf-8 sequence of 1 bytes from index 0"
+        );
+        assert_eq!(
+            format_args!("{}", MinimalErrors::Timeout).to_string(),
+            "Timeout"
+        );
+    }
+
+    #[test]
+    fn test_buf_capacity() {
+        #[resext(alias = Exact, buf_size = 8)]
+        enum ExactErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(404).context("12345678").unwrap_err();
+        assert_eq!(format_args!("{}", err).to_string(), "12345678\nError: 404");
+
+        let err = Err::<(), usize>(404)
+            .context("12345678")
+            .context("9")
+            .context("more")
+            .unwrap_err();
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "12345678...\nError: 404"
+        );
+    }
+
+    #[test]
+    fn test_buf_wider_than_u16() {
+        #[resext(alias = Wide, buf_size = 70000)]
+        enum WideErrors {
+            Http(usize),
+        }
+
+        let chunk = "x".repeat(1000);
+        let mut res: Result<(), WideErr> = Err(WideErr::from(404usize));
+
+        for _ in 0..69 {
+            res = res.context(chunk.as_str());
+        }
+
+        let err = res.unwrap_err();
+        let output = format_args!("{}", err).to_string();
+
+        assert!(!output.contains("..."));
+        assert_eq!(output.matches('x').count(), 69000);
+    }
+
+    #[test]
+    fn test_custom_storage() {
+        #[resext(alias = Stored, storage = alloc::string::String, headline = "last_context")]
+        enum StoredErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(404)
+            .context("Failed to fetch a page with a rather long url")
+            .context(ctx!("Failed to load {}", "dashboard"))
+            .unwrap_err();
+
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Failed to load dashboard\n - Failed to fetch a page with a rather long url\nError: 404"
+        );
+    }
+
+    #[test]
+    fn test_origin() {
+        #[resext(alias = Core, origin = "cli-core")]
+        enum CoreErrors {
+            Http(usize),
+        }
+
+        #[resext(alias = Pkg, origin = true)]
+        enum PkgErrors {
+            Http(usize),
+        }
+
+        let err = CoreErr::new("Failed to sync", 503usize);
+
+        assert_eq!(err.origin(), "cli-core");
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "[cli-core] Failed to sync\nError: 503"
+        );
+        assert_eq!(
+            format_args!("{}", PkgErr::from(404usize)).to_string(),
+            "[resext-macro] Error: 404"
+        );
+    }
+
+    #[test]
+    fn test_error_id() {
+        #[resext(alias = Tracked, id = true)]
+        enum TrackedErrors {
+            Http(usize),
+        }
+
+        fn fixed_id() -> u32 {
+            0x7f3a
+        }
+
+        #[resext(alias = Fixed, id = fixed_id)]
+        enum FixedErrors {
+            Http(usize),
+        }
+
+        let first = TrackedErr::new("Failed to sync", 503usize);
+        let second = TrackedErr::from(404usize);
+
+        assert_ne!(first.id(), second.id());
+
+        let err = FixedErr::new("Failed to sync", 503usize);
+
+        assert_eq!(err.id(), 0x7f3a);
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Failed to sync\nError: 503\nerror id: 7f3a"
+        );
+    }
+
+    #[test]
+    fn test_related() {
+        #[resext(alias = Batch, related = true)]
+        enum BatchErrors {
+            Http(usize),
+        }
+
+        let mut err = BatchErr::new("Failed to sync", 503usize)
+            .add_related(BatchErr::new("Failed to roll back", 500usize));
+        err.push_related(404usize);
+
+        assert_eq!(err.related().len(), 2);
+        assert_eq!(
+            format_args!("{}", err).to_string(),
+            "Failed to sync\nError: 503\nrelated errors:\n  - Failed to roll back\n    Error: 500\n  - Error: 404"
+        );
+    }
+
+    #[test]
+    fn test_help() {
+        #[resext(alias = Cleanup, help = true, related = true)]
+        enum CleanupErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(409)
+            .context("Failed to remove build directory")
+            .help("try running with --force")
+            .unwrap_err()
+            .add_related(CleanupErr::new("Failed to unlock", 423usize))
+            .help(alloc::format!("or delete {}\nby hand", "target/"));
+
+        assert_eq!(
+            err.help_messages().collect::<alloc::vec::Vec<_>>(),
+            ["try running with --force", "or delete target/\nby hand"]
+        );
+        assert_eq!(err.contexts().count(), 1);
+        assert_eq!(
+            err.to_string(),
+            "Failed to remove build directory\nError: 409\nrelated errors:\n  - Failed to unlock\n    Error: 423\nhelp: try running with --force\nhelp: or delete target/\n    by hand"
+        );
+        assert!(
+            alloc::format!("{:?}", err)
+                .contains("\nhelp: try running with --force\n")
+        );
+    }
+
+    #[test]
+    fn test_notes() {
+        #[resext(alias = Deploy, notes = true, help = true, related = true)]
+        enum DeployErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(502)
+            .context("Failed to deploy")
+            .note("request id: 7f3a")
+            .help("retry later")
+            .unwrap_err()
+            .add_related(DeployErr::new("Failed to roll back", 500usize))
+            .note(alloc::format!("region: {}", "eu-west-1"));
+
+        assert_eq!(
+            err.notes().collect::<alloc::vec::Vec<_>>(),
+            ["request id: 7f3a", "region: eu-west-1"]
+        );
+        assert_eq!(err.contexts().count(), 1);
+        assert_eq!(
+            err.to_string(),
+            "Failed to deploy\nError: 502\nnote: request id: 7f3a\nnote: region: eu-west-1\nrelated errors:\n  - Failed to roll back\n    Error: 500\nhelp: retry later"
+        );
+        assert!(
+            alloc::format!("{:?}", err).contains("\nnote: request id: 7f3a\n")
+        );
+    }
+
+    #[test]
+    fn test_docs_url() {
+        #[resext(alias = Lookup, show_docs_url = true, notes = true)]
+        enum LookupErrors {
+            #[resext(docs_url = "https://docs.example.com/errors/E042")]
+            Http(usize),
+            Parse(core::num::ParseIntError),
+        }
+
+        let err = Err::<(), usize>(404)
+            .context("Failed to fetch user")
+            .note("request id: 7f3a")
+            .unwrap_err();
+
+        assert_eq!(
+            err.docs_url(),
+            Some("https://docs.example.com/errors/E042")
+        );
+        assert_eq!(
+            err.to_string(),
+            "Failed to fetch user\nError: 404\nnote: request id: 7f3a\nsee: https://docs.example.com/errors/E042"
+        );
+        assert!(
+            alloc::format!("{:?}", err)
+                .contains("\nsee: https://docs.example.com/errors/E042")
+        );
+
+        let err = "x".parse::<u8>().context("Failed to parse id").unwrap_err();
+
+        assert_eq!(err.docs_url(), None);
+        assert!(!err.to_string().contains("see: "));
+    }
+
+    #[test]
+    fn test_debug_summary() {
+        #[resext(alias = Upload, buf_size = 32)]
+        enum UploadErrors {
+            #[resext(debug_summary = 8)]
+            Body(alloc::string::String),
+            Http(usize),
+        }
+
+        let err = Err::<(), _>("é".repeat(1000))
+            .context("Failed to upload")
+            .unwrap_err();
+
+        assert!(
+            alloc::format!("{:?}", err)
+                .contains("Error: Body(\"éé… <1008 chars>")
+        );
+
+        let err =
+            Err::<(), usize>(413).context("Failed to upload").unwrap_err();
+
+        assert!(alloc::format!("{:?}", err).contains("Error: Http(413)"));
+    }
+
+    #[test]
+    fn test_attachments() {
+        #[derive(Debug, PartialEq)]
+        struct RequestId(u32);
+
+        #[resext(alias = Api, attachments = true)]
+        enum ApiErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(503)
+            .context("Failed to sync")
+            .attach(RequestId(7))
+            .attach("replica-2")
+            .unwrap_err()
+            .attach(RequestId(8));
+
+        assert_eq!(err.get_attached::<RequestId>(), Some(&RequestId(8)));
+        assert_eq!(err.get_attached::<&str>(), Some(&"replica-2"));
+        assert_eq!(err.get_attached::<usize>(), None);
+    }
+
+    #[test]
+    fn test_variant_display() {
+        #[resext(alias = Upstream)]
+        enum UpstreamErrors {
+            #[resext(display = "failed to fetch {0} from upstream")]
+            Fetch(&'static str),
+            #[resext(display = "upstream returned {status} ({{status}})")]
+            Status {
+                status: u16,
+            },
+            #[resext(display = "upstream unavailable")]
+            Unavailable,
+            Http(usize),
+        }
+
+        assert_eq!(
+            UpstreamErrors::Fetch("/users").to_string(),
+            "failed to fetch /users from upstream"
+        );
+        assert_eq!(
+            UpstreamErrors::Status { status: 503 }.to_string(),
+            "upstream returned 503 ({status})"
+        );
+        assert_eq!(
+            UpstreamErrors::Unavailable.to_string(),
+            "upstream unavailable"
+        );
+        assert_eq!(UpstreamErrors::Http(404).to_string(), "404");
+        assert_eq!(
+            UpstreamErr::new("Failed to sync", UpstreamErrors::Unavailable)
+                .to_string(),
+            "Failed to sync\nError: upstream unavailable"
+        );
+    }
+
+    #[test]
+    fn test_exit_code() {
+        #[resext(alias = Cli)]
+        enum CliErrors {
+            #[resext(exit_code = "EX_IOERR")]
+            Io(&'static str),
+            #[resext(exit_code = 3)]
+            Http(usize),
+            Parse(core::num::ParseIntError),
+        }
+
+        assert_eq!(
+            CliErrors::Io("disk full").exit_code(),
+            resext::exitcode::EX_IOERR
+        );
+        assert_eq!(CliErr::new("Failed to sync", 503usize).exit_code(), 3);
+        assert_eq!(
+            "x".parse::<u8>().map_err(CliErrors::from).unwrap_err().exit_code(),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "process exited with code 74:")]
+    fn test_or_exit_auto_named_code() {
+        #[resext(alias = Tool)]
+        enum ToolErrors {
+            #[resext(exit_code = "EX_IOERR")]
+            Io(&'static str),
+        }
+
+        Err::<(), _>("disk full")
+            .context("Failed to write report")
+            .or_exit_auto();
+    }
+
+    #[test]
+    #[should_panic(expected = "process exited with code 300:")]
+    fn test_or_exit_auto() {
+        #[resext(alias = Job)]
+        enum JobErrors {
+            #[resext(exit_code = 300)]
+            Queue(&'static str),
+        }
+
+        Err::<(), _>("queue closed")
+            .context("Failed to schedule job")
+            .or_exit_auto();
+    }
+
+    #[test]
+    fn test_sysexit_code() {
+        extern crate std;
+        use resext::exitcode;
+
+        #[resext(alias = Sys)]
+        enum SysErrors {
+            Io(std::io::Error),
+            Parse {
+                error: core::num::ParseIntError,
+            },
+            #[resext(exit_code = 3)]
+            Http(usize),
+            Plain(&'static str),
+        }
+
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+
+        assert_eq!(
+            SysErrors::Io(not_found).sysexit_code(),
+            exitcode::EX_NOINPUT
+        );
+        assert_eq!(
+            SysErrors::Io(std::io::Error::other("broken pipe")).sysexit_code(),
+            exitcode::EX_IOERR
+        );
+        assert_eq!(
+            "x".parse::<u8>()
+                .context("Failed to parse port")
+                .unwrap_err()
+                .sysexit_code(),
+            exitcode::EX_DATAERR
+        );
+        assert_eq!(SysErrors::Http(503).sysexit_code(), 3);
+        assert_eq!(
+            SysErrors::Plain("oops").sysexit_code(),
+            exitcode::EX_SOFTWARE
+        );
+
+        // Errors wrapped by other errors are found through `source()`.
+        let wrapped = std::io::Error::other("x".parse::<u8>().unwrap_err());
+
+        assert_eq!(exitcode::for_error(&wrapped), Some(exitcode::EX_IOERR));
+        assert_eq!(
+            exitcode::for_error(wrapped.get_ref().unwrap()),
+            Some(exitcode::EX_DATAERR)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "process exited with code 66:")]
+    fn test_or_exit_sysexit() {
+        extern crate std;
+
+        #[resext(alias = Loader)]
+        enum LoaderErrors {
+            Io(std::io::Error),
+        }
+
+        std::fs::read("missing/config.toml")
+            .context("Failed to read config")
+            .or_exit_sysexit();
+    }
+
+    #[test]
+    fn test_skip_from() {
+        #[resext(alias = Transfer)]
+        enum TransferErrors {
+            Read(core::str::Utf8Error),
+            #[resext(skip_from)]
+            Write(core::str::Utf8Error),
+        }
+
+        let bytes = [0, 158];
+        let err = core::str::from_utf8(&bytes)
+            .map_err(TransferErrors::Write)
+            .context("Failed to write file")
+            .unwrap_err();
+
+        assert!(matches!(err.into_source(), TransferErrors::Write(_)));
+
+        let err = core::str::from_utf8(&bytes)
+            .context("Failed to read file")
+            .unwrap_err();
+
+        assert!(matches!(err.into_source(), TransferErrors::Read(_)));
+    }
+
+    #[test]
+    fn test_skip_display() {
+        #[derive(Debug)]
+        struct Frame([u8; 16]);
+
+        impl core::fmt::Display for Frame {
+            fn fmt(
+                &self,
+                f: &mut core::fmt::Formatter<'_>,
+            ) -> core::fmt::Result {
+                write!(f, "{:?}", self.0)
+            }
+        }
+
+        #[resext(alias = Api, include_variant = true)]
+        enum ApiErrors {
+            #[resext(skip_display)]
+            Body(alloc::string::String),
+            #[resext(skip_display)]
+            Frame {
+                frame: Frame,
+            },
+            Http(usize),
+        }
+
+        let err = Err::<(), _>("x".repeat(1 << 20))
+            .context("Failed to parse response")
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse response\nError: Body: <1048576 bytes>"
+        );
+        assert_eq!(
+            ApiErrors::Frame { frame: Frame([0; 16]) }.to_string(),
+            "Frame"
+        );
+        assert_eq!(ApiErrors::Http(503).to_string(), "Http: 503");
+    }
+
+    #[test]
+    fn test_generic_enum() {
+        #[resext(alias = Store)]
+        enum StoreErrors<E: core::error::Error + 'static> {
+            Backend(E),
+            Parse(core::num::ParseIntError),
+        }
+
+        fn load<E: core::error::Error + 'static>(
+            res: Result<&str, E>,
+        ) -> Store<u8, E> {
+            let raw = res
+                .map_err(StoreErrors::Backend)
+                .context("Failed to load value")?;
+
+            raw.parse::<u8>().context("Failed to parse value")
+        }
+
+        let err = load(Err::<&str, _>(core::fmt::Error)).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to load value\nError: an error occurred when formatting an argument"
+        );
+        assert!(matches!(err.into_source(), StoreErrors::Backend(_)));
+
+        let err = load::<core::fmt::Error>(Ok("x")).unwrap_err();
+
+        assert!(matches!(err.into_source(), StoreErrors::Parse(_)));
+    }
+
+    #[test]
+    fn test_generic_enum_without_static_bound() {
+        #[resext(alias = Plugin)]
+        enum PluginErrors<E: core::error::Error> {
+            Inner(E),
+            Fmt(core::fmt::Error),
+        }
+
+        let err = Err::<(), _>(PluginErrors::Inner(core::fmt::Error))
+            .context("Failed to run plugin")
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to run plugin\nError: an error occurred when formatting an argument"
+        );
+        assert!(core::error::Error::source(&err).is_none());
+    }
+
+    #[test]
+    fn test_where_clause_and_const_generics() {
+        #[derive(Debug)]
+        struct Overflow<const N: usize>;
+
+        impl<const N: usize> core::fmt::Display for Overflow<N> {
+            fn fmt(
+                &self,
+                f: &mut core::fmt::Formatter<'_>,
+            ) -> core::fmt::Result {
+                write!(f, "batch is over {} items", N)
+            }
+        }
+
+        #[resext(alias = Batch)]
+        enum BatchErrors<E = core::fmt::Error, const N: usize = 4>
+        where
+            E: core::error::Error + 'static,
+        {
+            Backend(E),
+            Overflow(Overflow<N>),
+        }
+
+        fn push<const N: usize>(len: usize) -> Batch<(), core::fmt::Error, N> {
+            if len >= N {
+                return Err(BatchErrors::Overflow(Overflow))
+                    .context("Failed to queue record");
+            }
+
+            Err(BatchErrors::Backend(core::fmt::Error))
+                .context("Failed to write record")
+        }
+
+        // Defaults carry over to the alias and the wrapper.
+        let err: BatchErr = push(4).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to queue record\nError: batch is over 4 items"
+        );
+        assert_eq!(err.contexts().count(), 1);
+        assert!(matches!(
+            push::<8>(4).unwrap_err().into_source(),
+            BatchErrors::Backend(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generics_named_like_generated_ones() {
+        use resext::static_context;
+
+        #[derive(Debug)]
+        struct Limit<const LEN: usize>;
+
+        impl<const LEN: usize> core::fmt::Display for Limit<LEN> {
+            fn fmt(
+                &self,
+                f: &mut core::fmt::Formatter<'_>,
+            ) -> core::fmt::Result {
+                write!(f, "limit of {} bytes", LEN)
+            }
+        }
+
+        #[resext(alias = Capped, futures = true)]
+        enum CappedErrors<M, Fut, const LEN: usize>
+        where
+            M: core::error::Error,
+            Fut: core::error::Error,
+        {
+            Limit(Limit<LEN>),
+            #[resext(skip_from)]
+            Encode(M),
+            #[resext(skip_from)]
+            Flush(Fut),
+        }
+
+        type FmtCapped<const LEN: usize> =
+            CappedErr<core::fmt::Error, core::fmt::Error, LEN>;
+
+        let err: FmtCapped<16> = Err::<(), _>(CappedErrors::Limit(Limit))
+            .context(static_context!("Failed to append"))
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to append\nError: limit of 16 bytes"
+        );
+
+        let err: FmtCapped<16> =
+            Err::<(), _>(CappedErrors::Encode(core::fmt::Error))
+                .context("Failed to encode")
+                .unwrap_err();
+
+        assert!(matches!(err.into_source(), CappedErrors::Encode(_)));
+
+        let err: FmtCapped<16> =
+            async { Err::<(), _>(CappedErrors::Flush(core::fmt::Error)) }
+                .context("Failed to flush")
+                .await
+                .unwrap_err();
+
+        assert!(matches!(err.into_source(), CappedErrors::Flush(_)));
+    }
+
+    #[test]
+    fn test_generics_named_like_method_generics() {
+        #[resext(alias = Annotated, notes = true, help = true)]
+        enum AnnotatedErrors<N, H>
+        where
+            N: core::error::Error,
+            H: core::error::Error,
+        {
+            Inner(N),
+            #[resext(skip_from)]
+            Hint(H),
+        }
+
+        type FmtAnnotated = AnnotatedErr<core::fmt::Error, core::fmt::Error>;
+
+        let err: FmtAnnotated =
+            Err::<(), _>(AnnotatedErrors::Inner(core::fmt::Error))
+                .context("Failed to render")
+                .note("template: index.html")
+                .unwrap_err();
+
+        assert_eq!(err.notes().count(), 1);
+
+        let err: FmtAnnotated =
+            Err::<(), _>(AnnotatedErrors::Hint(core::fmt::Error))
+                .context("Failed to render")
+                .help("check the template syntax")
+                .unwrap_err();
+
+        assert_eq!(err.help_messages().count(), 1);
+    }
+
+    #[test]
+    fn test_user_message() {
+        #[resext(alias = Web, user_messages = true, delimiter = ": ")]
+        enum WebErrors {
+            Http(usize),
+            #[resext(user_facing, display = "this page doesn't exist")]
+            NotFound,
+        }
+
+        let err = Err::<(), _>(500usize)
+            .context("Failed to query upstream")
+            .user_context("Something went wrong")
+            .unwrap_err();
+
+        assert_eq!(err.user_message().to_string(), "Something went wrong");
+        assert_eq!(
+            err.to_string(),
+            "Failed to query upstream: Something went wrong\nError: 500"
+        );
+
+        let mut err = WebErr::from(WebErrors::NotFound);
+
+        assert_eq!(err.user_message().to_string(), "this page doesn't exist");
+
+        err.push_user_context("Could not open /docs");
+
+        assert_eq!(
+            err.user_message().to_string(),
+            "Could not open /docs\nthis page doesn't exist"
+        );
+        assert!(WebErr::from(404usize).user_message().is_empty());
+    }
+
+    #[test]
+    fn test_locations() {
+        #[resext(alias = Located, locations = true, delimiter = " | ", alloc = true)]
+        enum LocatedErrors {
+            Http(usize),
+        }
+
+        let line = line!() + 2;
+        let err = Err::<(), usize>(500)
+            .context("Failed to fetch page")
+            .with_context(|| "Failed to render dashboard")
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            alloc::format!(
+                "Failed to fetch page at {0}:{1} | Failed to render dashboard at {0}:{2}\nError: 500",
+                file!(),
+                line,
+                line + 1
+            )
+        );
+    }
+
+    /// Every method writing context reports its caller with `locations`.
+    mod locations {
+        use alloc::format;
+        use alloc::string::{String, ToString};
+        use resext::{IntoResExt, OptExt};
+        use resext_macro::resext;
+
+        #[resext(alias = Located, locations = true, user_messages = true)]
+        enum LocatedErrors {
+            Http(usize),
+            Parse(core::num::ParseIntError),
+        }
+
+        /// The rendered error for `msg` added on `line` of this file.
+        fn at(msg: &str, line: u32, source: &str) -> String {
+            format!("{} at {}:{}\nError: {}", msg, file!(), line, source)
+        }
+
+        #[test]
+        fn context() {
+            let line = line!() + 1;
+            let err = Err::<(), usize>(500).context("Failed").unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+        }
+
+        #[test]
+        fn with_context() {
+            let err =
+                Err::<(), usize>(500).with_context(|| "Failed").unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line!() - 2, "500"));
+        }
+
+        #[test]
+        fn with_row_context() {
+            let line = line!() + 1;
+            let err = Err::<(), usize>(500).with_row_context(3).unwrap_err();
+
+            assert_eq!(err.to_string(), at("Record 3", line, "500"));
+        }
+
+        #[test]
+        fn raw_context() {
+            let line = line!() + 2;
+            let err = Err::<(), usize>(500)
+                .raw_context(b"Failed")
+                .unwrap()
+                .unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+        }
+
+        #[test]
+        fn lossy_context() {
+            let err =
+                Err::<(), usize>(500).lossy_context(b"Failed").unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line!() - 2, "500"));
+        }
+
+        #[test]
+        fn or_else_ctx() {
+            let line = line!() + 2;
+            let err = Err::<(), usize>(500)
+                .or_else_ctx(|_| Err::<(), usize>(503))
+                .unwrap_err();
+
+            assert_eq!(
+                err.to_string(),
+                at("recovery attempted after: 500", line, "503")
+            );
+        }
+
+        #[test]
+        fn path_context() {
+            let line = line!() + 2;
+            let err = Err::<(), usize>(500)
+                .path_context("Failed", "/tmp")
+                .unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed: /tmp", line, "500"));
+        }
+
+        #[test]
+        fn user_context() {
+            let line = line!() + 1;
+            let err = Err::<(), usize>(500).user_context("Failed").unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+            assert_eq!(err.user_message().to_string(), "Failed");
+        }
+
+        #[test]
+        fn to_option_ctx() {
+            let mut line = 0;
+            let out = crate::capture_reports(|| {
+                line = line!() + 2;
+                assert_eq!(
+                    Err::<(), usize>(500).to_option_ctx("Failed", true),
+                    None
+                );
+            });
+
+            assert_eq!(out, at("Failed", line, "500") + "\n");
+        }
+
+        #[test]
+        fn or_default_ctx() {
+            let mut line = 0;
+            let out = crate::capture_reports(|| {
+                line = line!() + 2;
+                assert_eq!(
+                    Err::<u16, usize>(500).or_default_ctx("Failed", 80, true),
+                    80
+                );
+            });
+
+            assert_eq!(out, at("Failed", line, "500") + "\n");
+        }
+
+        #[test]
+        fn parse_ctx() {
+            let line = line!() + 1;
+            let err = "x".parse_ctx::<u8>("Failed").unwrap_err();
+            let source = "x".parse::<u8>().unwrap_err().to_string();
+
+            assert_eq!(err.to_string(), at("Failed (as u8)", line, &source));
+        }
+
+        #[test]
+        fn ok_or_context() {
+            let line = line!() + 1;
+            let err = None::<()>.ok_or_context("Failed", 500usize).unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+        }
+
+        #[test]
+        fn ok_or_with_context() {
+            let line = line!() + 2;
+            let err = None::<()>
+                .ok_or_with_context(|| "Failed", 500usize)
+                .unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+        }
+
+        #[test]
+        fn context_into() {
+            let line = line!() + 2;
+            let err = Err::<(), usize>(500)
+                .context_into::<LocatedErr>("Failed")
+                .unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+        }
+
+        #[test]
+        fn ok_or_context_into() {
+            let line = line!() + 2;
+            let err = None::<()>
+                .ok_or_context_into::<LocatedErr, _>("Failed", 500usize)
+                .unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+        }
+
+        #[test]
+        fn ok_or_with_context_into() {
+            let line = line!() + 2;
+            let err = None::<()>
+                .ok_or_with_context_into::<LocatedErr, _, _, _>(
+                    || "Failed",
+                    500usize,
+                )
+                .unwrap_err();
+
+            assert_eq!(err.to_string(), at("Failed", line, "500"));
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        use resext::FieldValue;
+
+        #[resext(alias = Traced, id = true)]
+        enum TracedErrors {
+            #[resext(exit_code = 3)]
+            Http(usize) = 20,
+        }
+
+        let err = TracedErr::new("Failed to fetch page", 500usize);
+        let mut fields = err.fields();
+
+        assert_eq!(fields.next(), Some(("variant", FieldValue::Str("Http"))));
+        assert_eq!(fields.next(), Some(("code", FieldValue::Int(20))));
+        assert_eq!(fields.next(), Some(("exit_code", FieldValue::Int(3))));
+        assert_eq!(
+            fields.next(),
+            Some(("id", FieldValue::UInt(u64::from(err.id()))))
+        );
+        assert_eq!(fields.next(), None);
+    }
+
+    #[test]
+    fn test_spill_storage() {
+        /// Fixed-size arena keeping only what fits.
+        #[derive(Default)]
+        struct FixedArena {
+            buf: [u8; 32],
+            len: usize,
+        }
+
+        impl resext::SpillStorage for FixedArena {
+            fn extend_from_slice(&mut self, bytes: &[u8]) -> usize {
+                let mut len = bytes.len().min(self.buf.len() - self.len);
+
+                while len < bytes.len() && (bytes[len] & 0xC0) == 0x80 {
+                    len -= 1;
+                }
+
+                self.buf[self.len..self.len + len]
+                    .copy_from_slice(&bytes[..len]);
+                self.len += len;
+
+                len
+            }
+
+            fn as_slice(&self) -> &[u8] {
+                &self.buf[..self.len]
+            }
+
+            fn as_mut_slice(&mut self) -> &mut [u8] {
+                &mut self.buf[..self.len]
+            }
+        }
+
+        #[resext(alias = Arena, buf_size = 8, alloc = true, spill = FixedArena)]
+        enum ArenaErrors {
+            Http(usize),
+        }
+
+        let err =
+            Err::<(), usize>(500).context("Failed to fetch page").unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to fetch page\nError: 500");
+
+        let err = Err::<(), usize>(500)
+            .context("Failed to fetch page")
+            .context("Failed to render dashboard")
+            .unwrap_err();
+
+        assert!(err.was_truncated());
+        assert_eq!(
+            err.to_string(),
+            "Failed to fetch page\n - Failed t...\nError: 500"
+        );
+
+        // A character that doesn't fit completely is left out.
+        let err = Err::<(), usize>(500)
+            .context("Failed to fetch page")
+            .context("1234567½")
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to fetch page\n - 1234567...\nError: 500"
+        );
+    }
+
+    #[test]
+    fn test_corrupted_buffer() {
+        /// Storage losing the last byte of its first write.
+        #[derive(Default)]
+        struct DropOnce {
+            buf: alloc::vec::Vec<u8>,
+            dropped: bool,
+        }
+
+        impl resext::SpillStorage for DropOnce {
+            fn extend_from_slice(&mut self, bytes: &[u8]) -> usize {
+                if self.dropped || bytes.is_empty() {
+                    self.buf.extend_from_slice(bytes);
+                } else {
+                    self.dropped = true;
+                    self.buf.extend_from_slice(&bytes[..bytes.len() - 1]);
+                }
+
+                // Claims the whole write, hiding the lost byte.
+                bytes.len()
+            }
+
+            fn as_slice(&self) -> &[u8] {
+                &self.buf
+            }
+
+            fn as_mut_slice(&mut self) -> &mut [u8] {
+                &mut self.buf
+            }
+        }
+
+        #[resext(alias = Corrupt, buf_size = 4, alloc = true, spill = DropOnce, headline = "last_context")]
+        enum CorruptErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(500).context("Over ½").unwrap_err();
+
+        assert_eq!(err.to_string(), "<invalid utf-8>\nError: 500");
+        assert!(err.contexts().eq(["<invalid utf-8>"]));
+
+        let err = Err::<(), usize>(500)
+            .context("Over ½")
+            .context("Failed to fetch page")
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "<invalid utf-8>\nError: 500");
+        assert!(err.contexts().eq(["<invalid utf-8>"]));
+    }
+
+    #[test]
+    fn test_rotate_tail_rounds_to_char_boundary() {
+        use resext::ContextBuf;
+
+        let mut buf = alloc::string::String::from("ab½");
+        ContextBuf::rotate_tail(&mut buf, 3);
+
+        assert_eq!(buf, "ab½");
+
+        ContextBuf::rotate_tail(&mut buf, 2);
+
+        assert_eq!(buf, "½ab");
+    }
+
+    #[test]
+    fn test_ok_or_context() {
+        #[resext(alias = Lookup)]
+        enum LookupErrors {
+            #[resext(display = "not found")]
+            NotFound,
+        }
+
+        let ports = [("http", 80u16)];
+        let find = |name: &str| {
+            ports.iter().find(|(key, _)| *key == name).map(|(_, port)| *port)
+        };
+
+        assert_eq!(
+            find("http")
+                .ok_or_context("Missing port", LookupErrors::NotFound)
+                .unwrap(),
+            80
+        );
+
+        let err = find("ssh")
+            .ok_or_context("Missing port", LookupErrors::NotFound)
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Missing port\nError: not found");
+
+        let mut calls = 0;
+        let res = find("http").ok_or_with_context(
+            || {
+                calls += 1;
+                "unused"
+            },
+            LookupErrors::NotFound,
+        );
+
+        assert!(res.is_ok());
+        assert_eq!(calls, 0);
+
+        let err = find("ssh")
+            .ok_or_with_context(
+                || alloc::format!("Missing port for {}", "ssh"),
+                LookupErrors::NotFound,
+            )
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Missing port for ssh\nError: not found");
+    }
+
+    #[test]
+    fn test_static_context() {
+        use resext::static_context;
+
+        #[resext(alias = Bounded, buf_size = 24, delimiter = " | ")]
+        enum BoundedErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(500)
+            .context(static_context!("Failed to fetch page"))
+            .context(static_context!("Failed to load"))
+            .unwrap_err();
+
+        // Each message fits on its own, the buffer can still run out.
+        assert_eq!(err.to_string(), "Failed to fetch page | F...\nError: 500");
+        assert_eq!(
+            static_context!("Failed to load").as_str(),
+            "Failed to load"
+        );
+    }
+
+    #[test]
+    // The probe's autoref specialization needs both borrows.
+    #[allow(clippy::needless_borrow)]
+    fn test_color_option() {
+        use resext::__private::{
+            ReportColored as _, ReportDisplay, ReportPlain as _, ReportProbe,
+        };
+
+        #[resext(
+            alias = Colored,
+            color = true,
+            origin = "cli",
+            notes = true,
+            help = true,
+            related = true
+        )]
+        enum ColoredErrors {
+            Http(usize),
+        }
+
+        let err = Err::<(), usize>(404)
+            .context("Failed to fetch page")
+            .note("request id: 7f3a")
+            .help("check the URL")
+            .unwrap_err()
+            .add_related(ColoredErr::new("Failed to close", 500usize));
+
+        // The colored report keeps every line below the chain.
+        assert_eq!(
+            alloc::format!("{}", err.render_colored()),
+            "\x1b[2m[cli]\x1b[0m \x1b[36mFailed to fetch page\x1b[0m\n\x1b[1;31mError: \x1b[0m404\nnote: request id: 7f3a\nrelated errors:\n  - [cli] Failed to close\n    Error: 500\nhelp: check the URL"
+        );
+
+        // `Display` is plain no matter where stderr points.
+        assert_eq!(
+            err.to_string(),
+            "[cli] Failed to fetch page\nError: 404\nnote: request id: 7f3a\nrelated errors:\n  - [cli] Failed to close\n    Error: 500\nhelp: check the URL"
+        );
+
+        // Reports pick the colored rendering only for `color = true`.
+        assert!(matches!(
+            (&&ReportProbe(&err)).report_display(),
+            ReportDisplay::Colored(_)
+        ));
+        assert!(matches!(
+            (&&ReportProbe(&crate::ResextErr::new("", 404))).report_display(),
+            ReportDisplay::Plain(_)
+        ));
+
+        // Padding and precision only count visible chars.
+        let padded = alloc::format!("{:<40.8}|", err);
+
+        assert_eq!(padded, alloc::format!("[cli] Fa{}|", " ".repeat(32)));
+    }
+
+    #[test]
+    fn test_interconvert() {
+        mod layers {
+            pub mod app {
+                use resext_macro::resext;
+
+                #[resext(alias = AppRes)]
+                pub enum AppErrors {
+                    Parse(core::num::ParseIntError),
+                    Utf8(core::str::Utf8Error),
+                    Http { status: usize },
+                }
+            }
+
+            pub mod parse {
+                use resext_macro::resext;
+
+                #[resext(alias = ParseRes, interconvert = [super::app::AppErrors])]
+                pub enum ParseErrors {
+                    Int(core::num::ParseIntError),
+                    Utf8 { error: core::str::Utf8Error },
+                }
+            }
+        }
+
+        use layers::{app, parse};
+
+        let err =
+            "x".parse::<u8>().map_err(parse::ParseErrors::from).unwrap_err();
+
+        assert!(matches!(app::AppErrors::from(err), app::AppErrors::Parse(_)));
+
+        let err = core::str::from_utf8(&[0, 158])
+            .map_err(parse::ParseErrors::from)
+            .unwrap_err();
+        let err: app::AppErrors = err.into();
+
+        assert_eq!(err.variant_name(), "Utf8");
+    }
+
+    #[test]
+    fn test_ensure() {
+        use resext::ensure;
+
+        #[resext(alias = Check, buf_size = 32)]
+        enum CheckErrors {
+            Http(usize),
+            #[resext(display = "limit exceeded")]
+            Limit,
+        }
+
+        fn fetch(page: usize) -> Check<usize> {
+            ensure!(
+                page < 10,
+                CheckErrors::Limit,
+                "Page {} is out of range",
+                page
+            );
+            ensure!(page != 4, 404usize, "Failed to fetch page {}", page);
+
+            Ok(page)
+        }
+
+        assert_eq!(fetch(3).unwrap(), 3);
+        assert_eq!(
+            fetch(12).unwrap_err().to_string(),
+            "Page 12 is out of range\nError: limit exceeded"
+        );
+        assert_eq!(
+            fetch(4).unwrap_err().to_string(),
+            "Failed to fetch page 4\nError: 404"
+        );
+    }
+
+    #[test]
+    fn test_bail() {
+        use resext::bail;
+
+        #[resext(alias = Job, buf_size = 32)]
+        enum JobErrors {
+            Http(usize),
+            #[resext(display = "timed out")]
+            Timeout,
+        }
+
+        fn run(secs: u64) -> Job<()> {
+            match secs {
+                0 => bail!(JobErrors::Timeout),
+                1..=30 => Ok(()),
+                _ => bail!(JobErrors::Timeout, "Gave up after {}s", secs),
+            }
+        }
+
+        assert!(run(5).is_ok());
+        assert_eq!(run(0).unwrap_err().to_string(), "Error: timed out");
+        assert_eq!(
+            run(60).unwrap_err().to_string(),
+            "Gave up after 60s\nError: timed out"
+        );
+    }
+
+    #[test]
+    fn test_wrapper_name() {
+        // `ResultErr` would be the default wrapper name, and the alias shadows
+        // `Result` in this scope.
+        #[allow(dead_code)]
+        struct ResultErr;
+
+        #[resext(alias = Result, wrapper = Failure)]
+        enum Errors {
+            Http(usize),
+        }
+
+        fn fetch() -> Result<()> {
+            Err(503usize).context("Failed to fetch page")
+        }
+
+        let err: Failure = fetch().unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to fetch page\nError: 503");
+        assert_eq!(Failure::new("", 404usize).to_string(), "Error: 404");
     }
 }
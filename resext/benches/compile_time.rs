@@ -0,0 +1,88 @@
+//! Compile-time benchmark for `#[resext]` expansions.
+//!
+//! Generates a throwaway crate with many error enums and times a clean
+//! `cargo check` of it, which is dominated by macro expansion and type
+//! checking of the generated code.
+//!
+//! ```sh
+//! cargo bench -p resext --bench compile_time
+//! RESEXT_BENCH_ENUMS=200 cargo bench -p resext --bench compile_time
+//! ```
+
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+fn main() {
+    let enums: usize = std::env::var("RESEXT_BENCH_ENUMS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(50);
+
+    let resext = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join("resext-compile-bench");
+    let src = dir.join("src");
+    std::fs::create_dir_all(&src).expect("failed to create bench crate");
+
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"resext-compile-bench\"\nversion = \"0.0.0\"\n\
+             edition = \"2024\"\n\n[dependencies]\nresext = {{ path = {:?} }}\n\n\
+             [workspace]\n",
+            resext
+        ),
+    )
+    .expect("failed to write Cargo.toml");
+
+    let mut lib = String::new();
+
+    for i in 0..enums {
+        let _ = write!(
+            lib,
+            "pub mod m{i} {{\n\
+                 use resext::{{ctx, resext}};\n\n\
+                 #[resext(alloc = true)]\n\
+                 pub enum E{i} {{\n\
+                     Io(std::io::Error),\n\
+                     Parse(std::num::ParseIntError),\n\
+                     Utf8 {{ error: std::str::Utf8Error }},\n\
+                 }}\n\n\
+                 pub fn f(s: &str) -> Res<i32> {{\n\
+                     s.parse::<i32>().context(ctx!(\"Failed to parse {{}}\", s))\n\
+                 }}\n\
+             }}\n\n"
+        );
+    }
+
+    std::fs::write(src.join("lib.rs"), &lib).expect("failed to write lib.rs");
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let target = dir.join("target");
+
+    // Check dependencies once, then rewrite `lib.rs` to bump its mtime so
+    // only the generated crate is timed.
+    run(&cargo, &dir, &target);
+    std::fs::write(src.join("lib.rs"), &lib).expect("failed to write lib.rs");
+
+    let start = Instant::now();
+    run(&cargo, &dir, &target);
+
+    println!(
+        "cargo check of {} #[resext] enums: {:.2?}",
+        enums,
+        start.elapsed()
+    );
+}
+
+fn run(cargo: &str, dir: &Path, target: &Path) {
+    let status = Command::new(cargo)
+        .args(["check", "--quiet"])
+        .env("CARGO_TARGET_DIR", target)
+        .current_dir(dir)
+        .status()
+        .expect("failed to run cargo");
+
+    assert!(status.success(), "bench crate failed to compile");
+}
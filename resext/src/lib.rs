@@ -55,6 +55,124 @@
 //! - `alias` - Custom type alias name which is used for getting the names for other items generated by the proc-macro (default: `Res`)
 //! - `buf_size` - Size for the context message byte buffer (default: 64)
 //! - `alloc` Enable heap-spilling if context exceeds `buf_size`
+//! - `storage` - Type implementing [`ContextBuf`] used to store context messages instead of the
+//!   built-in buffers, e.g. `String`. Can't be combined with `buf_size` or `alloc`
+//! - `formatter` - Type implementing [`ErrorFormatter`] used for the wrapper's `Display` output
+//! - `format` - Template for the wrapper's `Display` output (see below)
+//! - `headline` - `"last_context"` shows the most recent (outermost) context as the first line,
+//!   with earlier contexts beneath it (default: `"first_context"`)
+//! - `non_exhaustive` - Add `#[non_exhaustive]` to the enum so new variants aren't a breaking
+//!   change for downstream crates (default: false)
+//! - `opaque` - Generate the enum inside a private module and keep the wrapper's `source` field
+//!   private. A fieldless `{alias}Kind` enum and a `kind()` accessor on the wrapper are generated
+//!   instead, so libraries can change their internal variants freely (default: false)
+//! - `origin` - Component name rendered as `[name] ` before the error and returned by the
+//!   wrapper's `origin()`, so operators can tell which crate of a workspace produced an error.
+//!   `origin = true` uses the package name of the crate defining the enum (default: none)
+//! - `id` - Attach a per-occurrence ID to every error, shown as `error id: 7f3a` after it and
+//!   returned by the wrapper's `id()`, for correlating user-visible errors with logs. `id = true`
+//!   uses a process-wide counter, `id = path::to::function` any `fn() -> u32` (default: none)
+//! - `related` - Let the wrapper hold secondary errors attached with `add_related()`, rendered as
+//!   an indented `related errors:` list below the main chain. Requires the `alloc` feature
+//!   (default: false)
+//! - `attachments` - Let errors carry typed values, e.g. the path or request ID involved, attached
+//!   with `.attach(value)` and retrieved with `get_attached::<T>()` for programmatic handling.
+//!   One value per type, requires the `alloc` feature (default: false)
+//! - `minimal` - Only generate the enum's `Display`, `Error`, `From` and accessor impls, like a
+//!   `thiserror` derive, for types that don't need context chains. No wrapper, traits or alias
+//!   are generated, so wrapper options can't be combined with it (default: false)
+//!
+//! ## Display Templates
+//!
+//! `format` describes the whole error layout in one string, compiled at macro expansion time:
+//!
+//! - `{contexts}` - Context messages, `{contexts:SEP}` also sets the delimiter to `SEP`
+//! - `{source}` - The source error
+//! - `{variant}` - Name of the source error's variant
+//! - `{prefix}` / `{suffix}` - Values of the `prefix` and `suffix` options
+//! - `{newline}` - Line break, only emitted when the error has context
+//! - `{{` / `}}` - Literal braces
+//!
+//! ```rust
+//! # use resext::resext;
+//! #[resext(format = "{contexts:\n - }{newline}caused by {source}")]
+//! enum AppError {
+//!     Parse(std::num::ParseIntError),
+//! }
+//!
+//! let err = "x".parse::<u8>().context("Failed to parse port").unwrap_err();
+//! assert_eq!(err.to_string(), "Failed to parse port\ncaused by invalid digit found in string");
+//! ```
+//!
+//! ## Variant Display
+//!
+//! `#[resext(display = "...")]` on a variant replaces its `Display` arm, which otherwise forwards
+//! to the inner error. Like `thiserror`, `{0}` refers to a tuple field and `{name}` to a named one:
+//!
+//! ```rust
+//! # use resext::resext;
+//! #[resext]
+//! enum AppError {
+//!     #[resext(display = "failed to fetch {0} from upstream")]
+//!     Fetch(String),
+//!     #[resext(display = "upstream unavailable")]
+//!     Unavailable,
+//! }
+//!
+//! assert_eq!(
+//!     AppError::Fetch("/users".into()).to_string(),
+//!     "failed to fetch /users from upstream"
+//! );
+//! ```
+//!
+//! `#[resext(exit_code = "EX_IOERR")]` sets the variant's `exit_code()`, used by `.or_exit_auto()`.
+//! Strings name a constant of [`exitcode`], integers are used as they are (default: 1). Unix
+//! parents only see the low 8 bits, Windows keeps the whole `i32`. Literals whose low 8 bits are
+//! 0, like 0 or 256, are rejected, since they report success on Unix:
+//!
+//! ```rust,compile_fail
+//! use resext::resext;
+//!
+//! #[resext]
+//! enum AppError {
+//!     #[resext(exit_code = 256)]
+//!     Io(std::io::Error),
+//! }
+//! ```
+//!
+//! `#[resext(skip_from)]` leaves out the `From` impls of a variant, for variants wrapping the same
+//! type as another one, e.g. `ReadIo(std::io::Error)` and `WriteIo(std::io::Error)`. Such variants
+//! are constructed manually, e.g. with `.map_err(AppError::WriteIo)`.
+//!
+//! `#[resext(skip_display)]` displays a variant as its name instead of its payload, with the size
+//! as `<N bytes>` for byte-like payloads (`AsRef<[u8]>`), e.g. `Body: <1048576 bytes>` for a
+//! `Body(Vec<u8>)` holding a response body.
+//!
+//! `#[resext(debug_summary = 64)]` cuts the variant's `Debug` output in the wrapper's `Debug` to
+//! 64 chars, followed by its total length, e.g. `Body("{\"items\": [{\"id\"… <1048588 chars>`.
+//!
+//! `#[resext(docs_url = "https://docs.example.com/errors/E042")]` links a variant to its
+//! documentation, returned by `docs_url()` on the enum and wrapper. `show_docs_url = true` on the
+//! enum also shows it as a `see: ` line below the error.
+//!
+//! The enum can be generic. Its bounds and `where` clause must make every variant `Display` and
+//! `Debug`, and its type and const parameters are appended to the alias and wrapper, e.g.
+//! `Res<T, E>` and `ResErr<E>`, with the same defaults. Variants wrapping a parameter get no
+//! `From` impls, and lifetime parameters aren't supported. Type parameters are implicitly
+//! `'static` in the generated items, as sources are handed out as `dyn Error + 'static`.
+//!
+//! ```rust
+//! # use resext::resext;
+//! #[resext]
+//! enum MyError<E: std::error::Error> {
+//!     Inner(E),
+//!     Io(std::io::Error),
+//! }
+//!
+//! let err: ResErr<std::fmt::Error> = MyError::Inner(std::fmt::Error).into();
+//!
+//! assert_eq!(err.to_string(), "Error: an error occurred when formatting an argument");
+//! ```
 //!
 //! ## `.context()` Method
 //!
@@ -74,6 +192,108 @@
 //! # }
 //! ```
 //!
+//! ## `.or_else_ctx()` Method
+//!
+//! Attempt a recovery from an error. If the recovery fails too, `recovery attempted after: `
+//! followed by the original error's contexts and source is added as context to the new error.
+//!
+//! The method is defined on a second generated trait, `{alias}Ops` (`ResOps` by default).
+//!
+//! ```rust
+//! # use resext::resext;
+//! # #[resext] enum Error { Io(std::io::Error) }
+//! # fn doctest() -> Res<Vec<u8>> {
+//! let config = std::fs::read("config.toml")
+//!     .context("Failed to read config")
+//!     .or_else_ctx(|_| std::fs::read("default.toml").context("Failed to read defaults"))?;
+//! # Ok(config)
+//! # }
+//! ```
+//!
+//! ## `.on_err()` / `.on_ok()` Methods
+//!
+//! Passthrough hooks on `{alias}Ops` that call a closure with the error or value and return the
+//! Result unchanged, for metrics and logging without breaking a `?` chain.
+//!
+//! ```rust
+//! # use resext::resext;
+//! # #[resext] enum Error { Io(std::io::Error) }
+//! # fn doctest() -> Res<Vec<u8>> {
+//! # let mut failures = 0;
+//! let config = std::fs::read("config.toml")
+//!     .context("Failed to read config")
+//!     .on_err(|_| failures += 1)?;
+//! # Ok(config)
+//! # }
+//! ```
+//!
+//! ## Error Codes
+//!
+//! Explicit discriminants double as numeric error codes, retrievable with `.code()` on both
+//! the enum and the wrapper. A `#[repr(i32)]` is added unless the enum already has a `repr`.
+//!
+//! ```rust
+//! # use resext::resext;
+//! #[resext]
+//! enum CliError {
+//!     Io(std::io::Error) = 74,
+//!     Parse(std::num::ParseIntError) = 65,
+//! }
+//!
+//! let err: ResErr = "x".parse::<i32>().context("Failed to parse port").unwrap_err();
+//! assert_eq!(err.code(), 65);
+//! ```
+//!
+//! ## Source Chain
+//!
+//! The wrapper and the enum both implement `Error`, so generic reporters that walk `source()`
+//! chains can render ResExt errors. Each message appears once in the chain: the wrapper already
+//! prints the enum, so its `source()` skips to the sources of the error wrapped by the variant,
+//! e.g. what an `io::Error` was caused by. Variants with a custom `display` or `skip_display`
+//! don't print the wrapped error, so for them the chain starts at that error. On nightly,
+//! `std::error::Report::new(err).pretty(true)` works out of the box.
+//!
+//! ---
+//!
+//! # Cargo Features
+//!
+//! Code generated by `#[resext]` uses runtime items from this crate, so `no_std` projects
+//! should disable default features:
+//!
+//! - `std` (default) - Helpers that need the standard library, e.g. [`Normalize`] and `.or_exit()`.
+//!   Implies `alloc`
+//! - `alloc` - Required by the `alloc = true` attribute option (heap-spilling context buffers),
+//!   [`Errors`] and [`ResWithWarnings`]. Reporting goes through [`ErrorSink`], which any
+//!   `core::fmt::Write` implements, so error aggregation and reporting don't need `std`
+//! - `test-no-exit` - Make `.or_exit()` panic with the formatted error instead of exiting, for
+//!   asserting on fatal paths in tests. Implies `std`
+//! - `reqwest` - `ResponseExt::error_for_status_ctx()` for turning HTTP failure statuses into
+//!   typed errors. Implies `std`
+//! - `serde_json` / `toml` - `json::from_str_ctx()` and `toml::from_str_ctx()` for parsing config
+//!   files with the path and line/column as context. Imply `std`
+//! - `clap` - `ToClapError` for printing errors in clap's `error:` style. Implies `std`
+//! - `anyhow` - [`Classify`] for converting `anyhow::Error`s into generated enums that opt in
+//!   with `classify = true`. Implies `std`
+//! - `error-stack` - `into_error_stack()` on generated wrappers, turning them into
+//!   `error_stack::Report`s with context messages as attachments. Implies `alloc`
+//! - `web` - `Console` sink writing to the browser's `console.error`, used by `.or_exit()` and
+//!   `#[resext::main]` on `wasm32` instead of stderr. Implies `alloc`
+//! - `diagnostics` - `.stats()` on generated wrappers, counting attached contexts, truncations and
+//!   heap spills per error to help tune `buf_size`. Meant for development builds
+//! - `backtrace` - Capture a backtrace when the wrapper of an enum with `backtrace = true` is
+//!   created, if `RUST_BACKTRACE` enables it, and print it in the `{:?}` output. Implies `std`
+//! - `serde` - `serde::Serialize` for wrappers of enums with `serde = true`, as the variant name,
+//!   the source error's `Display` output and the context messages, for structured logs
+//! - `tui` - `to_text()` on wrappers of enums with `tui = true`, rendering them as styled
+//!   `ratatui` text for error panels. Implies `alloc`
+//! - `async` - `.context()` on futures resolving to Results, through a `Future{alias}Ext` trait
+//!   generated for enums with `futures = true`, so context can be added before `.await`
+//!
+//! ```toml
+//! [dependencies]
+//! resext = { version = "1.3.3", default-features = false, features = ["alloc"] }
+//! ```
+//!
 //! ---
 //!
 //! # Error Display Format
@@ -170,95 +390,50 @@
 //! }
 //! ```
 //!
-pub use resext_macro::resext;
-
-#[doc(hidden)]
-pub struct Writer<W: core::fmt::Write + ?Sized>(pub W);
+#![no_std]
 
-impl<W: core::fmt::Write + ?Sized> core::fmt::Write for Writer<W> {
-    #[inline]
-    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        self.0.write_str(s)
-    }
-}
+pub use resext_core::*;
+pub use resext_macro::{main, resext, test_res};
 
-/// Creates a lazily-evaluated context message for use with `.context()`.
+/// Extension traits and macros of the runtime crate, for a single glob import.
 ///
-/// Takes a format string and optional arguments identical to `write!` or `format_args!`,
-/// but only evaluates and writes the message if an error actually occurs.
+/// The traits generated by `#[resext]` live in the crate defining the enum,
+/// so they are imported from there.
 ///
 /// # Examples
 ///
 /// ```rust
-/// use resext::resext;
-/// use resext::ctx;
+/// use resext::prelude::*;
 ///
 /// #[resext]
-/// enum FileError {
+/// enum AppError {
 ///     Io(std::io::Error),
-///     Utf8(std::string::FromUtf8Error),
 /// }
 ///
-/// fn read_file(path: &str) -> Res<String> {
-///     let content = std::fs::read(path)
-///         .context(ctx!("Failed to read file: {}", path))?;
-///
-///     String::from_utf8(content)
-///         .context(ctx!("Failed to parse file: {}", path))
+/// fn port(value: Option<u16>) -> Result<u16, ResErr> {
+///     value.ok_or_context_into::<ResErr, _>("Missing port", std::io::Error::other("unset"))
 /// }
-/// ```
 ///
-/// Static messages without arguments are also supported, but it is encouraged
-/// to use raw `&str`:
+/// let err = port(None).unwrap_err();
 ///
-/// ```rust
-/// # use resext::resext;
-/// # use resext::ctx;
-/// # #[resext] enum Err { Io(std::io::Error) }
-/// # fn doctest() -> Res<()> {
-/// std::fs::read_to_string("config.toml")
-///     .context(ctx!("Failed to read config"))?;
-/// # Ok(())
-/// # }
+/// assert_eq!(err.to_string(), "Missing port\nError: unset");
 /// ```
-///
-/// # Note
-///
-/// This macro must be used with `.context()` method generated by `#[resext]`.
-/// It cannot be used standalone.
-#[macro_export]
-macro_rules! ctx {
-    ($fmt:expr, $($args:tt)*) => {
-        {
-            |w, d, mp, ms| {
-                use core::fmt::Write;
-
-                let mut w = $crate::Writer(w);
-
-                let _ = w.write_str(d);
-                let _ = w.write_str(mp);
-                let _ = write!(w, $fmt, $($args)*);
-                let _ = w.write_str(ms);
-
-                w.0
-            }
-        }
+pub mod prelude {
+    pub use crate::{
+        ErrorSink, FromCtx, IntoResExt, OptExt, bail, ctx, ensure, resext,
+        static_context,
     };
 
-    ($fmt:expr) => {
-        {
-            |w, d, mp, ms| {
-                use core::fmt::Write;
-
-                let mut w = $crate::Writer(w);
-
-                let _ = w.write_str(d);
-                let _ = w.write_str(mp);
-                let _ = write!(w, $fmt);
-                let _ = w.write_str(ms);
-
-                w.0
-            }
-        }
-    };
+    #[cfg(feature = "anyhow")]
+    pub use crate::Classify;
+    #[cfg(feature = "alloc")]
+    pub use crate::CollectErrors;
+    #[cfg(feature = "std")]
+    pub use crate::Normalize;
+    #[cfg(feature = "reqwest")]
+    pub use crate::ResponseExt;
+    #[cfg(feature = "clap")]
+    pub use crate::ToClapError;
+    #[cfg(feature = "std")]
+    pub use crate::testing::expect_exit;
 }
@@ -0,0 +1,281 @@
+//! Tests exercising the crate's `unsafe` code and shared state, meant to run
+//! under Miri:
+//!
+//! ```sh
+//! cargo +nightly miri test -p resext --features unsafe-audit --test unsafe_audit
+//! ```
+
+#![cfg(feature = "unsafe-audit")]
+
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::thread;
+
+use resext::__private::{InlineBuf, SpillBuf};
+use resext::ContextBuf;
+use resext::resext;
+
+/// Fewer iterations under Miri, which is orders of magnitude slower.
+const ROUNDS: usize = if cfg!(miri) { 4 } else { 256 };
+
+/// Serializes the tests sharing the process-wide prepared fatal report.
+#[cfg(unix)]
+static FATAL: Mutex<()> = Mutex::new(());
+
+#[test]
+#[cfg(unix)]
+fn prepare_fatal_from_many_threads() {
+    let _fatal = FATAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            thread::spawn(move || {
+                for round in 0..ROUNDS {
+                    let msg = format!("thread {} failed in round {}", i, round);
+                    let len = resext::prepare_fatal(&msg).unwrap();
+
+                    assert_eq!(len, msg.len() + 1);
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn prepared_report_is_never_torn() {
+    let _fatal = FATAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    resext::prepare_fatal(&"0").unwrap();
+
+    let writers: Vec<_> = (0..2)
+        .map(|i| {
+            thread::spawn(move || {
+                for round in 0..ROUNDS {
+                    // Different lengths, so a torn read would mix them up.
+                    let msg = format!("{}{}", i, "x".repeat(round % 40));
+                    resext::prepare_fatal(&msg).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    let mut report = [0; 1024];
+
+    for _ in 0..ROUNDS {
+        if let Some(len) = resext::__private::read_prepared(&mut report) {
+            let report = std::str::from_utf8(&report[..len]).unwrap();
+            let body = report.strip_suffix('\n').unwrap();
+
+            assert!(body.starts_with(['0', '1']));
+            assert!(body[1..].bytes().all(|b| b == b'x'));
+        }
+    }
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn prepare_fatal_truncates_at_char_boundaries() {
+    let _fatal = FATAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // 2-byte chars never fit the 1 KiB buffer exactly with the newline.
+    let msg = "é".repeat(600);
+
+    assert_eq!(resext::prepare_fatal(&msg), Err(1201));
+    assert_eq!(resext::prepare_fatal(&"fits"), Ok(5));
+}
+
+#[test]
+fn fmt_into_every_buffer_size() {
+    #[resext(alias = Net, delimiter = " | ")]
+    enum NetErrors {
+        Http(usize),
+    }
+
+    let err = Err::<(), usize>(503)
+        .context("Zeitüberschreitung 💥 beim Abruf")
+        .unwrap_err();
+    let full = err.to_string();
+
+    for size in 0..=full.len() + 1 {
+        let mut buf = vec![0u8; size];
+        let written = match err.fmt_into(&mut buf) {
+            Ok(written) => {
+                assert_eq!(written, full.len());
+                written
+            }
+            Err(needed) => {
+                assert_eq!(needed, full.len());
+                (0..=size)
+                    .rev()
+                    .find(|&end| full.is_char_boundary(end))
+                    .unwrap()
+            }
+        };
+
+        let out = std::str::from_utf8(&buf[..written]).unwrap();
+
+        assert!(full.starts_with(out));
+    }
+}
+
+#[test]
+fn inline_buf_fills_to_exact_capacity() {
+    let mut buf = InlineBuf::<8, u8>::new();
+
+    buf.write_str("1234").unwrap();
+    buf.write_str("5678").unwrap();
+
+    assert_eq!(buf.as_str(), "12345678");
+    assert!(!buf.is_truncated());
+
+    // A full buffer refuses even a single byte.
+    buf.write_str("9").unwrap();
+
+    assert_eq!(buf.as_str(), "12345678");
+    assert!(buf.is_truncated());
+}
+
+#[test]
+fn inline_buf_cuts_multi_byte_chars_at_the_edge() {
+    // 2-, 3- and 4-byte chars, each one byte short of fitting.
+    for (text, kept) in [("abcé", "abc"), ("ab€", "ab"), ("a💥", "a")] {
+        let mut buf = InlineBuf::<4, u8>::new();
+        buf.write_str(text).unwrap();
+
+        assert_eq!(buf.as_str(), kept);
+        assert!(buf.is_truncated());
+    }
+
+    // And exactly fitting.
+    for text in ["abé", "a€", "💥"] {
+        let mut buf = InlineBuf::<4, u8>::new();
+        buf.write_str(text).unwrap();
+
+        assert_eq!(buf.as_str(), text);
+        assert!(!buf.is_truncated());
+    }
+}
+
+#[test]
+fn rotate_tail_rounds_up_to_a_char_boundary() {
+    fn rotated<B: ContextBuf>(mut buf: B) -> String {
+        buf.write_str("ab💥cd").unwrap();
+
+        // Offsets 3 to 5 are inside "💥", which stays in front of the tail.
+        buf.rotate_tail(3);
+        buf.as_str().to_owned()
+    }
+
+    assert_eq!(rotated(InlineBuf::<16, u8>::new()), "cdab💥");
+    assert_eq!(rotated(SpillBuf::<4, u8>::new()), "cdab💥");
+    assert_eq!(rotated(String::new()), "cdab💥");
+}
+
+/// Aborts with the prepared report while other threads keep replacing it.
+///
+/// Miri can't spawn processes, so the child runs natively. The
+/// interleavings Miri could explore are covered by the tests above
+/// exercising `read_prepared()` directly.
+#[test]
+#[cfg(unix)]
+#[cfg_attr(miri, ignore)]
+fn abort_while_preparing_never_tears_the_report() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    const CHILD: &str = "RESEXT_ABORT_CHILD";
+
+    if std::env::var_os(CHILD).is_some() {
+        resext::prepare_fatal(&"0").unwrap();
+
+        for i in 1..=2 {
+            thread::spawn(move || {
+                for round in 0.. {
+                    let msg = format!("{}{}", i, "x".repeat(round % 40));
+                    resext::prepare_fatal(&msg).unwrap();
+                }
+            });
+        }
+
+        thread::sleep(std::time::Duration::from_millis(10));
+        resext::abort_with_prepared();
+    }
+
+    for _ in 0..8 {
+        let out = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "abort_while_preparing_never_tears_the_report",
+                "--nocapture",
+            ])
+            .env(CHILD, "1")
+            .output()
+            .unwrap();
+
+        assert_eq!(out.status.signal(), Some(6));
+
+        // Nothing if the abort interrupted a write, else one whole report.
+        let stderr = String::from_utf8(out.stderr).unwrap();
+
+        if let Some(body) = stderr.strip_suffix('\n') {
+            assert!(body.starts_with(['0', '1', '2']), "{:?}", stderr);
+            assert!(body[1..].bytes().all(|b| b == b'x'), "{:?}", stderr);
+        } else {
+            assert!(stderr.is_empty(), "{:?}", stderr);
+        }
+    }
+}
+
+#[test]
+fn spill_buf_rotates_after_spilling() {
+    #[resext(alias = Spilled, buf_size = 4, alloc = true, headline = "last_context")]
+    enum SpilledErrors {
+        Http(usize),
+    }
+
+    let mut res = Err::<(), usize>(500).context("ä");
+
+    for i in 0..16 {
+        res = res.context(if i % 2 == 0 { "💥" } else { "ö" });
+    }
+
+    let err = res.unwrap_err();
+    let first = err.contexts().next().unwrap();
+
+    assert_eq!(first, "ö");
+    assert_eq!(err.contexts().next_back(), Some("ä"));
+    assert!(!err.was_truncated());
+}
+
+#[test]
+fn error_ids_are_unique_across_threads() {
+    #[resext(alias = Tracked, id = true)]
+    enum TrackedErrors {
+        Http(usize),
+    }
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                (0..ROUNDS)
+                    .map(|_| TrackedErr::from(500usize).id())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let mut ids: Vec<_> =
+        threads.into_iter().flat_map(|thread| thread.join().unwrap()).collect();
+    let count = ids.len();
+
+    ids.sort_unstable();
+    ids.dedup();
+
+    assert_eq!(ids.len(), count);
+}